@@ -0,0 +1,561 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use serde::{Deserialize, Serialize};
+
+/// ファイルを開くことができるアプリケーション（freedesktop のデスクトップエントリ）
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DesktopApplication {
+    /// デスクトップファイルID（例: "org.gnome.eog.desktop"）
+    pub id: String,
+    pub name: String,
+    pub icon: Option<String>,
+    /// このファイルの種類の既定のアプリケーションかどうか
+    pub is_default: bool,
+}
+
+/// 解析したデスクトップエントリ
+#[derive(Clone, Debug, PartialEq)]
+struct DesktopEntry {
+    id: String,
+    name: String,
+    icon: Option<String>,
+    exec: String,
+    working_dir: Option<PathBuf>,
+    mime_types: Vec<String>,
+    source: PathBuf,
+}
+
+/// 既定のアプリケーションで開く・アプリケーションを指定して開く・ファイルマネージャーで表示する処理を担当するサービスクラス
+///
+/// アプリケーションの一覧は XDG のデータディレクトリにあるデスクトップエントリから、
+/// 既定のアプリケーションは mimeapps.list から読み取る。
+pub struct ApplicationService {
+    data_dirs: Vec<PathBuf>,
+    config_dirs: Vec<PathBuf>,
+}
+
+impl ApplicationService {
+    /// 検索するデータディレクトリと設定ディレクトリを指定してインスタンスを作成（優先度の高い順）
+    pub fn new(data_dirs: Vec<PathBuf>, config_dirs: Vec<PathBuf>) -> Self {
+        Self { data_dirs, config_dirs }
+    }
+
+    /// XDG 環境変数からディレクトリを決めてインスタンスを作成
+    pub fn from_environment() -> Self {
+        let home = env::var_os("HOME").map(PathBuf::from);
+        let xdg_dir = |name: &str, fallback: &str| {
+            env::var_os(name)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| home.as_ref().map(|home| home.join(fallback)))
+        };
+        let xdg_dirs = |name: &str, fallback: &str| -> Vec<PathBuf> {
+            let value = env::var(name).ok().filter(|value| !value.is_empty());
+            value
+                .as_deref()
+                .unwrap_or(fallback)
+                .split(':')
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+                .collect()
+        };
+
+        let mut data_dirs: Vec<PathBuf> = xdg_dir("XDG_DATA_HOME", ".local/share").into_iter().collect();
+        data_dirs.extend(xdg_dirs("XDG_DATA_DIRS", "/usr/local/share:/usr/share"));
+        let mut config_dirs: Vec<PathBuf> = xdg_dir("XDG_CONFIG_HOME", ".config").into_iter().collect();
+        config_dirs.extend(xdg_dirs("XDG_CONFIG_DIRS", "/etc/xdg"));
+        Self::new(data_dirs, config_dirs)
+    }
+
+    /// 既定のアプリケーションでファイル・フォルダを開く
+    ///
+    /// 実行権限のあるファイルやデスクトップエントリは、開くとプログラムが起動するため開かない。
+    pub fn open_default(path: &Path) -> Result<(), String> {
+        ensure_not_launchable(path)?;
+        tauri_plugin_opener::open_path(path, None::<&str>)
+            .map_err(|e| format!("ファイルを開けませんでした: {}", e))
+    }
+
+    /// ファイルマネージャーで親フォルダを開き、項目を選択状態にする
+    pub fn reveal(path: &Path) -> Result<(), String> {
+        tauri_plugin_opener::reveal_item_in_dir(path)
+            .map_err(|e| format!("ファイルマネージャーで表示できませんでした: {}", e))
+    }
+
+    /// ファイルを開けるアプリケーションの一覧（既定のアプリケーションが先頭、残りは名前順）
+    pub fn applications_for(&self, path: &Path) -> Vec<DesktopApplication> {
+        let mime_type = mime_type_of(path);
+        let defaults = self.default_application_ids(&mime_type);
+
+        let mut applications: Vec<DesktopApplication> = self
+            .desktop_entries()
+            .into_values()
+            .filter(|entry| entry.mime_types.iter().any(|pattern| mime_matches(pattern, &mime_type)))
+            .map(|entry| DesktopApplication {
+                is_default: defaults.first() == Some(&entry.id),
+                id: entry.id,
+                name: entry.name,
+                icon: entry.icon,
+            })
+            .collect();
+        applications.sort_by(|a, b| b.is_default.cmp(&a.is_default).then_with(|| a.name.cmp(&b.name)));
+        applications
+    }
+
+    /// デスクトップファイルIDで指定したアプリケーションでファイルを開く
+    pub fn open_with(&self, path: &Path, application_id: &str) -> Result<(), String> {
+        let entry = self
+            .desktop_entries()
+            .remove(application_id)
+            .ok_or_else(|| format!("アプリケーションが見つかりません: {}", application_id))?;
+        let args = expand_exec(&entry, path)?;
+        let (program, args) = args
+            .split_first()
+            .ok_or_else(|| "アプリケーションの起動コマンドが空です".to_string())?;
+
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if let Some(working_dir) = entry.working_dir.filter(|dir| dir.is_dir()) {
+            command.current_dir(working_dir);
+        }
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("アプリケーションの起動に失敗しました: {}", e))?;
+        // 終了したプロセスがゾンビとして残らないよう、別スレッドで待つ
+        std::thread::spawn(move || {
+            let _ = child.wait();
+        });
+        Ok(())
+    }
+
+    /// すべてのデータディレクトリからデスクトップエントリを集める（同じIDは優先度の高い方を使う）
+    fn desktop_entries(&self) -> HashMap<String, DesktopEntry> {
+        let mut entries = HashMap::new();
+        for data_dir in self.data_dirs.iter().rev() {
+            let applications_dir = data_dir.join("applications");
+            let mut files = Vec::new();
+            collect_desktop_files(&applications_dir, &mut files);
+            for file in files {
+                let Some(id) = desktop_file_id(&applications_dir, &file) else {
+                    continue;
+                };
+                // 上位のディレクトリで隠されたエントリは下位のものも消す
+                match fs::read_to_string(&file).ok().and_then(|content| parse_desktop_entry(&id, &file, &content)) {
+                    Some(entry) => entries.insert(id, entry),
+                    None => entries.remove(&id),
+                };
+            }
+        }
+        entries
+    }
+
+    /// mimeapps.list の [Default Applications] から既定のアプリケーションIDを優先度順に取得
+    fn default_application_ids(&self, mime_type: &str) -> Vec<String> {
+        let lists = self
+            .config_dirs
+            .iter()
+            .map(|dir| dir.join("mimeapps.list"))
+            .chain(self.data_dirs.iter().flat_map(|dir| {
+                let applications = dir.join("applications");
+                [applications.join("mimeapps.list"), applications.join("defaults.list")]
+            }));
+
+        let mut ids = Vec::new();
+        for list in lists {
+            let Ok(content) = fs::read_to_string(&list) else {
+                continue;
+            };
+            if let Some(value) = ini_value(&content, "Default Applications", mime_type) {
+                ids.extend(split_list(&value));
+            }
+        }
+        ids
+    }
+}
+
+/// 開くとプログラムが起動するファイル（実行権限のあるファイル・.desktop）ならエラー
+fn ensure_not_launchable(path: &Path) -> Result<(), String> {
+    let metadata = fs::metadata(path).map_err(|e| format!("ファイル情報の取得に失敗しました: {}", e))?;
+    if metadata.is_dir() {
+        return Ok(());
+    }
+    let is_desktop_entry = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("desktop"));
+    #[cfg(unix)]
+    let is_executable = {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o111 != 0
+    };
+    #[cfg(not(unix))]
+    let is_executable = false;
+
+    if is_desktop_entry || is_executable {
+        return Err(format!("プログラムとして起動するファイルは既定のアプリケーションで開けません: {}", path.display()));
+    }
+    Ok(())
+}
+
+/// ファイルの MIME タイプ（フォルダは inode/directory）
+fn mime_type_of(path: &Path) -> String {
+    if path.is_dir() {
+        return "inode/directory".to_string();
+    }
+    mime_guess::from_path(path).first_or_octet_stream().essence_str().to_string()
+}
+
+/// デスクトップエントリの MimeType（"image/*" のようなワイルドカードを含む）と一致するか
+fn mime_matches(pattern: &str, mime_type: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(prefix) => mime_type.split('/').next() == Some(prefix),
+        None => pattern.eq_ignore_ascii_case(mime_type),
+    }
+}
+
+fn collect_desktop_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_desktop_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "desktop") {
+            files.push(path);
+        }
+    }
+}
+
+/// applications ディレクトリからの相対パスの区切りを "-" にしたものがデスクトップファイルID
+fn desktop_file_id(applications_dir: &Path, file: &Path) -> Option<String> {
+    let relative = file.strip_prefix(applications_dir).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("-"))
+}
+
+/// デスクトップエントリを解析する。ファイルを開くのに使えないもの（非表示、端末用など）は `None`
+fn parse_desktop_entry(id: &str, source: &Path, content: &str) -> Option<DesktopEntry> {
+    let group = ini_group(content, "Desktop Entry")?;
+    let value = |key: &str| group.get(key).cloned();
+    let flag = |key: &str| value(key).is_some_and(|value| value == "true");
+
+    if value("Type").as_deref() != Some("Application") || flag("Hidden") || flag("Terminal") {
+        return None;
+    }
+    let exec = value("Exec").filter(|exec| !exec.trim().is_empty())?;
+    if let Some(try_exec) = value("TryExec") {
+        if !executable_exists(&try_exec) {
+            return None;
+        }
+    }
+
+    let name = locale_names()
+        .iter()
+        .find_map(|locale| value(&format!("Name[{}]", locale)))
+        .or_else(|| value("Name"))
+        .unwrap_or_else(|| id.trim_end_matches(".desktop").to_string());
+    Some(DesktopEntry {
+        id: id.to_string(),
+        name,
+        icon: value("Icon").filter(|icon| !icon.is_empty()),
+        exec,
+        working_dir: value("Path").filter(|dir| !dir.is_empty()).map(PathBuf::from),
+        mime_types: value("MimeType").map(|types| split_list(&types)).unwrap_or_default(),
+        source: source.to_path_buf(),
+    })
+}
+
+/// ini 形式の指定したグループのキーと値
+fn ini_group(content: &str, group_name: &str) -> Option<HashMap<String, String>> {
+    let mut values = None;
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            if values.is_some() {
+                break;
+            }
+            if name == group_name {
+                values = Some(HashMap::new());
+            }
+            continue;
+        }
+        if let (Some(values), Some((key, value))) = (values.as_mut(), line.split_once('=')) {
+            values.entry(key.trim().to_string()).or_insert_with(|| value.trim().to_string());
+        }
+    }
+    values
+}
+
+fn ini_value(content: &str, group_name: &str, key: &str) -> Option<String> {
+    ini_group(content, group_name)?.remove(key)
+}
+
+/// ";" 区切りのリスト
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 表示名の候補にするロケール（"ja_JP.UTF-8" なら "ja_JP", "ja"）
+fn locale_names() -> Vec<String> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+    let mut names = Vec::new();
+    if !locale.is_empty() && locale != "C" && locale != "POSIX" {
+        names.push(locale.to_string());
+        if let Some((language, _)) = locale.split_once('_') {
+            names.push(language.to_string());
+        }
+    }
+    names
+}
+
+fn executable_exists(program: &str) -> bool {
+    let program = Path::new(program);
+    if program.is_absolute() {
+        return program.is_file();
+    }
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// Exec キーを引数に分割し、フィールドコードをファイルパスなどに置き換える
+///
+/// ファイルを受け取るフィールドコードが無い場合は末尾にパスを追加する。
+/// 仕様に従い、ファイルのフィールドコードは単独の引数でのみ置き換え、他の文字列やクォートの中に
+/// 埋め込まれている場合はファイル名がシェルのコードとして解釈されうるためエラーにする。
+fn expand_exec(entry: &DesktopEntry, path: &Path) -> Result<Vec<String>, String> {
+    let path_text = path.to_string_lossy().to_string();
+    let mut args = Vec::new();
+    let mut has_file_code = false;
+
+    for word in split_exec(&entry.exec)? {
+        match word.as_str() {
+            "%f" | "%F" | "%u" | "%U" => {
+                has_file_code = true;
+                args.push(path_text.clone());
+            }
+            "%i" => {
+                if let Some(icon) = &entry.icon {
+                    args.push("--icon".to_string());
+                    args.push(icon.clone());
+                }
+            }
+            _ => {
+                let mut expanded = String::new();
+                let mut chars = word.chars();
+                while let Some(c) = chars.next() {
+                    if c != '%' {
+                        expanded.push(c);
+                        continue;
+                    }
+                    match chars.next() {
+                        Some('%') => expanded.push('%'),
+                        Some('f' | 'F' | 'u' | 'U') => {
+                            return Err(format!(
+                                "ファイルを引数の一部に埋め込む起動コマンドには対応していません: {}",
+                                entry.exec
+                            ));
+                        }
+                        Some('c') => expanded.push_str(&entry.name),
+                        Some('k') => expanded.push_str(&entry.source.to_string_lossy()),
+                        // 非推奨・未対応のフィールドコードは取り除く
+                        _ => {}
+                    }
+                }
+                if !expanded.is_empty() {
+                    args.push(expanded);
+                }
+            }
+        }
+    }
+    if !has_file_code {
+        args.push(path_text);
+    }
+    Ok(args)
+}
+
+/// Exec の値を引数に分割する（ダブルクォートとその中のバックスラッシュエスケープに対応）
+fn split_exec(exec: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => current.push(escaped),
+                            None => return Err("アプリケーションの起動コマンドが不正です".to_string()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("アプリケーションの起動コマンドが不正です".to_string()),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_entry(data_dir: &Path, relative: &str, content: &str) {
+        let path = data_dir.join("applications").join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn entry(exec: &str) -> DesktopEntry {
+        DesktopEntry {
+            id: "viewer.desktop".to_string(),
+            name: "Viewer".to_string(),
+            icon: Some("viewer".to_string()),
+            exec: exec.to_string(),
+            working_dir: None,
+            mime_types: vec![],
+            source: PathBuf::from("/usr/share/applications/viewer.desktop"),
+        }
+    }
+
+    #[test]
+    fn test_expand_exec_field_codes() {
+        let path = Path::new("/tmp/my photo.png");
+        assert_eq!(
+            expand_exec(&entry("viewer --new %U"), path).unwrap(),
+            vec!["viewer", "--new", "/tmp/my photo.png"]
+        );
+        assert_eq!(
+            expand_exec(&entry("\"/opt/My App/bin\" %i --name=%c 100%%"), path).unwrap(),
+            vec!["/opt/My App/bin", "--icon", "viewer", "--name=Viewer", "100%", "/tmp/my photo.png"]
+        );
+        assert_eq!(expand_exec(&entry("viewer %d %f"), path).unwrap(), vec!["viewer", "/tmp/my photo.png"]);
+        assert_eq!(
+            split_exec(r#"sh -c "echo \"hi\" \\ok""#).unwrap(),
+            vec!["sh", "-c", "echo \"hi\" \\ok"]
+        );
+        assert!(split_exec("viewer \"unterminated").is_err());
+
+        // 引数に埋め込まれたファイル名はシェルのコードになりうるため置き換えない
+        let malicious = Path::new("/tmp/x'; rm -rf ~; '.png");
+        let error = expand_exec(&entry("sh -c \"viewer '%f'\""), malicious).unwrap_err();
+        assert!(error.starts_with("ファイルを引数の一部に埋め込む起動コマンド"));
+        assert!(expand_exec(&entry("viewer --file=%u"), malicious).is_err());
+        assert_eq!(
+            expand_exec(&entry("viewer %f"), malicious).unwrap(),
+            vec!["viewer", "/tmp/x'; rm -rf ~; '.png"]
+        );
+    }
+
+    #[test]
+    fn test_mime_matches() {
+        assert!(mime_matches("image/png", "image/png"));
+        assert!(mime_matches("image/*", "image/jpeg"));
+        assert!(!mime_matches("image/*", "text/plain"));
+        assert!(!mime_matches("image/png", "image/jpeg"));
+    }
+
+    #[test]
+    fn test_applications_for_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let user_data = temp_dir.path().join("user");
+        let system_data = temp_dir.path().join("system");
+        let config = temp_dir.path().join("config");
+
+        write_entry(&system_data, "viewer.desktop", "[Desktop Entry]\nType=Application\nName=Viewer\nExec=viewer %f\nMimeType=image/png;image/jpeg;\n");
+        write_entry(&system_data, "gimp.desktop", "[Desktop Entry]\nType=Application\nName=GIMP\nExec=gimp %U\nMimeType=image/*;\n");
+        write_entry(&system_data, "editor.desktop", "[Desktop Entry]\nType=Application\nName=Editor\nExec=editor %F\nMimeType=text/plain;\n");
+        write_entry(&system_data, "term.desktop", "[Desktop Entry]\nType=Application\nName=Term\nExec=vim %F\nTerminal=true\nMimeType=image/png;\n");
+        write_entry(&system_data, "kde/paint.desktop", "[Desktop Entry]\nType=Application\nName=Paint\nExec=paint %f\nMimeType=image/png;\n");
+        // ユーザーのデータディレクトリで隠したエントリは表示しない
+        write_entry(&system_data, "old.desktop", "[Desktop Entry]\nType=Application\nName=Old\nExec=old %f\nMimeType=image/png;\n");
+        write_entry(&user_data, "old.desktop", "[Desktop Entry]\nType=Application\nName=Old\nHidden=true\n");
+        fs::create_dir_all(&config).unwrap();
+        fs::write(config.join("mimeapps.list"), "[Default Applications]\nimage/png=viewer.desktop;gimp.desktop;\n").unwrap();
+
+        let service = ApplicationService::new(vec![user_data, system_data], vec![config]);
+        let applications = service.applications_for(Path::new("/photos/cat.png"));
+        let names: Vec<&str> = applications.iter().map(|app| app.name.as_str()).collect();
+        assert_eq!(names, vec!["Viewer", "GIMP", "Paint"]);
+        assert!(applications[0].is_default);
+        assert!(!applications[1].is_default);
+        assert_eq!(applications[2].id, "kde-paint.desktop");
+
+        let texts = service.applications_for(Path::new("/notes/memo.txt"));
+        assert_eq!(texts.len(), 1);
+        assert_eq!(texts[0].name, "Editor");
+    }
+
+    #[test]
+    fn test_launchable_files_are_not_opened() {
+        let temp_dir = TempDir::new().unwrap();
+        let note = temp_dir.path().join("note.txt");
+        let launcher = temp_dir.path().join("evil.Desktop");
+        fs::write(&note, "memo").unwrap();
+        fs::write(&launcher, "[Desktop Entry]\nExec=sh\n").unwrap();
+
+        assert!(ensure_not_launchable(&note).is_ok());
+        assert!(ensure_not_launchable(temp_dir.path()).is_ok());
+        assert!(ensure_not_launchable(&launcher).is_err());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let script = temp_dir.path().join("run.txt");
+            fs::write(&script, "#!/bin/sh\n").unwrap();
+            fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+            let error = ensure_not_launchable(&script).unwrap_err();
+            assert!(error.starts_with("プログラムとして起動するファイル"));
+        }
+    }
+
+    #[test]
+    fn test_open_with_unknown_application() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = ApplicationService::new(vec![temp_dir.path().to_path_buf()], vec![]);
+        let result = service.open_with(temp_dir.path(), "missing.desktop");
+        assert!(result.unwrap_err().contains("アプリケーションが見つかりません"));
+    }
+}
@@ -0,0 +1,770 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use base64::{Engine as _, engine::general_purpose};
+use chrono::{Datelike, Local, TimeZone, Timelike};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+use crate::progress::{Progress, ProgressTracker};
+use crate::temp_path::temp_path_for;
+use crate::walker::build_walker;
+
+/// 書庫の展開・作成の進捗を送るイベント名
+pub const ARCHIVE_PROGRESS_EVENT: &str = "archive-progress";
+
+/// エントリのプレビューで既定で読み込むサイズ
+const DEFAULT_PREVIEW_BYTES: usize = 1024 * 1024;
+
+/// エントリのプレビューで読み込むサイズの上限
+const MAX_PREVIEW_BYTES: usize = 10 * 1024 * 1024;
+
+/// 展開後の合計サイズの上限（圧縮率の極端に高い書庫で容量を使い切らないため）
+const MAX_EXTRACT_BYTES: u64 = 64 * 1024 * 1024 * 1024;
+
+/// 書庫の形式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+/// 書庫内のエントリ（DirectoryEntry に相当）
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ArchiveEntry {
+    pub name: String,
+    /// 書庫内のパス（区切りは "/"、末尾の "/" は付けない）
+    pub path: String,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub size: u64,
+    /// zip の場合の圧縮後サイズ
+    pub compressed_size: Option<u64>,
+    pub modified: Option<u64>,
+}
+
+/// 書庫内エントリのプレビュー
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ArchiveEntryPreview {
+    pub entry: ArchiveEntry,
+    /// 先頭部分を Base64 エンコードしたもの
+    pub data: String,
+    pub mime_type: Option<String>,
+    pub truncated: bool,
+}
+
+/// 展開・作成の進捗
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ArchiveProgress {
+    pub archive_path: String,
+    pub current_path: String,
+    pub processed_bytes: u64,
+    pub total_bytes: u64,
+    pub processed_files: usize,
+    pub total_files: usize,
+}
+
+/// 展開の結果
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ExtractOutcome {
+    /// 展開したファイル・フォルダのパス
+    pub extracted: Vec<String>,
+    /// 展開しなかったエントリ（危険なパス、リンク、既存ファイル）の書庫内パス
+    pub skipped: Vec<String>,
+}
+
+/// 作成した書庫
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CreatedArchive {
+    pub path: String,
+    pub size: u64,
+    pub file_count: usize,
+}
+
+impl ArchiveProgress {
+    fn start(archive: &Path, total_bytes: u64, total_files: usize) -> Self {
+        Self {
+            archive_path: archive.to_string_lossy().to_string(),
+            current_path: String::new(),
+            processed_bytes: 0,
+            total_bytes,
+            processed_files: 0,
+            total_files,
+        }
+    }
+}
+
+impl Progress for ArchiveProgress {
+    fn set_current_path(&mut self, path: String) {
+        self.current_path = path;
+    }
+
+    fn add_processed(&mut self, files: usize, bytes: u64) {
+        self.processed_files += files;
+        self.processed_bytes += bytes;
+    }
+}
+
+/// エントリを1件処理したあとに続けるかどうか
+enum Visit {
+    Continue,
+    Stop,
+}
+
+/// zip・tar・tar.gz の一覧・プレビュー・展開と zip の作成を担当するサービスクラス
+pub struct ArchiveService;
+
+impl ArchiveService {
+    /// 拡張子から書庫の形式を判定し、分からなければ先頭のバイト列で判定する
+    pub fn detect_format(archive: &Path) -> Result<ArchiveFormat, String> {
+        let name = archive
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.ends_with(".zip") {
+            return Ok(ArchiveFormat::Zip);
+        }
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            return Ok(ArchiveFormat::TarGz);
+        }
+        if name.ends_with(".tar") {
+            return Ok(ArchiveFormat::Tar);
+        }
+
+        let mut header = Vec::with_capacity(512);
+        File::open(archive)
+            .and_then(|file| file.take(512).read_to_end(&mut header))
+            .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+        if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+            Ok(ArchiveFormat::Zip)
+        } else if header.starts_with(&[0x1f, 0x8b]) {
+            Ok(ArchiveFormat::TarGz)
+        } else if header.len() >= 262 && &header[257..262] == b"ustar" {
+            Ok(ArchiveFormat::Tar)
+        } else {
+            Err("対応していない書庫形式です".to_string())
+        }
+    }
+
+    /// 書庫内のエントリ一覧を取得
+    pub fn list_entries(archive: &Path) -> Result<Vec<ArchiveEntry>, String> {
+        let mut entries = Vec::new();
+        visit_entries(archive, |entry, _| {
+            entries.push(entry);
+            Ok(Visit::Continue)
+        })?;
+        Ok(entries)
+    }
+
+    /// 書庫全体を展開せずに、1つのエントリの先頭を読み込む
+    pub fn preview_entry(archive: &Path, entry_path: &str, max_bytes: Option<usize>) -> Result<ArchiveEntryPreview, String> {
+        let max_bytes = max_bytes.unwrap_or(DEFAULT_PREVIEW_BYTES).clamp(1, MAX_PREVIEW_BYTES);
+        let target = normalize_entry_path(entry_path);
+        let mut preview = None;
+
+        visit_entries(archive, |entry, reader| {
+            if entry.path != target {
+                return Ok(Visit::Continue);
+            }
+            if entry.is_dir {
+                return Err("フォルダはプレビューできません".to_string());
+            }
+            let mut data = Vec::new();
+            reader
+                .take(max_bytes as u64)
+                .read_to_end(&mut data)
+                .map_err(|e| format!("書庫の読み込みに失敗しました: {}", e))?;
+            preview = Some(ArchiveEntryPreview {
+                mime_type: mime_guess::from_path(&entry.name).first().map(|mime| mime.essence_str().to_string()),
+                truncated: (data.len() as u64) < entry.size,
+                data: general_purpose::STANDARD.encode(&data),
+                entry,
+            });
+            Ok(Visit::Stop)
+        })?;
+
+        preview.ok_or_else(|| format!("書庫内にエントリが見つかりません: {}", entry_path))
+    }
+
+    /// 書庫を `dest_dir` に展開する
+    ///
+    /// `selected` を指定した場合はそのエントリ（フォルダなら配下すべて）だけを展開する。
+    /// 展開先の外に出るパスやリンクは展開せず、既存のファイルは `overwrite` が false なら残す。
+    /// 展開後の合計が `MAX_EXTRACT_BYTES` を超える書庫や、記録より大きいエントリは展開しない。
+    pub fn extract<F>(
+        archive: &Path,
+        dest_dir: &Path,
+        selected: Option<&[String]>,
+        overwrite: bool,
+        on_progress: F,
+    ) -> Result<ExtractOutcome, String>
+    where
+        F: FnMut(&ArchiveProgress),
+    {
+        extract_limited(archive, dest_dir, selected, overwrite, MAX_EXTRACT_BYTES, on_progress)
+    }
+
+    /// 選択したファイル・フォルダから zip を作成する
+    ///
+    /// 書庫内のパスは各パスの親フォルダからの相対パスになる。既存のファイルは上書きしない。
+    pub fn create_zip<F>(sources: &[PathBuf], destination: &Path, mut on_progress: F) -> Result<CreatedArchive, String>
+    where
+        F: FnMut(&ArchiveProgress),
+    {
+        if sources.is_empty() {
+            return Err("書庫に入れるファイルを選択してください".to_string());
+        }
+        if destination.exists() {
+            return Err("同じ名前のファイルが既に存在します".to_string());
+        }
+
+        let mut files = Vec::new();
+        for source in sources {
+            if fs::symlink_metadata(source).is_err() {
+                return Err(format!("ファイルが見つかりません: {}", source.display()));
+            }
+            let base = source.parent().unwrap_or(Path::new(""));
+            for entry in build_walker(source, true, false, None) {
+                let entry = entry.map_err(|e| format!("フォルダの読み込みに失敗しました: {}", e))?;
+                let path = entry.into_path();
+                let Ok(relative) = path.strip_prefix(base) else { continue };
+                let name = relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push((path, name));
+            }
+        }
+
+        let total_bytes = files
+            .iter()
+            .filter_map(|(path, _)| fs::symlink_metadata(path).ok().filter(|m| m.is_file()))
+            .map(|metadata| metadata.len())
+            .sum();
+        let total_files = files.iter().filter(|(path, _)| path.is_file()).count();
+        let mut tracker = ProgressTracker::new(ArchiveProgress::start(destination, total_bytes, total_files), &mut on_progress);
+
+        // 途中で失敗しても壊れた zip が残らないよう、一時ファイルに書いてから名前を変える
+        let temp_path = temp_path_for(destination);
+        let result = (|| -> Result<usize, String> {
+            let file = File::create(&temp_path).map_err(|e| format!("ファイルの作成に失敗しました: {}", e))?;
+            let mut writer = ZipWriter::new(BufWriter::new(file));
+            let mut file_count = 0;
+            for (path, name) in &files {
+                let metadata = fs::symlink_metadata(path).map_err(|e| format!("ファイル情報の取得に失敗しました: {}", e))?;
+                let options = zip_options(&metadata);
+                if metadata.is_dir() {
+                    writer
+                        .add_directory(name.as_str(), options)
+                        .map_err(|e| format!("書庫の作成に失敗しました: {}", e))?;
+                    continue;
+                }
+                if !metadata.is_file() {
+                    // シンボリックリンクなどは含めない
+                    continue;
+                }
+
+                tracker.set_current(name);
+                writer
+                    .start_file(name.as_str(), options)
+                    .map_err(|e| format!("書庫の作成に失敗しました: {}", e))?;
+                let mut reader = BufReader::new(File::open(path).map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?);
+                copy_with_progress(&mut reader, &mut writer, &mut tracker)
+                    .map_err(|e| format!("書庫の作成に失敗しました: {}", e))?;
+                tracker.add_files(1, 0);
+                file_count += 1;
+            }
+            writer
+                .finish()
+                .map_err(|e| format!("書庫の作成に失敗しました: {}", e))?
+                .flush()
+                .map_err(|e| format!("書庫の作成に失敗しました: {}", e))?;
+            fs::rename(&temp_path, destination).map_err(|e| format!("ファイルの保存に失敗しました: {}", e))?;
+            Ok(file_count)
+        })();
+
+        let file_count = result.inspect_err(|_| {
+            let _ = fs::remove_file(&temp_path);
+        })?;
+        tracker.report(true);
+
+        Ok(CreatedArchive {
+            path: destination.to_string_lossy().to_string(),
+            size: fs::metadata(destination).map(|m| m.len()).unwrap_or(0),
+            file_count,
+        })
+    }
+}
+
+/// `max_bytes` を展開後の合計サイズの上限として書庫を展開する
+fn extract_limited<F>(
+    archive: &Path,
+    dest_dir: &Path,
+    selected: Option<&[String]>,
+    overwrite: bool,
+    max_bytes: u64,
+    mut on_progress: F,
+) -> Result<ExtractOutcome, String>
+where
+    F: FnMut(&ArchiveProgress),
+{
+    if !dest_dir.is_dir() {
+        return Err("展開先のフォルダが見つかりません".to_string());
+    }
+    let dest_root = dest_dir
+        .canonicalize()
+        .map_err(|e| format!("展開先の確認に失敗しました: {}", e))?;
+    let selected: Option<Vec<String>> = selected.map(|paths| paths.iter().map(|path| normalize_entry_path(path)).collect());
+    let is_selected = |path: &str| {
+        selected.as_ref().is_none_or(|selected| {
+            selected
+                .iter()
+                .any(|s| path == s || path.strip_prefix(s.as_str()).is_some_and(|rest| rest.starts_with('/')))
+        })
+    };
+
+    // 進捗の合計を出すために先に一覧を読む
+    let (total_bytes, total_files) = ArchiveService::list_entries(archive)?
+        .iter()
+        .filter(|entry| !entry.is_dir && is_selected(&entry.path))
+        .fold((0u64, 0), |(bytes, files), entry| (bytes.saturating_add(entry.size), files + 1));
+    // 各エントリは記録されたサイズまでしか書き込まないため、合計もこの値を超えない
+    if total_bytes > max_bytes {
+        return Err(format!(
+            "展開後のサイズ（{} バイト）が上限（{} バイト）を超えるため展開できません",
+            total_bytes, max_bytes
+        ));
+    }
+    let mut tracker = ProgressTracker::new(ArchiveProgress::start(archive, total_bytes, total_files), &mut on_progress);
+    let mut outcome = ExtractOutcome::default();
+
+    visit_entries(archive, |entry, reader| {
+        if !is_selected(&entry.path) {
+            return Ok(Visit::Continue);
+        }
+        let Some(relative) = safe_relative_path(&entry.path) else {
+            outcome.skipped.push(entry.path);
+            return Ok(Visit::Continue);
+        };
+        if entry.is_symlink {
+            outcome.skipped.push(entry.path);
+            return Ok(Visit::Continue);
+        }
+
+        let target = dest_root.join(&relative);
+        if entry.is_dir {
+            create_dir_within(&dest_root, &target)?;
+            outcome.extracted.push(target.to_string_lossy().to_string());
+            return Ok(Visit::Continue);
+        }
+
+        tracker.set_current(&entry.path);
+        if let Some(parent) = target.parent() {
+            create_dir_within(&dest_root, parent)?;
+        }
+        if let Ok(existing) = fs::symlink_metadata(&target) {
+            if !overwrite || existing.is_dir() {
+                outcome.skipped.push(entry.path);
+                tracker.add_files(1, entry.size);
+                return Ok(Visit::Continue);
+            }
+            // リンク先に書き込まないよう、既存のファイルやリンクは先に消す
+            fs::remove_file(&target).map_err(|e| format!("既存ファイルの削除に失敗しました: {}", e))?;
+        }
+
+        let mut writer = BufWriter::new(
+            File::create(&target).map_err(|e| format!("ファイルの作成に失敗しました: {}", e))?,
+        );
+        // 記録より大きいエントリは、1バイト多く読めた時点で止める
+        let copied = copy_with_progress(&mut reader.take(entry.size.saturating_add(1)), &mut writer, &mut tracker)
+            .and_then(|copied| writer.flush().map(|_| copied));
+        let error = match copied {
+            Ok(copied) if copied > entry.size => Some(format!("エントリが記録されたサイズより大きいため展開を中止しました: {}", entry.path)),
+            Ok(_) => None,
+            Err(e) => Some(format!("書庫の展開に失敗しました: {}", e)),
+        };
+        if let Some(error) = error {
+            drop(writer);
+            let _ = fs::remove_file(&target);
+            return Err(error);
+        }
+        tracker.add_files(1, 0);
+        outcome.extracted.push(target.to_string_lossy().to_string());
+        Ok(Visit::Continue)
+    })?;
+
+    tracker.report(true);
+    Ok(outcome)
+}
+
+/// 書庫のエントリを先頭から順に読み、エントリ情報と中身の読み出し口を `visit` に渡す
+fn visit_entries<F>(archive: &Path, mut visit: F) -> Result<(), String>
+where
+    F: FnMut(ArchiveEntry, &mut dyn Read) -> Result<Visit, String>,
+{
+    let format = ArchiveService::detect_format(archive)?;
+    let file = File::open(archive).map_err(|e| format!("書庫を開けませんでした: {}", e))?;
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = ZipArchive::new(BufReader::new(file)).map_err(|e| format!("書庫を開けませんでした: {}", e))?;
+            for index in 0..zip.len() {
+                let mut entry = zip
+                    .by_index(index)
+                    .map_err(|e| format!("書庫の読み込みに失敗しました: {}", e))?;
+                let info = ArchiveEntry {
+                    name: String::new(),
+                    path: normalize_entry_path(entry.name()),
+                    is_dir: entry.is_dir(),
+                    is_symlink: entry.is_symlink(),
+                    size: entry.size(),
+                    compressed_size: Some(entry.compressed_size()),
+                    modified: entry.last_modified().and_then(zip_datetime_to_unix),
+                };
+                if let Visit::Stop = visit(with_name(info), &mut entry)? {
+                    break;
+                }
+            }
+        }
+        ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+            let reader: Box<dyn Read> = match format {
+                ArchiveFormat::TarGz => Box::new(GzDecoder::new(BufReader::new(file))),
+                _ => Box::new(BufReader::new(file)),
+            };
+            let mut tar = tar::Archive::new(reader);
+            let entries = tar.entries().map_err(|e| format!("書庫を開けませんでした: {}", e))?;
+            for entry in entries {
+                let mut entry = entry.map_err(|e| format!("書庫の読み込みに失敗しました: {}", e))?;
+                let entry_type = entry.header().entry_type();
+                if !(entry_type.is_file() || entry_type.is_dir() || entry_type.is_symlink() || entry_type.is_hard_link()) {
+                    // PAX ヘッダなどの管理用エントリは一覧に含めない
+                    continue;
+                }
+                let path = entry
+                    .path()
+                    .map(|path| path.to_string_lossy().to_string())
+                    .map_err(|e| format!("書庫の読み込みに失敗しました: {}", e))?;
+                let info = ArchiveEntry {
+                    name: String::new(),
+                    path: normalize_entry_path(&path),
+                    is_dir: entry_type.is_dir(),
+                    is_symlink: entry_type.is_symlink() || entry_type.is_hard_link(),
+                    size: entry.size(),
+                    compressed_size: None,
+                    modified: entry.header().mtime().ok(),
+                };
+                if let Visit::Stop = visit(with_name(info), &mut entry)? {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// パスの最後の要素を名前にする
+fn with_name(mut entry: ArchiveEntry) -> ArchiveEntry {
+    entry.name = entry.path.rsplit('/').next().unwrap_or_default().to_string();
+    entry
+}
+
+/// 書庫内パスの区切りを "/" に揃え、先頭の "./" と末尾の "/" を取り除く
+fn normalize_entry_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut path = path.as_str();
+    while let Some(rest) = path.strip_prefix("./") {
+        path = rest;
+    }
+    path.trim_end_matches('/').to_string()
+}
+
+/// 展開先の中に収まる相対パスにする。絶対パスや ".." を含むものは None
+fn safe_relative_path(entry_path: &str) -> Option<PathBuf> {
+    if entry_path.starts_with('/') {
+        return None;
+    }
+    let mut relative = PathBuf::new();
+    for component in Path::new(entry_path).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+/// 展開先から1階層ずつフォルダを作成する
+///
+/// 途中にシンボリックリンクやファイルがあれば辿らずに止めるため、展開先の外には作成しない。
+fn create_dir_within(root: &Path, dir: &Path) -> Result<(), String> {
+    let outside = || format!("展開先の外には書き込めません: {}", dir.display());
+    let relative = dir.strip_prefix(root).map_err(|_| outside())?;
+    let mut current = root.to_path_buf();
+    for component in relative.components() {
+        let Component::Normal(part) = component else {
+            return Err(outside());
+        };
+        current.push(part);
+        match fs::symlink_metadata(&current) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(metadata) if metadata.file_type().is_symlink() => return Err(outside()),
+            Ok(_) => return Err(format!("同じ名前のファイルがあるためフォルダを作成できません: {}", current.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                fs::create_dir(&current).map_err(|e| format!("フォルダの作成に失敗しました: {}", e))?;
+            }
+            Err(e) => return Err(format!("フォルダの作成に失敗しました: {}", e)),
+        }
+    }
+    Ok(())
+}
+
+/// 読み込んだバイト数を進捗に足しながらコピーし、コピーしたバイト数を返す
+fn copy_with_progress(reader: &mut dyn Read, writer: &mut dyn Write, tracker: &mut ProgressTracker<ArchiveProgress>) -> io::Result<u64> {
+    let mut buffer = vec![0u8; 256 * 1024];
+    let mut copied = 0;
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(copied),
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buffer[..read])?;
+        copied += read as u64;
+        tracker.add_bytes(read as u64);
+    }
+}
+
+/// zip に記録する更新日時とパーミッション
+fn zip_options(metadata: &fs::Metadata) -> SimpleFileOptions {
+    // 4GiB 以上のファイルは ZIP64 で記録する
+    let mut options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(metadata.len() >= u64::from(u32::MAX));
+    let modified = metadata
+        .modified()
+        .ok()
+        .map(chrono::DateTime::<Local>::from)
+        .and_then(|time| {
+            zip::DateTime::from_date_and_time(
+                u16::try_from(time.year()).ok()?,
+                time.month() as u8,
+                time.day() as u8,
+                time.hour() as u8,
+                time.minute() as u8,
+                time.second() as u8,
+            )
+            .ok()
+        });
+    if let Some(modified) = modified {
+        options = options.last_modified_time(modified);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        options = options.unix_permissions(metadata.permissions().mode() & 0o777);
+    }
+    options
+}
+
+/// zip の日時（ローカル時刻）をUNIX秒に変換
+fn zip_datetime_to_unix(time: zip::DateTime) -> Option<u64> {
+    Local
+        .with_ymd_and_hms(
+            time.year() as i32,
+            time.month() as u32,
+            time.day() as u32,
+            time.hour() as u32,
+            time.minute() as u32,
+            time.second() as u32,
+        )
+        .earliest()
+        .and_then(|time| u64::try_from(time.timestamp()).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use tempfile::TempDir;
+
+    /// docs/readme.txt と docs/img/logo.bin を含むフォルダを作る
+    fn setup_source(root: &Path) -> PathBuf {
+        let docs = root.join("docs");
+        fs::create_dir_all(docs.join("img")).unwrap();
+        fs::write(docs.join("readme.txt"), "hello archive").unwrap();
+        fs::write(docs.join("img/logo.bin"), vec![9u8; 3000]).unwrap();
+        docs
+    }
+
+    fn names(entries: &[ArchiveEntry]) -> Vec<&str> {
+        let mut names: Vec<&str> = entries.iter().map(|entry| entry.path.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_create_list_and_extract_zip() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs = setup_source(temp_dir.path());
+        let archive = temp_dir.path().join("docs.zip");
+
+        let mut progress = Vec::new();
+        let created = ArchiveService::create_zip(std::slice::from_ref(&docs), &archive, |p| progress.push(p.clone())).unwrap();
+        assert_eq!(created.file_count, 2);
+        assert_eq!(progress.last().unwrap().processed_bytes, 3013);
+        assert!(!temp_path_for(&archive).exists());
+
+        let entries = ArchiveService::list_entries(&archive).unwrap();
+        assert_eq!(names(&entries), vec!["docs", "docs/img", "docs/img/logo.bin", "docs/readme.txt"]);
+        let readme = entries.iter().find(|entry| entry.path == "docs/readme.txt").unwrap();
+        assert_eq!((readme.name.as_str(), readme.size, readme.is_dir), ("readme.txt", 13, false));
+        assert!(readme.compressed_size.is_some() && readme.modified.is_some());
+
+        let out = temp_dir.path().join("out");
+        fs::create_dir(&out).unwrap();
+        let outcome = ArchiveService::extract(&archive, &out, None, false, |_| {}).unwrap();
+        assert!(outcome.skipped.is_empty());
+        assert_eq!(fs::read_to_string(out.join("docs/readme.txt")).unwrap(), "hello archive");
+        assert_eq!(fs::read(out.join("docs/img/logo.bin")).unwrap().len(), 3000);
+
+        // 既存ファイルは上書きしない
+        fs::write(out.join("docs/readme.txt"), "edited").unwrap();
+        let outcome = ArchiveService::extract(&archive, &out, None, false, |_| {}).unwrap();
+        assert_eq!(outcome.skipped.len(), 2);
+        assert!(outcome.skipped.contains(&"docs/readme.txt".to_string()));
+        assert_eq!(fs::read_to_string(out.join("docs/readme.txt")).unwrap(), "edited");
+
+        let error = ArchiveService::create_zip(&[docs], &archive, |_| {}).unwrap_err();
+        assert_eq!(error, "同じ名前のファイルが既に存在します");
+    }
+
+    #[test]
+    fn test_preview_and_selective_extract() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs = setup_source(temp_dir.path());
+        let archive = temp_dir.path().join("docs.zip");
+        ArchiveService::create_zip(&[docs], &archive, |_| {}).unwrap();
+
+        let preview = ArchiveService::preview_entry(&archive, "docs/readme.txt", Some(5)).unwrap();
+        assert_eq!(general_purpose::STANDARD.decode(&preview.data).unwrap(), b"hello");
+        assert!(preview.truncated);
+        assert_eq!(preview.mime_type.as_deref(), Some("text/plain"));
+        assert!(ArchiveService::preview_entry(&archive, "docs/missing.txt", None).is_err());
+
+        let out = temp_dir.path().join("out");
+        fs::create_dir(&out).unwrap();
+        let selected = vec!["docs/img/".to_string()];
+        ArchiveService::extract(&archive, &out, Some(&selected), false, |_| {}).unwrap();
+        assert!(out.join("docs/img/logo.bin").exists());
+        assert!(!out.join("docs/readme.txt").exists());
+    }
+
+    #[test]
+    fn test_tar_gz_listing_and_extract() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive = temp_dir.path().join("data.tar.gz");
+        let encoder = GzEncoder::new(File::create(&archive).unwrap(), Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o644);
+        header.set_mtime(1_700_000_000);
+        header.set_cksum();
+        builder.append_data(&mut header, "dir/data.txt", &b"abcd"[..]).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let entries = ArchiveService::list_entries(&archive).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, "dir/data.txt");
+        assert_eq!(entries[0].modified, Some(1_700_000_000));
+
+        let out = temp_dir.path().join("out");
+        fs::create_dir(&out).unwrap();
+        ArchiveService::extract(&archive, &out, None, false, |_| {}).unwrap();
+        assert_eq!(fs::read_to_string(out.join("dir/data.txt")).unwrap(), "abcd");
+    }
+
+    #[test]
+    fn test_extract_rejects_zip_slip() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive = temp_dir.path().join("evil.zip");
+        let mut writer = ZipWriter::new(File::create(&archive).unwrap());
+        writer.start_file("../escaped.txt", SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"bad").unwrap();
+        writer.start_file("/abs/escaped.txt", SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"bad").unwrap();
+        writer.start_file("safe.txt", SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"ok").unwrap();
+        writer.finish().unwrap();
+
+        let out = temp_dir.path().join("out");
+        fs::create_dir(&out).unwrap();
+        let outcome = ArchiveService::extract(&archive, &out, None, false, |_| {}).unwrap();
+        assert_eq!(outcome.skipped, vec!["../escaped.txt", "/abs/escaped.txt"]);
+        assert!(!temp_dir.path().join("escaped.txt").exists());
+        assert_eq!(fs::read_to_string(out.join("safe.txt")).unwrap(), "ok");
+    }
+
+    #[test]
+    fn test_extract_size_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs = setup_source(temp_dir.path());
+        let archive = temp_dir.path().join("docs.zip");
+        ArchiveService::create_zip(&[docs], &archive, |_| {}).unwrap();
+
+        let out = temp_dir.path().join("out");
+        fs::create_dir(&out).unwrap();
+        let error = extract_limited(&archive, &out, None, false, 3012, |_| {}).unwrap_err();
+        assert!(error.starts_with("展開後のサイズ（3013 バイト）が上限"));
+        assert_eq!(fs::read_dir(&out).unwrap().count(), 0);
+
+        // 記録されたサイズを偽ったエントリは途中で止める
+        let forged = temp_dir.path().join("forged.zip");
+        let mut writer = ZipWriter::new(File::create(&forged).unwrap());
+        writer.start_file("bomb.bin", SimpleFileOptions::default()).unwrap();
+        writer.write_all(&vec![0u8; 100_000]).unwrap();
+        writer.finish().unwrap();
+        let mut bytes = fs::read(&forged).unwrap();
+        let central = bytes.windows(4).position(|window| window == b"PK\x01\x02").unwrap();
+        bytes[22..26].copy_from_slice(&10u32.to_le_bytes());
+        bytes[central + 24..central + 28].copy_from_slice(&10u32.to_le_bytes());
+        fs::write(&forged, &bytes).unwrap();
+
+        assert!(ArchiveService::extract(&forged, &out, None, false, |_| {}).is_err());
+        assert!(!out.join("bomb.bin").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_dir_within_stops_at_symlinks() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("out");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir(&root).unwrap();
+        fs::create_dir(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+
+        create_dir_within(&root, &root.join("a/b")).unwrap();
+        assert!(root.join("a/b").is_dir());
+        let error = create_dir_within(&root, &root.join("link/sub")).unwrap_err();
+        assert!(error.starts_with("展開先の外には書き込めません"));
+        assert!(!outside.join("sub").exists());
+    }
+
+    #[test]
+    fn test_safe_relative_path() {
+        assert_eq!(safe_relative_path("a/b.txt"), Some(PathBuf::from("a/b.txt")));
+        assert_eq!(safe_relative_path("a/./b.txt"), Some(PathBuf::from("a/b.txt")));
+        assert_eq!(safe_relative_path("a/../../b"), None);
+        assert_eq!(safe_relative_path("/etc/passwd"), None);
+        assert_eq!(safe_relative_path(""), None);
+        assert_eq!(normalize_entry_path("./dir\\sub/"), "dir/sub");
+    }
+}
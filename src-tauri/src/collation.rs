@@ -0,0 +1,230 @@
+use std::cmp::Ordering;
+use icu_normalizer::DecomposingNormalizerBorrowed;
+
+/// デンマーク語・ノルウェー語で Z の後ろに並べる文字（この順）
+const AFTER_Z_DANISH: &[char] = &['æ', 'ø', 'å'];
+
+/// スウェーデン語・フィンランド語で Z の後ろに並べる文字（この順）
+const AFTER_Z_SWEDISH: &[char] = &['å', 'ä', 'ö'];
+
+/// ロケールに応じて名前を比べる照合器
+///
+/// 文字を Unicode の互換分解で基底文字とアクセントに分け、次の順に比べる。
+/// 1. 基底文字（全角・半角、アクセント、ひらがな・カタカナを区別しない。数字の並びは数値として比べる）
+/// 2. アクセント
+/// 3. 大文字・小文字とひらがな・カタカナ（小文字・ひらがなが先）
+/// 4. 元の文字列
+///
+/// 記号は数字より、数字は文字より前に並ぶ。北欧語のロケールでは å・ä・ö などを Z の後ろに並べる。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Collator {
+    after_z: &'static [char],
+}
+
+impl Collator {
+    /// ロケール名（"ja_JP.UTF-8"・"sv-SE" など）から照合器を作る
+    pub fn new(locale: &str) -> Self {
+        let language = locale
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let after_z = match language.as_str() {
+            "da" | "nb" | "nn" | "no" => AFTER_Z_DANISH,
+            "sv" | "fi" => AFTER_Z_SWEDISH,
+            _ => &[],
+        };
+        Self { after_z }
+    }
+
+    /// 環境変数 LC_ALL・LC_COLLATE・LANG の順にロケールを決めて照合器を作る
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_COLLATE", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        Self::new(&locale)
+    }
+
+    /// 比較用のキーを作る。`case_sensitive` の場合は大文字を小文字より先に並べる
+    pub fn key(&self, text: &str, case_sensitive: bool) -> CollationKey {
+        let normalizer = DecomposingNormalizerBorrowed::new_nfkd();
+        let mut elements: Vec<Element> = Vec::new();
+        let mut digits = String::new();
+
+        for c in text.chars() {
+            let lower = c.to_lowercase().next().unwrap_or(c);
+            if let Some(index) = self.after_z.iter().position(|&letter| letter == lower) {
+                push_number(&mut elements, &mut digits);
+                let weight = LETTER_AFTER_Z + index as u32;
+                elements.push(Element::letter(weight, c != lower, case_sensitive));
+                continue;
+            }
+
+            for decomposed in normalizer.normalize_iter(std::iter::once(c)) {
+                if is_combining_mark(decomposed) {
+                    match elements.last_mut() {
+                        Some(element) if digits.is_empty() => element.accents.push(decomposed as u32),
+                        _ => elements.push(Element::new(Primary::Symbol(weight(decomposed)), 0)),
+                    }
+                } else if decomposed.is_ascii_digit() {
+                    digits.push(decomposed);
+                } else {
+                    push_number(&mut elements, &mut digits);
+                    let element = if decomposed.is_alphanumeric() {
+                        let folded = decomposed.to_lowercase().next().unwrap_or(decomposed);
+                        let (base, is_katakana) = match folded {
+                            // カタカナはひらがなと同じ文字として扱う
+                            'ァ'..='ヶ' => (char::from_u32(folded as u32 - 0x60).unwrap_or(folded), true),
+                            _ => (folded, false),
+                        };
+                        let mut element = Element::letter(weight(base), decomposed != folded, case_sensitive);
+                        element.variant |= u8::from(is_katakana);
+                        element
+                    } else {
+                        Element::new(Primary::Symbol(weight(decomposed)), 0)
+                    };
+                    elements.push(element);
+                }
+            }
+        }
+        push_number(&mut elements, &mut digits);
+
+        CollationKey { elements, text: text.to_string() }
+    }
+}
+
+/// Z の後ろに並べる文字の重み（'z' と '{' の重みの間）
+const LETTER_AFTER_Z: u32 = ('z' as u32) * 4 + 1;
+
+/// 文字の重み（ロケールで足す文字を間に置けるよう間隔をあける）
+fn weight(c: char) -> u32 {
+    c as u32 * 4
+}
+
+/// 結合文字（アクセントや濁点）なら true
+fn is_combining_mark(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{3099}'..='\u{309A}'
+            | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
+/// 溜めた数字を 1 つの数値要素として追加する
+fn push_number(elements: &mut Vec<Element>, digits: &mut String) {
+    if digits.is_empty() {
+        return;
+    }
+    let value = digits.trim_start_matches('0');
+    elements.push(Element::new(Primary::Number(value.len(), value.to_string()), 0));
+    digits.clear();
+}
+
+/// 基底文字の比較値（記号 < 数値 < 文字）
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Primary {
+    Symbol(u32),
+    /// 先頭の 0 を除いた桁数と数字
+    Number(usize, String),
+    /// 大文字を先に並べる場合の順位と重み
+    Letter(u8, u32),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Element {
+    primary: Primary,
+    accents: Vec<u32>,
+    /// 大文字・カタカナなら 1
+    variant: u8,
+}
+
+impl Element {
+    fn new(primary: Primary, variant: u8) -> Self {
+        Self { primary, accents: Vec::new(), variant }
+    }
+
+    fn letter(weight: u32, is_upper: bool, case_sensitive: bool) -> Self {
+        let rank = if case_sensitive { u8::from(!is_upper) } else { 0 };
+        Self::new(Primary::Letter(rank, weight), u8::from(is_upper))
+    }
+}
+
+/// 照合用のキー。並べ替えの前に作っておくと比較のたびに分解せずに済む
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollationKey {
+    elements: Vec<Element>,
+    text: String,
+}
+
+impl Ord for CollationKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (&self.elements, &other.elements);
+        a.iter()
+            .map(|element| &element.primary)
+            .cmp(b.iter().map(|element| &element.primary))
+            .then_with(|| a.iter().map(|element| &element.accents).cmp(b.iter().map(|element| &element.accents)))
+            .then_with(|| a.iter().map(|element| element.variant).cmp(b.iter().map(|element| element.variant)))
+            .then_with(|| self.text.cmp(&other.text))
+    }
+}
+
+impl PartialOrd for CollationKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(collator: &Collator, names: &[&str], case_sensitive: bool) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        names.sort_by_cached_key(|name| collator.key(name, case_sensitive));
+        names
+    }
+
+    #[test]
+    fn test_accents_and_width_sort_with_base_letter() {
+        let collator = Collator::new("en_US.UTF-8");
+        assert_eq!(
+            sorted(&collator, &["zebra", "Éclair", "eclair", "ｅｄｇｅ", "apple"], false),
+            vec!["apple", "eclair", "Éclair", "ｅｄｇｅ", "zebra"]
+        );
+    }
+
+    #[test]
+    fn test_natural_numbers_and_case() {
+        let collator = Collator::new("en");
+        assert_eq!(
+            sorted(&collator, &["file10", "File3", "file2", "_notes", "2024"], false),
+            vec!["_notes", "2024", "file2", "File3", "file10"]
+        );
+        assert_eq!(sorted(&collator, &["b", "B", "a", "A"], false), vec!["a", "A", "b", "B"]);
+        assert_eq!(sorted(&collator, &["b", "B", "a", "A"], true), vec!["A", "B", "a", "b"]);
+    }
+
+    #[test]
+    fn test_kana_sort_together() {
+        let collator = Collator::new("ja_JP.UTF-8");
+        assert_eq!(
+            sorted(&collator, &["ガラス", "いぬ", "カメラ", "あめ", "ｱｲｽ"], false),
+            vec!["ｱｲｽ", "あめ", "いぬ", "カメラ", "ガラス"]
+        );
+        assert_eq!(sorted(&collator, &["がき", "カキ", "かき"], false), vec!["かき", "カキ", "がき"]);
+    }
+
+    #[test]
+    fn test_nordic_letters_after_z() {
+        let names = ["ösel", "zebra", "åbo", "apple"];
+        assert_eq!(sorted(&Collator::new("en_US"), &names, false), vec!["åbo", "apple", "ösel", "zebra"]);
+        assert_eq!(sorted(&Collator::new("sv-SE"), &names, false), vec!["apple", "zebra", "åbo", "ösel"]);
+        assert_eq!(sorted(&Collator::new("nb_NO"), &["å", "ø", "æ"], false), vec!["æ", "ø", "å"]);
+    }
+}
@@ -0,0 +1,1230 @@
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::ops::ControlFlow;
+use std::path::Path;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use encoding_rs::{Encoding, BIG5, EUC_KR, GB18030, GBK, ISO_2022_JP, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8};
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use crate::preview_service::detect_encoding;
+
+/// 1ページの既定の行数
+const DEFAULT_PAGE_SIZE: usize = 200;
+
+/// 1ページの最大行数
+const MAX_PAGE_SIZE: usize = 5000;
+
+/// 形式・区切り文字・見出し行の判定に読む先頭のサイズ
+const SAMPLE_BYTES: usize = 64 * 1024;
+
+/// 見出し行の判定に使う行数
+const HEADER_SAMPLE_ROWS: usize = 20;
+
+/// 列の型の推定に使う先頭の行数
+const TYPE_SAMPLE_ROWS: usize = 1000;
+
+/// 異なる値の数を数える上限（超えた分は数えない）
+const MAX_DISTINCT_VALUES: usize = 100_000;
+
+/// 区切り文字の候補
+const DELIMITER_CANDIDATES: [u8; 4] = [b',', b'\t', b';', b'|'];
+
+/// データファイルの形式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DataFormat {
+    Csv,
+    Json,
+    /// 1行に1つの JSON 値（.jsonl / .ndjson）
+    JsonLines,
+}
+
+/// 列の推定型
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+    /// 値がすべて空
+    Empty,
+    Boolean,
+    Integer,
+    Float,
+    Date,
+    String,
+    /// オブジェクト・配列
+    Json,
+}
+
+/// データファイルの読み込み設定（省略した項目は自動判定する）
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct DataOptions {
+    pub format: Option<DataFormat>,
+    /// CSV の区切り文字
+    pub delimiter: Option<char>,
+    /// CSV の1行目が見出しかどうか
+    pub has_header: Option<bool>,
+    /// CSV の文字コード（例: "shift_jis"）
+    pub encoding: Option<String>,
+}
+
+/// 列の名前と推定型
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DataColumn {
+    pub name: String,
+    pub column_type: ColumnType,
+}
+
+/// データファイルの1ページ
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DataPage {
+    pub format: DataFormat,
+    pub delimiter: Option<char>,
+    pub has_header: bool,
+    pub encoding: String,
+    pub columns: Vec<DataColumn>,
+    /// 各行の値は `columns` と同じ順（値が無い列は null）
+    pub rows: Vec<Vec<Value>>,
+    /// 見出し行を除いた先頭からの行位置
+    pub offset: usize,
+    pub has_more: bool,
+    /// 次のページを読むためのカーソル（has_more が true の場合のみ）
+    pub next_cursor: Option<DataCursor>,
+}
+
+/// 次のページを読み始める位置と、それまでに判定したファイルの構造
+///
+/// `DataService::read_next_page` に渡すと、形式の判定や型の推定をやり直さずに続きから読める。
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DataCursor {
+    /// 次の行が始まるバイト位置
+    pub byte_offset: u64,
+    /// JSON Lines のエラー表示に使う、読み終えた行数
+    pub line: u64,
+    /// 見出し行を除いた次の行の位置
+    pub row: usize,
+    /// カーソルを作ったときのファイルサイズ（変更の検出に使う）
+    pub file_size: u64,
+    pub format: DataFormat,
+    pub delimiter: Option<char>,
+    pub has_header: bool,
+    pub encoding: String,
+    pub columns: Vec<DataColumn>,
+}
+
+/// 列の統計情報
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ColumnStats {
+    pub name: String,
+    pub column_type: ColumnType,
+    /// 空でない値の数
+    pub count: u64,
+    pub null_count: u64,
+    pub distinct_count: u64,
+    /// 上限に達したため distinct_count が下限値であるかどうか
+    pub distinct_capped: bool,
+    /// 数値の列は数値順、それ以外は文字列順
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+}
+
+/// ファイル全体の統計情報
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DataStats {
+    pub format: DataFormat,
+    pub total_rows: u64,
+    pub columns: Vec<ColumnStats>,
+}
+
+/// 読み込んだ1行
+enum Record {
+    Fields(Vec<String>),
+    Json(Value),
+}
+
+/// 次の行を読み始める位置
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ResumePoint {
+    byte: u64,
+    /// 読み終えた行数（JSON Lines のみ数える）
+    line: u64,
+}
+
+/// 判定したファイルの構造
+struct Layout {
+    format: DataFormat,
+    delimiter: u8,
+    has_header: bool,
+    encoding: &'static Encoding,
+    bom_length: u64,
+    header_names: Vec<String>,
+}
+
+/// 列名と位置の対応（JSON のキーは見つかった順に列を追加する）
+#[derive(Clone, Default)]
+struct ColumnSet {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+}
+
+impl ColumnSet {
+    fn new(names: Vec<String>) -> Self {
+        let mut columns = Self::default();
+        for name in names {
+            columns.push(name);
+        }
+        columns
+    }
+
+    fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    fn push(&mut self, name: String) -> usize {
+        let position = self.names.len();
+        self.index.entry(name.clone()).or_insert(position);
+        self.names.push(name);
+        position
+    }
+
+    /// 位置で対応する列を `len` 列まで用意する
+    fn ensure_positional(&mut self, len: usize) {
+        while self.names.len() < len {
+            let name = positional_name(self.names.len());
+            self.push(name);
+        }
+    }
+
+    fn key_position(&mut self, key: &str) -> usize {
+        match self.index.get(key) {
+            Some(&position) => position,
+            None => self.push(key.to_string()),
+        }
+    }
+
+    /// 1行を列の順の値にする
+    fn row_values(&mut self, record: Record) -> Vec<Value> {
+        let cells: Vec<(usize, Value)> = match record {
+            Record::Fields(fields) => {
+                self.ensure_positional(fields.len());
+                fields
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, field)| (i, if field.is_empty() { Value::Null } else { Value::String(field) }))
+                    .collect()
+            }
+            Record::Json(Value::Object(object)) => object
+                .into_iter()
+                .map(|(key, value)| (self.key_position(&key), value))
+                .collect(),
+            Record::Json(Value::Array(items)) => {
+                self.ensure_positional(items.len());
+                items.into_iter().enumerate().collect()
+            }
+            Record::Json(value) => vec![(self.key_position("value"), value)],
+        };
+
+        let mut row = vec![Value::Null; self.len()];
+        for (position, value) in cells {
+            row[position] = value;
+        }
+        row
+    }
+}
+
+/// CSV・JSON・JSON Lines を少しずつ読み込み、表として表示するサービスクラス
+///
+/// ファイル全体をメモリに読み込まず、必要な行だけを先頭から順に読む。続きのページは
+/// `DataCursor` のバイト位置から読むため、後ろのページでも先頭から読み直さない。
+pub struct DataService;
+
+impl DataService {
+    /// 見出し行を除いて `offset` 行目から最大 `limit` 行を読み込む
+    pub fn read_page(path: &Path, options: &DataOptions, offset: usize, limit: Option<usize>) -> Result<DataPage, String> {
+        let layout = inspect(path, options)?;
+        let (columns, types) = sample_columns(path, &layout)?;
+        read_rows(path, &layout, columns, types, None, offset, limit)
+    }
+
+    /// 前のページの `next_cursor` の位置から最大 `limit` 行を読み込む
+    ///
+    /// ファイルを先頭から読み直さず、カーソルのバイト位置から読み始める。
+    pub fn read_next_page(path: &Path, cursor: &DataCursor, limit: Option<usize>) -> Result<DataPage, String> {
+        let file_size = fs::metadata(path)
+            .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?
+            .len();
+        if file_size != cursor.file_size {
+            return Err("ファイルが変更されたため、先頭から読み直してください".to_string());
+        }
+        let encoding = Encoding::for_label(cursor.encoding.as_bytes())
+            .ok_or_else(|| format!("未対応の文字コードです: {}", cursor.encoding))?;
+        let delimiter = match cursor.format {
+            DataFormat::Csv => {
+                check_csv_encoding(encoding)?;
+                csv_delimiter(cursor.delimiter.unwrap_or(','), encoding)?
+            }
+            _ => b',',
+        };
+        let layout = Layout {
+            format: cursor.format,
+            delimiter,
+            has_header: cursor.has_header,
+            encoding,
+            bom_length: 0,
+            header_names: Vec::new(),
+        };
+        let columns = ColumnSet::new(cursor.columns.iter().map(|column| column.name.clone()).collect());
+        let types = cursor.columns.iter().map(|column| column.column_type).collect();
+        let start = ResumePoint { byte: cursor.byte_offset, line: cursor.line };
+        read_rows(path, &layout, columns, types, Some(start), cursor.row, limit)
+    }
+
+    /// ファイル全体を読み、列ごとの件数・異なる値の数・最小値・最大値を求める
+    pub fn column_stats(path: &Path, options: &DataOptions) -> Result<DataStats, String> {
+        let layout = inspect(path, options)?;
+        let (mut columns, _) = sample_columns(path, &layout)?;
+        let mut accumulators: Vec<StatsAccumulator> = Vec::new();
+        let mut total_rows = 0u64;
+
+        for_each_record(path, &layout, None, |record, _| {
+            let row = columns.row_values(record);
+            // 途中で増えた列は、それまでの行を空として数える
+            accumulators.resize_with(row.len(), || StatsAccumulator::with_nulls(total_rows));
+            for (accumulator, value) in accumulators.iter_mut().zip(&row) {
+                accumulator.add(value, layout.format);
+            }
+            total_rows += 1;
+            ControlFlow::Continue(())
+        })?;
+        accumulators.resize_with(columns.len(), || StatsAccumulator::with_nulls(total_rows));
+
+        Ok(DataStats {
+            format: layout.format,
+            total_rows,
+            columns: columns
+                .names
+                .into_iter()
+                .zip(accumulators)
+                .map(|(name, accumulator)| accumulator.finish(name))
+                .collect(),
+        })
+    }
+}
+
+/// `start`（省略時は先頭）から最大 `limit` 行を読み込み、1ページにまとめる
+///
+/// `offset` は見出し行を除いた行位置で、`start` を省略した場合はその行まで読み飛ばす。
+fn read_rows(
+    path: &Path,
+    layout: &Layout,
+    mut columns: ColumnSet,
+    mut types: Vec<ColumnType>,
+    start: Option<ResumePoint>,
+    offset: usize,
+    limit: Option<usize>,
+) -> Result<DataPage, String> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let skip = if start.is_some() { 0 } else { offset };
+
+    let mut rows = Vec::with_capacity(limit);
+    let mut has_more = false;
+    let mut next = ResumePoint::default();
+    let mut index = 0;
+    for_each_record(path, layout, start, |record, end| {
+        if index >= skip + limit {
+            has_more = true;
+            return ControlFlow::Break(());
+        }
+        if index >= skip {
+            rows.push(columns.row_values(record));
+            next = end;
+        }
+        index += 1;
+        ControlFlow::Continue(())
+    })?;
+
+    // ページ内で初めて現れた列の型はページの値から推定する
+    let width = columns.len();
+    let sampled = types.len();
+    types.resize(width, ColumnType::Empty);
+    for row in &mut rows {
+        row.resize(width, Value::Null);
+        for (position, value) in row.iter().enumerate().skip(sampled) {
+            types[position] = types[position].merge(value_type(value, layout.format));
+        }
+    }
+
+    let delimiter = (layout.format == DataFormat::Csv).then_some(layout.delimiter as char);
+    let encoding = layout.encoding.name().to_string();
+    let columns: Vec<DataColumn> = columns
+        .names
+        .into_iter()
+        .zip(types)
+        .map(|(name, column_type)| DataColumn { name, column_type })
+        .collect();
+    let next_cursor = if has_more {
+        Some(DataCursor {
+            byte_offset: next.byte,
+            line: next.line,
+            row: offset + rows.len(),
+            file_size: fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0),
+            format: layout.format,
+            delimiter,
+            has_header: layout.has_header,
+            encoding: encoding.clone(),
+            columns: columns.clone(),
+        })
+    } else {
+        None
+    };
+
+    Ok(DataPage {
+        format: layout.format,
+        delimiter,
+        has_header: layout.has_header,
+        encoding,
+        columns,
+        rows,
+        offset,
+        has_more,
+        next_cursor,
+    })
+}
+
+/// 列ごとの統計の途中経過
+#[derive(Default)]
+struct StatsAccumulator {
+    column_type: Option<ColumnType>,
+    count: u64,
+    null_count: u64,
+    distinct: HashSet<String>,
+    distinct_capped: bool,
+    min_number: Option<(f64, Value)>,
+    max_number: Option<(f64, Value)>,
+    min_text: Option<String>,
+    max_text: Option<String>,
+}
+
+impl StatsAccumulator {
+    fn with_nulls(null_count: u64) -> Self {
+        Self { null_count, ..Default::default() }
+    }
+
+    fn add(&mut self, value: &Value, format: DataFormat) {
+        let value_type = value_type(value, format);
+        if value_type == ColumnType::Empty {
+            self.null_count += 1;
+            return;
+        }
+        self.count += 1;
+        self.column_type = Some(self.column_type.map_or(value_type, |current| current.merge(value_type)));
+
+        let text = match value {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        if self.distinct.len() < MAX_DISTINCT_VALUES {
+            self.distinct.insert(text.clone());
+        } else if !self.distinct.contains(&text) {
+            self.distinct_capped = true;
+        }
+
+        if let Some(number) = number_value(value) {
+            if self.min_number.as_ref().is_none_or(|(min, _)| number < *min) {
+                self.min_number = Some((number, value.clone()));
+            }
+            if self.max_number.as_ref().is_none_or(|(max, _)| number > *max) {
+                self.max_number = Some((number, value.clone()));
+            }
+        }
+        if !matches!(value, Value::Object(_) | Value::Array(_)) {
+            if self.min_text.as_ref().is_none_or(|min| text < *min) {
+                self.min_text = Some(text.clone());
+            }
+            if self.max_text.as_ref().is_none_or(|max| text > *max) {
+                self.max_text = Some(text);
+            }
+        }
+    }
+
+    fn finish(self, name: String) -> ColumnStats {
+        let column_type = self.column_type.unwrap_or(ColumnType::Empty);
+        let (min, max) = match column_type {
+            ColumnType::Integer | ColumnType::Float => {
+                (self.min_number.map(|(_, value)| value), self.max_number.map(|(_, value)| value))
+            }
+            ColumnType::Empty | ColumnType::Json => (None, None),
+            _ => (self.min_text.map(Value::String), self.max_text.map(Value::String)),
+        };
+        ColumnStats {
+            name,
+            column_type,
+            count: self.count,
+            null_count: self.null_count,
+            distinct_count: self.distinct.len() as u64,
+            distinct_capped: self.distinct_capped,
+            min,
+            max,
+        }
+    }
+}
+
+impl ColumnType {
+    /// 2つの型をどちらの値も表せる型にまとめる
+    fn merge(self, other: ColumnType) -> ColumnType {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (ColumnType::Empty, other) | (other, ColumnType::Empty) => other,
+            (ColumnType::Integer, ColumnType::Float) | (ColumnType::Float, ColumnType::Integer) => ColumnType::Float,
+            _ => ColumnType::String,
+        }
+    }
+}
+
+/// 値の型（CSV の値は文字列から推定する）
+fn value_type(value: &Value, format: DataFormat) -> ColumnType {
+    match value {
+        Value::Null => ColumnType::Empty,
+        Value::Bool(_) => ColumnType::Boolean,
+        Value::Number(number) if number.is_i64() || number.is_u64() => ColumnType::Integer,
+        Value::Number(_) => ColumnType::Float,
+        Value::String(text) if format == DataFormat::Csv => text_type(text),
+        Value::String(text) if is_date(text.trim()) => ColumnType::Date,
+        Value::String(_) => ColumnType::String,
+        Value::Object(_) | Value::Array(_) => ColumnType::Json,
+    }
+}
+
+fn text_type(text: &str) -> ColumnType {
+    let text = text.trim();
+    if text.is_empty() {
+        ColumnType::Empty
+    } else if text.eq_ignore_ascii_case("true") || text.eq_ignore_ascii_case("false") {
+        ColumnType::Boolean
+    } else if text.parse::<i64>().is_ok() {
+        ColumnType::Integer
+    } else if parse_float(text).is_some() {
+        ColumnType::Float
+    } else if is_date(text) {
+        ColumnType::Date
+    } else {
+        ColumnType::String
+    }
+}
+
+/// "inf" や "NaN" は数値として扱わない
+fn parse_float(text: &str) -> Option<f64> {
+    text.parse::<f64>().ok().filter(|number| number.is_finite() && text.bytes().any(|b| b.is_ascii_digit()))
+}
+
+fn number_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => parse_float(text.trim()),
+        _ => None,
+    }
+}
+
+fn is_date(text: &str) -> bool {
+    ["%Y-%m-%d", "%Y/%m/%d"].iter().any(|format| NaiveDate::parse_from_str(text, format).is_ok())
+        || ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y/%m/%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+            .iter()
+            .any(|format| NaiveDateTime::parse_from_str(text, format).is_ok())
+        || DateTime::parse_from_rfc3339(text).is_ok()
+}
+
+fn positional_name(index: usize) -> String {
+    format!("列{}", index + 1)
+}
+
+/// 形式・文字コード・区切り文字・見出し行を判定する
+fn inspect(path: &Path, options: &DataOptions) -> Result<Layout, String> {
+    if !path.is_file() {
+        return Err("ファイルが見つかりません".to_string());
+    }
+    let mut head = Vec::with_capacity(SAMPLE_BYTES);
+    File::open(path)
+        .and_then(|file| file.take(SAMPLE_BYTES as u64).read_to_end(&mut head))
+        .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+    let is_complete = head.len() < SAMPLE_BYTES;
+    let bom = Encoding::for_bom(&head);
+    let bom_length = bom.map_or(0, |(_, length)| length);
+    let body = &head[bom_length..];
+
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let format = match options.format {
+        Some(format) => format,
+        None => match extension.as_str() {
+            "jsonl" | "ndjson" => DataFormat::JsonLines,
+            "csv" | "tsv" | "tab" => DataFormat::Csv,
+            _ => sniff_format(body),
+        },
+    };
+
+    if format != DataFormat::Csv {
+        if bom.is_some_and(|(encoding, _)| encoding != UTF_8) {
+            return Err("JSON は UTF-8 で保存されている必要があります".to_string());
+        }
+        return Ok(Layout {
+            format,
+            delimiter: b',',
+            has_header: false,
+            encoding: UTF_8,
+            bom_length: bom_length as u64,
+            header_names: Vec::new(),
+        });
+    }
+
+    let encoding = match (&options.encoding, bom) {
+        (Some(label), _) => {
+            Encoding::for_label(label.as_bytes()).ok_or_else(|| format!("未対応の文字コードです: {}", label))?
+        }
+        (None, Some((encoding, _))) => encoding,
+        (None, None) => detect_encoding(body, is_complete),
+    };
+    check_csv_encoding(encoding)?;
+
+    let delimiter = match options.delimiter {
+        Some(delimiter) => csv_delimiter(delimiter, encoding)?,
+        None if extension == "tsv" || extension == "tab" => b'\t',
+        None => sniff_delimiter(&encoding.decode_without_bom_handling(body).0, is_complete, encoding),
+    };
+
+    let mut sample = Vec::new();
+    let mut reader = csv_reader(body, delimiter);
+    for record in reader.byte_records().take(HEADER_SAMPLE_ROWS + 1) {
+        let Ok(record) = record else { break };
+        sample.push(decode_record(&record, encoding));
+    }
+    // 途中で切れた最後の行は判定に使わない
+    if !is_complete && sample.len() > 1 {
+        sample.pop();
+    }
+    let has_header = options.has_header.unwrap_or_else(|| detect_header(&sample));
+    let header_names = match sample.first() {
+        Some(first) if has_header => first
+            .iter()
+            .enumerate()
+            .map(|(i, name)| if name.trim().is_empty() { positional_name(i) } else { name.trim().to_string() })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(Layout {
+        format,
+        delimiter,
+        has_header,
+        encoding,
+        bom_length: bom_length as u64,
+        header_names,
+    })
+}
+
+/// 拡張子で判断できない場合に、先頭の文字で JSON・JSON Lines・CSV を見分ける
+fn sniff_format(body: &[u8]) -> DataFormat {
+    let text = String::from_utf8_lossy(body);
+    let trimmed = text.trim_start();
+    if trimmed.starts_with('[') {
+        return DataFormat::Json;
+    }
+    if !trimmed.starts_with('{') {
+        return DataFormat::Csv;
+    }
+    // 1行目だけで値が完結し、次の行にも値があれば JSON Lines
+    let mut lines = trimmed.lines().filter(|line| !line.trim().is_empty());
+    let first_is_complete = lines.next().is_some_and(|line| serde_json::from_str::<Value>(line).is_ok());
+    let has_next = lines.next().is_some_and(|line| line.trim_start().starts_with(['{', '[']));
+    if first_is_complete && has_next {
+        DataFormat::JsonLines
+    } else {
+        DataFormat::Json
+    }
+}
+
+/// CSV をバイト単位で区切れる文字コードか確かめる
+///
+/// UTF-16 と ISO-2022-JP は区切り文字や引用符と同じバイトが文字の途中に現れるため扱えない。
+fn check_csv_encoding(encoding: &'static Encoding) -> Result<(), String> {
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        return Err("UTF-16 の CSV には対応していません".to_string());
+    }
+    if encoding == ISO_2022_JP {
+        return Err("ISO-2022-JP の CSV には対応していません".to_string());
+    }
+    Ok(())
+}
+
+/// 区切り文字をバイトに変換する
+///
+/// 区切りはデコード前のバイト列で探すため、`encoding` の多バイト文字の 2 バイト目以降に現れうるバイト
+/// （Shift_JIS の "|" など）は文字の一部と見分けられず、使えない。
+fn csv_delimiter(delimiter: char, encoding: &'static Encoding) -> Result<u8, String> {
+    if !delimiter.is_ascii() {
+        return Err("区切り文字は半角の1文字で指定してください".to_string());
+    }
+    let byte = delimiter as u8;
+    let ambiguous = if encoding == SHIFT_JIS || encoding == BIG5 {
+        (0x40..=0x7E).contains(&byte)
+    } else if encoding == GBK || encoding == GB18030 {
+        byte.is_ascii_digit() || (0x40..=0x7E).contains(&byte)
+    } else if encoding == EUC_KR {
+        byte.is_ascii_alphabetic()
+    } else {
+        false
+    };
+    if ambiguous {
+        return Err(format!("{} のファイルでは区切り文字 {:?} を使えません", encoding.name(), delimiter));
+    }
+    Ok(byte)
+}
+
+/// 各行での出現数が最もそろっている候補を区切り文字とする（`encoding` で使えない候補は除く）
+fn sniff_delimiter(text: &str, is_complete: bool, encoding: &'static Encoding) -> u8 {
+    let mut lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).take(HEADER_SAMPLE_ROWS + 1).collect();
+    if !is_complete && lines.len() > 1 {
+        lines.pop();
+    }
+
+    DELIMITER_CANDIDATES
+        .iter()
+        .filter(|&&delimiter| csv_delimiter(delimiter as char, encoding).is_ok())
+        .filter_map(|&delimiter| {
+            let counts: Vec<usize> = lines.iter().map(|line| count_unquoted(line, delimiter)).collect();
+            let first = *counts.first()?;
+            if first == 0 {
+                return None;
+            }
+            let consistent = counts.iter().filter(|&&count| count == first).count();
+            Some(((consistent, first), delimiter))
+        })
+        .max_by_key(|(score, _)| *score)
+        .map_or(b',', |(_, delimiter)| delimiter)
+}
+
+/// 引用符の外にある区切り文字の数
+fn count_unquoted(line: &str, delimiter: u8) -> usize {
+    let mut quoted = false;
+    line.bytes()
+        .filter(|&byte| {
+            if byte == b'"' {
+                quoted = !quoted;
+            }
+            !quoted && byte == delimiter
+        })
+        .count()
+}
+
+/// 1行目が見出しかどうかを推定する
+///
+/// 1行目がすべて空でない重複の無い文字列で、2行目以降に数値などの列があるか、
+/// 1行目の値が同じ列の他の行に現れなければ見出しとみなす。
+fn detect_header(rows: &[Vec<String>]) -> bool {
+    let Some((first, rest)) = rows.split_first() else {
+        return false;
+    };
+    if first.iter().any(|value| text_type(value) != ColumnType::String) {
+        return false;
+    }
+    let unique: HashSet<&String> = first.iter().collect();
+    if unique.len() != first.len() {
+        return false;
+    }
+    if rest.is_empty() {
+        return true;
+    }
+
+    let typed_column = (0..first.len()).any(|column| {
+        let merged = rest
+            .iter()
+            .filter_map(|row| row.get(column))
+            .fold(ColumnType::Empty, |merged, value| merged.merge(text_type(value)));
+        !matches!(merged, ColumnType::String | ColumnType::Empty)
+    });
+    let repeated = first
+        .iter()
+        .enumerate()
+        .any(|(column, value)| rest.iter().any(|row| row.get(column) == Some(value)));
+    typed_column || !repeated
+}
+
+fn csv_reader<R: Read>(reader: R, delimiter: u8) -> csv::Reader<R> {
+    csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader)
+}
+
+fn decode_record(record: &csv::ByteRecord, encoding: &'static Encoding) -> Vec<String> {
+    record
+        .iter()
+        .map(|field| encoding.decode_without_bom_handling(field).0.into_owned())
+        .collect()
+}
+
+/// 見出し行と先頭の行から列と型を求める
+fn sample_columns(path: &Path, layout: &Layout) -> Result<(ColumnSet, Vec<ColumnType>), String> {
+    let mut columns = ColumnSet::new(layout.header_names.clone());
+    let mut types = vec![ColumnType::Empty; columns.len()];
+    let mut sampled = 0;
+    for_each_record(path, layout, None, |record, _| {
+        let row = columns.row_values(record);
+        types.resize(row.len(), ColumnType::Empty);
+        for (column_type, value) in types.iter_mut().zip(&row) {
+            *column_type = column_type.merge(value_type(value, layout.format));
+        }
+        sampled += 1;
+        if sampled >= TYPE_SAMPLE_ROWS {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })?;
+    types.resize(columns.len(), ColumnType::Empty);
+    Ok((columns, types))
+}
+
+/// 見出し行を除いた行を `start`（省略時は先頭）から順に渡す（`Break` を返すと読み込みをやめる）
+///
+/// 各行と一緒に、その次の行を読み始める位置を渡す。
+fn for_each_record<F>(path: &Path, layout: &Layout, start: Option<ResumePoint>, mut on_record: F) -> Result<(), String>
+where
+    F: FnMut(Record, ResumePoint) -> ControlFlow<()>,
+{
+    let mut point = start.unwrap_or(ResumePoint { byte: layout.bom_length, line: 0 });
+    let mut file = File::open(path).map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+    file.seek(SeekFrom::Start(point.byte))
+        .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+    let mut reader = BufReader::new(file);
+
+    match layout.format {
+        DataFormat::Csv => {
+            let base = point.byte;
+            let mut reader = csv_reader(reader, layout.delimiter);
+            let mut record = csv::ByteRecord::new();
+            let mut skip_header = layout.has_header && start.is_none();
+            while reader
+                .read_byte_record(&mut record)
+                .map_err(|e| format!("CSV の解析に失敗しました: {}", e))?
+            {
+                if skip_header {
+                    skip_header = false;
+                    continue;
+                }
+                point.byte = base + reader.position().byte();
+                if on_record(Record::Fields(decode_record(&record, layout.encoding)), point).is_break() {
+                    break;
+                }
+            }
+            Ok(())
+        }
+        DataFormat::JsonLines => {
+            let mut line = String::new();
+            loop {
+                line.clear();
+                let read = reader
+                    .read_line(&mut line)
+                    .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+                if read == 0 {
+                    break;
+                }
+                point.byte += read as u64;
+                point.line += 1;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let value: Value = serde_json::from_str(&line)
+                    .map_err(|e| format!("JSON の解析に失敗しました（{} 行目）: {}", point.line, e))?;
+                if on_record(Record::Json(value), point).is_break() {
+                    break;
+                }
+            }
+            Ok(())
+        }
+        DataFormat::Json => {
+            // 続きから読む場合は配列の途中なので、区切りを読み飛ばして "[" を補う
+            let resumed = start.is_some();
+            if resumed {
+                point.byte += skip_separator(&mut reader).map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+            }
+            let position = Cell::new(point.byte);
+            let counting = CountingReader { inner: reader, position: &position };
+            let opening: &[u8] = if resumed { b"[" } else { b"" };
+            let mut stopped = false;
+            let mut deserializer = serde_json::Deserializer::from_reader(opening.chain(counting));
+            let result = RecordVisitor { on_record: &mut on_record, position: &position, stopped: &mut stopped }
+                .deserialize(&mut deserializer);
+            match result {
+                Err(_) if stopped => Ok(()),
+                Err(e) => Err(format!("JSON の解析に失敗しました: {}", e)),
+                Ok(true) => Ok(()),
+                Ok(false) => Err("配列を含まない JSON オブジェクトは表として表示できません".to_string()),
+            }
+        }
+    }
+}
+
+/// 配列の途中にある空白と要素の区切りの "," を読み飛ばし、読み飛ばしたバイト数を返す
+fn skip_separator<R: BufRead>(reader: &mut R) -> io::Result<u64> {
+    let mut skipped = 0;
+    let mut seen_comma = false;
+    loop {
+        let buffer = reader.fill_buf()?;
+        let length = buffer
+            .iter()
+            .take_while(|&&byte| match byte {
+                b',' if !seen_comma => {
+                    seen_comma = true;
+                    true
+                }
+                _ => byte.is_ascii_whitespace(),
+            })
+            .count();
+        let finished = length < buffer.len() || buffer.is_empty();
+        reader.consume(length);
+        skipped += length as u64;
+        if finished {
+            return Ok(skipped);
+        }
+    }
+}
+
+/// 読み込んだバイト数を数える
+///
+/// serde_json はリーダーから1バイトずつ読むため、要素を読み終えた時点の値が次の要素の位置になる
+/// （数値の直後だけは区切りを1文字先読みしているが、再開時に読み飛ばす区切りに含まれる）。
+struct CountingReader<'a, R> {
+    inner: R,
+    position: &'a Cell<u64>,
+}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buffer)?;
+        self.position.set(self.position.get() + read as u64);
+        Ok(read)
+    }
+}
+
+/// 最上位の配列の要素を1つずつ読み込んで渡す
+///
+/// 最上位がオブジェクトの場合は、最初に配列の値を持つキーの要素を行とする。
+/// 行として読む配列が見つからなければ false を返す。
+struct RecordVisitor<'a, F> {
+    on_record: &'a mut F,
+    position: &'a Cell<u64>,
+    stopped: &'a mut bool,
+}
+
+impl<'de, F> DeserializeSeed<'de> for RecordVisitor<'_, F>
+where
+    F: FnMut(Record, ResumePoint) -> ControlFlow<()>,
+{
+    type Value = bool;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<bool, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, F> Visitor<'de> for RecordVisitor<'_, F>
+where
+    F: FnMut(Record, ResumePoint) -> ControlFlow<()>,
+{
+    type Value = bool;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("配列またはオブジェクト")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<bool, A::Error> {
+        while let Some(value) = seq.next_element::<Value>()? {
+            let end = ResumePoint { byte: self.position.get(), line: 0 };
+            if (self.on_record)(Record::Json(value), end).is_break() {
+                // 残りを読まずに終えるため、エラーとして抜ける
+                *self.stopped = true;
+                return Err(de::Error::custom("stopped"));
+            }
+        }
+        Ok(true)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<bool, A::Error> {
+        let mut found = false;
+        while map.next_key::<de::IgnoredAny>()?.is_some() {
+            if found {
+                map.next_value::<de::IgnoredAny>()?;
+                continue;
+            }
+            found = map.next_value_seed(NestedRecords(RecordVisitor {
+                on_record: &mut *self.on_record,
+                position: self.position,
+                stopped: &mut *self.stopped,
+            }))?;
+        }
+        Ok(found)
+    }
+}
+
+/// オブジェクトの値のうち、配列なら行として読み、それ以外は読み飛ばす
+struct NestedRecords<'a, F>(RecordVisitor<'a, F>);
+
+impl<'de, F> DeserializeSeed<'de> for NestedRecords<'_, F>
+where
+    F: FnMut(Record, ResumePoint) -> ControlFlow<()>,
+{
+    type Value = bool;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<bool, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, F> Visitor<'de> for NestedRecords<'_, F>
+where
+    F: FnMut(Record, ResumePoint) -> ControlFlow<()>,
+{
+    type Value = bool;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("JSON の値")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<bool, A::Error> {
+        self.0.visit_seq(seq)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<bool, A::Error> {
+        while map.next_entry::<de::IgnoredAny, de::IgnoredAny>()?.is_some() {}
+        Ok(false)
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<bool, E> {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(temp_dir: &TempDir, name: &str, content: &[u8]) -> std::path::PathBuf {
+        let path = temp_dir.path().join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn column_types(page: &DataPage) -> Vec<ColumnType> {
+        page.columns.iter().map(|column| column.column_type).collect()
+    }
+
+    #[test]
+    fn test_csv_with_header_and_pages() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut content = String::from("\u{feff}id;name;price;active;date\n");
+        for i in 1..=25 {
+            content.push_str(&format!("{};\"item; {}\";{}.5;{};2024-01-{:02}\n", i, i, i, i % 2 == 0, i));
+        }
+        let path = write(&temp_dir, "items.txt", content.as_bytes());
+
+        let page = DataService::read_page(&path, &DataOptions::default(), 0, Some(10)).unwrap();
+        assert_eq!(page.format, DataFormat::Csv);
+        assert_eq!(page.delimiter, Some(';'));
+        assert!(page.has_header);
+        let names: Vec<&str> = page.columns.iter().map(|column| column.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "name", "price", "active", "date"]);
+        assert_eq!(
+            column_types(&page),
+            vec![ColumnType::Integer, ColumnType::String, ColumnType::Float, ColumnType::Boolean, ColumnType::Date]
+        );
+        assert_eq!(page.rows.len(), 10);
+        assert_eq!(page.rows[0][1], Value::String("item; 1".to_string()));
+        assert!(page.has_more);
+
+        let last = DataService::read_page(&path, &DataOptions::default(), 20, Some(10)).unwrap();
+        assert_eq!(last.rows.len(), 5);
+        assert_eq!(last.rows[0][0], Value::String("21".to_string()));
+        assert!(!last.has_more);
+    }
+
+    #[test]
+    fn test_csv_without_header_and_shift_jis() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write(&temp_dir, "numbers.csv", b"1,2,3\n4,5\n7,8,9,10\n");
+        let page = DataService::read_page(&path, &DataOptions::default(), 0, None).unwrap();
+        assert!(!page.has_header);
+        assert_eq!(page.columns.len(), 4);
+        assert_eq!(page.columns[3].name, "列4");
+        assert_eq!(page.rows[1], vec![Value::from("4"), Value::from("5"), Value::Null, Value::Null]);
+
+        let (encoded, _, _) = encoding_rs::SHIFT_JIS.encode("名前\t点数\n山田\t80\n佐藤\t95\n");
+        let path = write(&temp_dir, "scores.tsv", &encoded);
+        let page = DataService::read_page(&path, &DataOptions::default(), 0, None).unwrap();
+        assert_eq!(page.encoding, "Shift_JIS");
+        assert!(page.has_header);
+        assert_eq!(page.columns[0].name, "名前");
+        assert_eq!(page.rows[1][0], Value::from("佐藤"));
+
+        let forced = DataOptions { has_header: Some(false), ..Default::default() };
+        let page = DataService::read_page(&path, &forced, 0, None).unwrap();
+        assert_eq!(page.rows.len(), 3);
+        assert_eq!(page.columns[1].column_type, ColumnType::String);
+    }
+
+    #[test]
+    fn test_delimiter_that_can_be_a_trail_byte_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        // "ポ" の Shift_JIS は 0x83 0x7C で、2 バイト目が "|" と同じ
+        let (encoded, _, _) = encoding_rs::SHIFT_JIS.encode("名前,メモ\nポスト,表示\nノート,ポンプ\n");
+        assert!(encoded.contains(&b'|'));
+        let path = write(&temp_dir, "notes.csv", &encoded);
+
+        let page = DataService::read_page(&path, &DataOptions::default(), 0, None).unwrap();
+        assert_eq!(page.delimiter, Some(','));
+        assert_eq!(page.rows[0], vec![Value::from("ポスト"), Value::from("表示")]);
+
+        let piped = DataOptions { delimiter: Some('|'), ..Default::default() };
+        assert!(DataService::read_page(&path, &piped, 0, None).is_err());
+
+        // カーソルの区切り文字も同じように確かめる
+        let mut cursor = DataService::read_page(&path, &DataOptions::default(), 0, Some(1)).unwrap().next_cursor.unwrap();
+        cursor.delimiter = Some('|');
+        assert!(DataService::read_next_page(&path, &cursor, None).is_err());
+        cursor.delimiter = Some('、');
+        assert_eq!(
+            DataService::read_next_page(&path, &cursor, None).unwrap_err(),
+            "区切り文字は半角の1文字で指定してください"
+        );
+    }
+
+    #[test]
+    fn test_json_array_and_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write(
+            &temp_dir,
+            "users.json",
+            br#"[{"id": 1, "name": "a"}, {"id": 2, "tags": ["x"]}, {"id": 3.5, "name": null}, {"id": 4}]"#,
+        );
+        let page = DataService::read_page(&path, &DataOptions::default(), 1, Some(2)).unwrap();
+        assert_eq!(page.format, DataFormat::Json);
+        let names: Vec<&str> = page.columns.iter().map(|column| column.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "name", "tags"]);
+        assert_eq!(column_types(&page), vec![ColumnType::Float, ColumnType::String, ColumnType::Json]);
+        assert_eq!(page.rows[0], vec![Value::from(2), Value::Null, serde_json::json!(["x"])]);
+        assert!(page.has_more);
+
+        let path = write(&temp_dir, "events.log", b"{\"level\": \"info\", \"at\": \"2024-05-01T10:00:00Z\"}\n\n{\"level\": \"error\"}\n");
+        let page = DataService::read_page(&path, &DataOptions::default(), 0, None).unwrap();
+        assert_eq!(page.format, DataFormat::JsonLines);
+        assert_eq!(page.rows.len(), 2);
+        let at = page.columns.iter().find(|column| column.name == "at").unwrap();
+        assert_eq!(at.column_type, ColumnType::Date);
+
+        let broken = write(&temp_dir, "broken.jsonl", b"{\"a\": 1}\n{oops}\n");
+        assert!(DataService::read_page(&broken, &DataOptions::default(), 0, None).unwrap_err().contains("2 行目"));
+    }
+
+    /// `next_cursor` をたどって全ページを読み、各行の最初の値を集める
+    fn read_all_pages(path: &Path, limit: usize) -> Vec<Value> {
+        let mut page = DataService::read_page(path, &DataOptions::default(), 0, Some(limit)).unwrap();
+        let mut firsts: Vec<Value> = page.rows.iter().map(|row| row[0].clone()).collect();
+        while let Some(cursor) = page.next_cursor.take() {
+            assert_eq!(cursor.row, firsts.len());
+            page = DataService::read_next_page(path, &cursor, Some(limit)).unwrap();
+            assert_eq!(page.offset, cursor.row);
+            firsts.extend(page.rows.iter().map(|row| row[0].clone()));
+        }
+        assert!(!page.has_more);
+        firsts
+    }
+
+    #[test]
+    fn test_cursor_pages_match_offset_pages() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write(&temp_dir, "rows.csv", b"n,name\r\n1,\"a\nb\"\r\n2,c\r\n3,d\r\n4,e\r\n5,f\r\n");
+        let expected: Vec<Value> = (1..=5).map(|n| Value::from(n.to_string())).collect();
+        assert_eq!(read_all_pages(&path, 2), expected);
+
+        let path = write(&temp_dir, "rows.jsonl", b"{\"n\": 1}\n\n{\"n\": 2}\n{\"n\": 3}\n{\"n\": 4}\n");
+        assert_eq!(read_all_pages(&path, 2), (1..=4).map(Value::from).collect::<Vec<_>>());
+        // 空行も含めた行番号を引き継ぐ
+        let page = DataService::read_page(&path, &DataOptions::default(), 0, Some(2)).unwrap();
+        assert_eq!(page.next_cursor.unwrap().line, 3);
+
+        // 数値の要素は直後の区切りを先読みする
+        let path = write(&temp_dir, "numbers.json", b"[1, 2 ,3,\n 4, 5]");
+        assert_eq!(read_all_pages(&path, 2), (1..=5).map(Value::from).collect::<Vec<_>>());
+
+        let path = write(&temp_dir, "wrapped.json", br#"{"meta": {"items": [0]}, "count": 3, "data": [{"n": 1}, {"n": 2}, {"n": 3}], "more": [9]}"#);
+        assert_eq!(read_all_pages(&path, 1), (1..=3).map(Value::from).collect::<Vec<_>>());
+
+        let mut cursor = DataService::read_page(&path, &DataOptions::default(), 0, Some(1)).unwrap().next_cursor.unwrap();
+        cursor.file_size += 1;
+        assert!(DataService::read_next_page(&path, &cursor, None).unwrap_err().contains("ファイルが変更された"));
+    }
+
+    #[test]
+    fn test_json_object_without_array() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write(&temp_dir, "config.json", br#"{"name": "app", "options": {"list": [1]}}"#);
+        let error = DataService::read_page(&path, &DataOptions::default(), 0, None).unwrap_err();
+        assert_eq!(error, "配列を含まない JSON オブジェクトは表として表示できません");
+    }
+
+    #[test]
+    fn test_column_stats() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write(&temp_dir, "sales.csv", b"city,amount,note\nTokyo,120,\nOsaka,-5.5,x\nTokyo,300,\n");
+        let stats = DataService::column_stats(&path, &DataOptions::default()).unwrap();
+        assert_eq!(stats.total_rows, 3);
+
+        let city = &stats.columns[0];
+        assert_eq!(city.column_type, ColumnType::String);
+        assert_eq!(city.count, 3);
+        assert_eq!(city.distinct_count, 2);
+        assert_eq!(city.min, Some(Value::from("Osaka")));
+        assert_eq!(city.max, Some(Value::from("Tokyo")));
+
+        let amount = &stats.columns[1];
+        assert_eq!(amount.column_type, ColumnType::Float);
+        assert_eq!(amount.min, Some(Value::from("-5.5")));
+        assert_eq!(amount.max, Some(Value::from("300")));
+
+        let note = &stats.columns[2];
+        assert_eq!(note.count, 1);
+        assert_eq!(note.null_count, 2);
+
+        let path = write(&temp_dir, "rows.jsonl", b"{\"n\": 3}\n{\"n\": 10, \"extra\": true}\n");
+        let stats = DataService::column_stats(&path, &DataOptions::default()).unwrap();
+        assert_eq!(stats.columns[0].max, Some(Value::from(10)));
+        assert_eq!(stats.columns[1].null_count, 1);
+        assert_eq!(stats.columns[1].column_type, ColumnType::Boolean);
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use tauri_plugin_dialog::{DialogExt, FileDialogBuilder, FilePath};
+use crate::json_store::JsonStore;
+
+/// 用途ごとの最後に使ったディレクトリを保存するファイル名
+const DIALOG_STATE_FILE_NAME: &str = "dialog_directories.json";
+
+/// 保存ファイルの表示名（エラーメッセージ用）
+const DIALOG_STORE_LABEL: &str = "ダイアログ設定";
+
+/// 用途を指定しなかった場合の名前
+const DEFAULT_PURPOSE: &str = "default";
+
+/// ダイアログのファイル種別フィルター
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DialogFilter {
+    pub name: String,
+    /// ドットを付けない拡張子（例: "png"）
+    pub extensions: Vec<String>,
+}
+
+/// ファイル・フォルダ選択ダイアログの設定
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct OpenDialogOptions {
+    /// 最後に使ったディレクトリを覚えておく単位（例: "image", "archive"）
+    pub purpose: Option<String>,
+    pub title: Option<String>,
+    pub filters: Vec<DialogFilter>,
+    /// 最初に開くディレクトリ。省略時は用途ごとに最後に使ったディレクトリ
+    pub default_path: Option<String>,
+    pub multiple: bool,
+    /// true の場合はフォルダを選択する
+    pub directory: bool,
+}
+
+/// 保存ダイアログの設定
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SaveDialogOptions {
+    pub purpose: Option<String>,
+    pub title: Option<String>,
+    pub filters: Vec<DialogFilter>,
+    /// 最初に開くディレクトリ、またはファイル名を含む保存先
+    pub default_path: Option<String>,
+    /// 初期表示するファイル名（default_path のファイル名より優先）
+    pub file_name: Option<String>,
+}
+
+/// 保存ファイルの形式
+#[derive(Serialize, Deserialize, Default)]
+struct PersistedDirectories {
+    directories: HashMap<String, PathBuf>,
+}
+
+/// ファイル・フォルダの選択と保存先のダイアログを担当するサービスクラス
+///
+/// ダイアログはコールバック形式で開き、結果をチャネルで待つため、待っている間も
+/// 非同期ランタイムのスレッドを塞がない。
+pub struct DialogService {
+    last_directories: Mutex<HashMap<String, PathBuf>>,
+    store: JsonStore,
+}
+
+impl DialogService {
+    /// 用途ごとのディレクトリを `store_path` に保存するインスタンスを作成
+    pub fn new(store_path: Option<PathBuf>) -> Self {
+        let store = JsonStore::new(store_path, DIALOG_STORE_LABEL);
+        let persisted: PersistedDirectories = store.load_or_default();
+        Self {
+            last_directories: Mutex::new(persisted.directories),
+            store,
+        }
+    }
+
+    /// アプリデータディレクトリに状態を保存するインスタンスを作成
+    pub fn load(app_handle: &tauri::AppHandle) -> Result<Self, String> {
+        let app_dir = app_handle
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("アプリデータディレクトリの取得に失敗しました: {}", e))?;
+        Ok(Self::new(Some(app_dir.join(DIALOG_STATE_FILE_NAME))))
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<String, PathBuf>>, String> {
+        self.last_directories
+            .lock()
+            .map_err(|e| format!("ダイアログ状態のロックに失敗しました: {}", e))
+    }
+
+    /// ファイル・フォルダ選択ダイアログを開く。キャンセルされた場合は `None`
+    pub async fn open(&self, app: &tauri::AppHandle, options: &OpenDialogOptions) -> Result<Option<Vec<PathBuf>>, String> {
+        let purpose = options.purpose.as_deref();
+        let (directory, _) = self.initial_location(purpose, options.default_path.as_deref())?;
+        let builder = configure(app.dialog().file(), options.title.as_deref(), &options.filters, directory, None);
+
+        let (sender, mut receiver) = tauri::async_runtime::channel(1);
+        let send = move |paths: Option<Vec<FilePath>>| {
+            let _ = sender.try_send(paths);
+        };
+        match (options.directory, options.multiple) {
+            (true, true) => builder.pick_folders(send),
+            (true, false) => builder.pick_folder(move |path| send(path.map(|path| vec![path]))),
+            (false, true) => builder.pick_files(send),
+            (false, false) => builder.pick_file(move |path| send(path.map(|path| vec![path]))),
+        }
+
+        let Some(selected) = receiver.recv().await.flatten() else {
+            return Ok(None);
+        };
+        let paths = selected
+            .into_iter()
+            .map(|path| path.into_path().map_err(|e| format!("選択されたパスが不正です: {}", e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(first) = paths.first() {
+            let directory = if options.directory { first.parent().unwrap_or(first) } else { first };
+            self.remember(purpose, directory)?;
+        }
+        Ok(Some(paths))
+    }
+
+    /// 保存先選択ダイアログを開く。キャンセルされた場合は `None`
+    pub async fn save(&self, app: &tauri::AppHandle, options: &SaveDialogOptions) -> Result<Option<PathBuf>, String> {
+        let purpose = options.purpose.as_deref();
+        let (directory, default_name) = self.initial_location(purpose, options.default_path.as_deref())?;
+        let file_name = options.file_name.clone().or(default_name);
+        let builder = configure(app.dialog().file(), options.title.as_deref(), &options.filters, directory, file_name);
+
+        let (sender, mut receiver) = tauri::async_runtime::channel(1);
+        builder.save_file(move |path| {
+            let _ = sender.try_send(path);
+        });
+
+        let Some(selected) = receiver.recv().await.flatten() else {
+            return Ok(None);
+        };
+        let path = selected
+            .into_path()
+            .map_err(|e| format!("保存先のパスが不正です: {}", e))?;
+        self.remember(purpose, &path)?;
+        Ok(Some(path))
+    }
+
+    /// 最初に開くディレクトリと、指定されていればファイル名を決める
+    ///
+    /// `default_path` が既存のディレクトリならそこを、ファイルパスならその親とファイル名を使う。
+    /// 省略時は用途ごとに最後に使ったディレクトリ（存在する場合のみ）を使う。
+    pub fn initial_location(
+        &self,
+        purpose: Option<&str>,
+        default_path: Option<&str>,
+    ) -> Result<(Option<PathBuf>, Option<String>), String> {
+        if let Some(default_path) = default_path.filter(|path| !path.is_empty()) {
+            let path = Path::new(default_path);
+            if path.is_dir() {
+                return Ok((Some(path.to_path_buf()), None));
+            }
+            let directory = path.parent().filter(|parent| parent.is_dir()).map(Path::to_path_buf);
+            let file_name = path.file_name().map(|name| name.to_string_lossy().to_string());
+            return Ok((directory, file_name));
+        }
+
+        let remembered = self
+            .lock()?
+            .get(purpose.unwrap_or(DEFAULT_PURPOSE))
+            .filter(|dir| dir.is_dir())
+            .cloned();
+        Ok((remembered, None))
+    }
+
+    /// 選ばれたパスのディレクトリを用途ごとに記録し、保存する
+    pub fn remember(&self, purpose: Option<&str>, selected: &Path) -> Result<(), String> {
+        let directory = if selected.is_dir() {
+            selected
+        } else {
+            match selected.parent() {
+                Some(parent) => parent,
+                None => return Ok(()),
+            }
+        };
+
+        let mut directories = self.lock()?;
+        directories.insert(purpose.unwrap_or(DEFAULT_PURPOSE).to_string(), directory.to_path_buf());
+        self.store.save(&PersistedDirectories { directories: directories.clone() })
+    }
+}
+
+/// タイトル・フィルター・初期位置をダイアログに設定する
+fn configure(
+    mut builder: FileDialogBuilder<tauri::Wry>,
+    title: Option<&str>,
+    filters: &[DialogFilter],
+    directory: Option<PathBuf>,
+    file_name: Option<String>,
+) -> FileDialogBuilder<tauri::Wry> {
+    if let Some(title) = title {
+        builder = builder.set_title(title);
+    }
+    for filter in filters {
+        let extensions: Vec<&str> = filter.extensions.iter().map(|ext| ext.trim_start_matches('.')).collect();
+        builder = builder.add_filter(&filter.name, &extensions);
+    }
+    if let Some(directory) = directory {
+        builder = builder.set_directory(directory);
+    }
+    if let Some(file_name) = file_name {
+        builder = builder.set_file_name(file_name);
+    }
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn path_str(path: &Path) -> &str {
+        path.to_str().unwrap()
+    }
+
+    #[test]
+    fn test_initial_location_from_default_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = DialogService::new(None);
+
+        let (directory, name) = service.initial_location(None, Some(path_str(temp_dir.path()))).unwrap();
+        assert_eq!(directory.as_deref(), Some(temp_dir.path()));
+        assert!(name.is_none());
+
+        let target = temp_dir.path().join("report.json");
+        let (directory, name) = service.initial_location(None, Some(path_str(&target))).unwrap();
+        assert_eq!(directory.as_deref(), Some(temp_dir.path()));
+        assert_eq!(name.as_deref(), Some("report.json"));
+    }
+
+    #[test]
+    fn test_remembers_directory_per_purpose() {
+        let temp_dir = TempDir::new().unwrap();
+        let images = temp_dir.path().join("images");
+        let archives = temp_dir.path().join("archives");
+        fs::create_dir(&images).unwrap();
+        fs::create_dir(&archives).unwrap();
+        fs::write(images.join("photo.png"), b"png").unwrap();
+        let service = DialogService::new(None);
+
+        service.remember(Some("image"), &images.join("photo.png")).unwrap();
+        service.remember(Some("archive"), &archives).unwrap();
+
+        assert_eq!(service.initial_location(Some("image"), None).unwrap().0, Some(images));
+        assert_eq!(service.initial_location(Some("archive"), None).unwrap().0, Some(archives.clone()));
+        assert_eq!(service.initial_location(None, None).unwrap().0, None);
+
+        // 削除されたディレクトリは使わない
+        fs::remove_dir(&archives).unwrap();
+        assert_eq!(service.initial_location(Some("archive"), None).unwrap().0, None);
+    }
+
+    #[test]
+    fn test_persists_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let store_path = temp_dir.path().join("state/dialog_directories.json");
+
+        let service = DialogService::new(Some(store_path.clone()));
+        service.remember(None, temp_dir.path()).unwrap();
+        assert!(store_path.exists());
+
+        let reloaded = DialogService::new(Some(store_path));
+        assert_eq!(reloaded.initial_location(None, None).unwrap().0.as_deref(), Some(temp_dir.path()));
+    }
+}
@@ -0,0 +1,605 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+/// 集計完了時に送るイベント名
+pub const DISK_USAGE_FINISHED_EVENT: &str = "disk-usage-finished";
+
+/// ディレクトリごとに保持する大きいファイルの上限（top_n の上限でもある）
+const MAX_TOP_N: usize = 100;
+
+/// キャッシュに保持するディレクトリ数の上限
+const MAX_CACHED_DIRS: usize = 200_000;
+
+/// ディスク使用量の集計条件
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DiskUsageOptions {
+    /// 返すツリーの深さ（1 = ルート直下まで）
+    pub max_depth: usize,
+    /// 各ディレクトリで返す子の数。残りは other_size にまとめる
+    pub top_n: usize,
+}
+
+impl Default for DiskUsageOptions {
+    fn default() -> Self {
+        Self { max_depth: 3, top_n: 20 }
+    }
+}
+
+/// ツリーマップ用のノード
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DiskUsageNode {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    /// 配下を含む合計バイト数
+    pub size: u64,
+    /// 配下のファイル数（ファイル自身は 1）
+    pub file_count: u64,
+    /// 配下のディレクトリ数（自身は含まない）
+    pub dir_count: u64,
+    /// サイズの大きい順
+    pub children: Vec<DiskUsageNode>,
+    /// children に含めなかった残りの合計バイト数と件数
+    pub other_size: u64,
+    pub other_count: u64,
+}
+
+/// 集計結果
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DiskUsageReport {
+    pub root: DiskUsageNode,
+    /// 権限不足などで読めなかったエントリ数
+    pub skipped: u64,
+}
+
+/// disk-usage-finished イベントの内容
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DiskUsageFinished {
+    pub analysis_id: u64,
+    pub report: Option<DiskUsageReport>,
+    pub error: Option<String>,
+}
+
+/// ディレクトリ直下のファイルの合計サイズと最新の更新日時
+///
+/// ファイルの上書きは親ディレクトリの更新日時を変えないため、これを比べて検出する。
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct FilesSignature {
+    size: u64,
+    newest: Option<SystemTime>,
+}
+
+impl FilesSignature {
+    fn add(&mut self, metadata: &fs::Metadata) {
+        self.size += metadata.len();
+        self.newest = self.newest.max(metadata.modified().ok());
+    }
+
+    /// ディレクトリを読み直して直下のファイルを集計する。読めなければ None
+    fn read(dir: &Path) -> Option<Self> {
+        let mut signature = Self::default();
+        // 走査時と同じく、読めないエントリは数えない
+        for metadata in fs::read_dir(dir).ok()?.flatten().filter_map(|entry| entry.metadata().ok()) {
+            if !metadata.is_dir() {
+                signature.add(&metadata);
+            }
+        }
+        Some(signature)
+    }
+}
+
+/// ディレクトリ直下の内容（サブディレクトリの中身は含まない）
+#[derive(Debug)]
+struct DirListing {
+    modified: Option<SystemTime>,
+    files: FilesSignature,
+    /// 集計対象のサブディレクトリ（同じファイルシステムのもの）
+    subdirs: Vec<PathBuf>,
+    /// 直下の大きいファイル（MAX_TOP_N 件まで、大きい順）
+    largest_files: Vec<(String, u64)>,
+    /// largest_files に入らなかった直下のファイル
+    other_files_size: u64,
+    other_files_count: u64,
+    /// 読めなかった直下のエントリ数
+    skipped: u64,
+}
+
+impl DirListing {
+    /// ディレクトリを読み、直下のファイルとサブディレクトリを集める
+    fn read(dir: &Path, device: Option<u64>) -> Result<Self, String> {
+        // 読み込み前に更新日時を取得し、走査中の変更は次回の集計で拾う
+        let modified = directory_modified(dir);
+        let mut subdirs = Vec::new();
+        let mut files = Vec::new();
+        let mut signature = FilesSignature::default();
+        let mut skipped = 0;
+
+        let read_dir = fs::read_dir(dir).map_err(|e| format!("ディレクトリの読み込みに失敗しました: {}", e))?;
+        for entry in read_dir {
+            let Ok(entry) = entry else {
+                skipped += 1;
+                continue;
+            };
+            // DirEntry::metadata はシンボリックリンクを辿らない
+            let Ok(metadata) = entry.metadata() else {
+                skipped += 1;
+                continue;
+            };
+            if metadata.is_dir() {
+                if device.is_none() || metadata_device(&metadata) == device {
+                    subdirs.push(entry.path());
+                }
+            } else {
+                signature.add(&metadata);
+                files.push((entry.file_name().to_string_lossy().to_string(), metadata.len()));
+            }
+        }
+
+        files.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+        let other_files = files.split_off(files.len().min(MAX_TOP_N));
+        Ok(Self {
+            modified,
+            files: signature,
+            subdirs,
+            largest_files: files,
+            other_files_size: other_files.iter().map(|(_, size)| size).sum(),
+            other_files_count: other_files.len() as u64,
+            skipped,
+        })
+    }
+
+    /// 直下の内容が記録時から変わっていなければ true（サブディレクトリの中は見ない）
+    ///
+    /// ファイルの追加・削除・名前変更はディレクトリの更新日時で、既存ファイルの上書きは
+    /// 直下のファイルの合計サイズと最新の更新日時で検出する。
+    fn is_fresh(&self, dir: &Path) -> bool {
+        directory_modified(dir) == self.modified && FilesSignature::read(dir) == Some(self.files)
+    }
+
+    fn file_count(&self) -> u64 {
+        self.largest_files.len() as u64 + self.other_files_count
+    }
+}
+
+/// 走査済みのディレクトリ（キャッシュに保持する）
+///
+/// サブディレクトリはパスで持ち、キャッシュから引く。親が子を保持しないため、
+/// キャッシュから外したディレクトリのメモリはすぐに解放される。
+#[derive(Debug)]
+struct ScannedDir {
+    path: PathBuf,
+    listing: Arc<DirListing>,
+    /// 以下は配下を含む合計
+    size: u64,
+    file_count: u64,
+    dir_count: u64,
+    skipped: u64,
+    /// 走査できたサブディレクトリ
+    subdirs: Vec<PathBuf>,
+}
+
+impl ScannedDir {
+    /// ツリーマップ用のノードに変換する（サブディレクトリは `cache` から引く）
+    fn to_node(&self, cache: &HashMap<PathBuf, CacheEntry>, depth: usize, top_n: usize) -> DiskUsageNode {
+        let listing = &self.listing;
+        let mut node = DiskUsageNode {
+            name: entry_name(&self.path),
+            path: self.path.to_string_lossy().to_string(),
+            is_dir: true,
+            size: self.size,
+            file_count: self.file_count,
+            dir_count: self.dir_count,
+            children: Vec::new(),
+            other_size: listing.other_files_size,
+            other_count: listing.other_files_count,
+        };
+
+        if depth == 0 {
+            node.other_size = self.size;
+            node.other_count = self.subdirs.len() as u64 + listing.file_count();
+            return node;
+        }
+
+        let mut candidates: Vec<Child<'_>> = self
+            .subdirs
+            .iter()
+            .filter_map(|subdir| cache.get(subdir).map(|entry| Child::Dir(&entry.dir)))
+            .chain(listing.largest_files.iter().map(|(name, size)| Child::File(name, *size)))
+            .collect();
+        candidates.sort_by_key(|child| std::cmp::Reverse(child.size()));
+
+        for (index, child) in candidates.into_iter().enumerate() {
+            if index >= top_n {
+                node.other_size += child.size();
+                node.other_count += 1;
+                continue;
+            }
+            node.children.push(match child {
+                Child::Dir(subdir) => subdir.to_node(cache, depth - 1, top_n),
+                Child::File(name, size) => DiskUsageNode {
+                    name: name.to_string(),
+                    path: self.path.join(name).to_string_lossy().to_string(),
+                    is_dir: false,
+                    size,
+                    file_count: 1,
+                    dir_count: 0,
+                    children: Vec::new(),
+                    other_size: 0,
+                    other_count: 0,
+                },
+            });
+        }
+        node
+    }
+}
+
+/// キャッシュの項目。`used` は最後に使われた集計の世代
+struct CacheEntry {
+    dir: Arc<ScannedDir>,
+    used: u64,
+}
+
+/// ノードに変換する際の子の候補
+enum Child<'a> {
+    Dir(&'a ScannedDir),
+    File(&'a str, u64),
+}
+
+impl Child<'_> {
+    fn size(&self) -> u64 {
+        match self {
+            Child::Dir(subdir) => subdir.size,
+            Child::File(_, size) => *size,
+        }
+    }
+}
+
+/// ディレクトリ配下のサイズを並列に集計し、ツリーマップ用のデータを作るサービスクラス
+///
+/// 走査結果はディレクトリごとにキャッシュし、更新日時や直下のファイルが変わったディレクトリだけを
+/// 読み直す。キャッシュは `MAX_CACHED_DIRS` 件までで、古い集計で使われたものから捨てる。
+/// シンボリックリンクは辿らず、別のファイルシステムにまたがるディレクトリは集計しない。
+#[derive(Default)]
+pub struct DiskUsageService {
+    cache: Mutex<HashMap<PathBuf, CacheEntry>>,
+    /// 集計ごとに進める世代
+    generation: AtomicU64,
+    next_id: AtomicU64,
+    running: Mutex<HashMap<u64, Arc<AtomicBool>>>,
+}
+
+impl DiskUsageService {
+    /// ディレクトリ配下のサイズを集計する
+    pub fn analyze(&self, root: &Path, options: &DiskUsageOptions, cancel: &AtomicBool) -> Result<DiskUsageReport, String> {
+        if !root.is_dir() {
+            return Err("ディレクトリが見つかりません".to_string());
+        }
+
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        let scanned = self.scan(root, device_id(root), generation, cancel);
+        // ノードを作り終えてから上限を超えた分を捨てる
+        let report = scanned.and_then(|scanned| {
+            let cache = self
+                .cache
+                .lock()
+                .map_err(|e| format!("キャッシュのロックに失敗しました: {}", e))?;
+            Ok(DiskUsageReport {
+                root: scanned.to_node(&cache, options.max_depth, options.top_n.clamp(1, MAX_TOP_N)),
+                skipped: scanned.skipped,
+            })
+        });
+        self.evict(MAX_CACHED_DIRS);
+        report
+    }
+
+    /// キャッシュを破棄する。`path` を指定した場合はその配下だけを破棄する
+    pub fn clear_cache(&self, path: Option<&Path>) -> Result<(), String> {
+        let mut cache = self
+            .cache
+            .lock()
+            .map_err(|e| format!("キャッシュのロックに失敗しました: {}", e))?;
+        match path {
+            Some(path) => cache.retain(|cached, _| !cached.starts_with(path)),
+            None => cache.clear(),
+        }
+        Ok(())
+    }
+
+    /// バックグラウンドで集計を開始し、集計IDを返す
+    ///
+    /// 終了時に disk-usage-finished イベントを送る。
+    pub fn start(&self, app: tauri::AppHandle, root: PathBuf, options: DiskUsageOptions) -> Result<u64, String> {
+        let analysis_id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.lock_running()?.insert(analysis_id, cancel.clone());
+
+        std::thread::spawn(move || {
+            let Some(service) = tauri::Manager::try_state::<DiskUsageService>(&app) else { return };
+            let (report, error) = match service.analyze(&root, &options, &cancel) {
+                Ok(report) => (Some(report), None),
+                Err(e) => (None, Some(e)),
+            };
+            let _ = app.emit(DISK_USAGE_FINISHED_EVENT, DiskUsageFinished { analysis_id, report, error });
+
+            service.finish(analysis_id);
+        });
+
+        Ok(analysis_id)
+    }
+
+    /// 集計を中断する。該当する集計が無ければ false
+    pub fn cancel(&self, analysis_id: u64) -> Result<bool, String> {
+        match self.lock_running()?.get(&analysis_id) {
+            Some(cancel) => {
+                cancel.store(true, Ordering::Relaxed);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn finish(&self, analysis_id: u64) {
+        if let Ok(mut running) = self.running.lock() {
+            running.remove(&analysis_id);
+        }
+    }
+
+    fn lock_running(&self) -> Result<std::sync::MutexGuard<'_, HashMap<u64, Arc<AtomicBool>>>, String> {
+        self.running
+            .lock()
+            .map_err(|e| format!("集計状態のロックに失敗しました: {}", e))
+    }
+
+    /// キャッシュ済みの走査結果を取り出し、`generation` で使われたことを記録する
+    fn cached(&self, dir: &Path, generation: u64) -> Option<Arc<ScannedDir>> {
+        let mut cache = self.cache.lock().ok()?;
+        let entry = cache.get_mut(dir)?;
+        entry.used = generation;
+        Some(entry.dir.clone())
+    }
+
+    /// キャッシュが `limit` 件を超えていれば、古い世代・深い階層のものから捨てる
+    fn evict(&self, limit: usize) {
+        let Ok(mut cache) = self.cache.lock() else { return };
+        if cache.len() <= limit {
+            return;
+        }
+        let mut candidates: Vec<(u64, std::cmp::Reverse<usize>, PathBuf)> = cache
+            .iter()
+            .map(|(path, entry)| (entry.used, std::cmp::Reverse(path.components().count()), path.clone()))
+            .collect();
+        candidates.sort_unstable();
+        let excess = cache.len() - limit;
+        for (_, _, path) in candidates.into_iter().take(excess) {
+            cache.remove(&path);
+        }
+    }
+
+    /// ディレクトリを走査する。直下の内容が変わっていなければキャッシュを使い、サブディレクトリは並列に走査する
+    fn scan(&self, dir: &Path, device: Option<u64>, generation: u64, cancel: &AtomicBool) -> Result<Arc<ScannedDir>, String> {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("集計が中断されました".to_string());
+        }
+        let listing = match self.cached(dir, generation).filter(|cached| cached.listing.is_fresh(dir)) {
+            Some(cached) => cached.listing.clone(),
+            None => Arc::new(DirListing::read(dir, device)?),
+        };
+
+        let results: Vec<Result<Arc<ScannedDir>, String>> = listing
+            .subdirs
+            .par_iter()
+            .map(|path| self.scan(path, device, generation, cancel))
+            .collect();
+
+        let mut scanned = ScannedDir {
+            path: dir.to_path_buf(),
+            size: listing.files.size,
+            file_count: listing.file_count(),
+            dir_count: 0,
+            skipped: listing.skipped,
+            subdirs: Vec::new(),
+            listing: listing.clone(),
+        };
+        for result in results {
+            match result {
+                Ok(subdir) => {
+                    scanned.size += subdir.size;
+                    scanned.file_count += subdir.file_count;
+                    scanned.dir_count += 1 + subdir.dir_count;
+                    scanned.skipped += subdir.skipped;
+                    scanned.subdirs.push(subdir.path.clone());
+                }
+                Err(_) if cancel.load(Ordering::Relaxed) => return Err("集計が中断されました".to_string()),
+                // 読めないサブディレクトリは件数だけ数えて続ける
+                Err(_) => scanned.skipped += 1,
+            }
+        }
+
+        let scanned = Arc::new(scanned);
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(dir.to_path_buf(), CacheEntry { dir: scanned.clone(), used: generation });
+        }
+        Ok(scanned)
+    }
+}
+
+fn entry_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+fn directory_modified(dir: &Path) -> Option<SystemTime> {
+    fs::symlink_metadata(dir).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// ファイルシステムの識別子（取得できない環境では None となり、境界を判定しない）
+fn device_id(path: &Path) -> Option<u64> {
+    fs::metadata(path).ok().and_then(|metadata| metadata_device(&metadata))
+}
+
+#[cfg(unix)]
+fn metadata_device(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn metadata_device(_metadata: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn setup() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("videos/2024")).unwrap();
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(root.join("videos/2024/movie.mp4"), vec![0u8; 5000]).unwrap();
+        fs::write(root.join("videos/clip.mp4"), vec![0u8; 1000]).unwrap();
+        fs::write(root.join("docs/a.txt"), vec![0u8; 100]).unwrap();
+        fs::write(root.join("docs/b.txt"), vec![0u8; 50]).unwrap();
+        fs::write(root.join("readme.md"), vec![0u8; 10]).unwrap();
+        temp_dir
+    }
+
+    fn cached(service: &DiskUsageService, dir: &Path) -> Option<Arc<ScannedDir>> {
+        service.cache.lock().unwrap().get(dir).map(|entry| entry.dir.clone())
+    }
+
+    fn analyze(service: &DiskUsageService, root: &Path, options: &DiskUsageOptions) -> DiskUsageReport {
+        service.analyze(root, options, &AtomicBool::new(false)).unwrap()
+    }
+
+    #[test]
+    fn test_analyze_builds_sorted_tree() {
+        let temp_dir = setup();
+        let report = analyze(&DiskUsageService::default(), temp_dir.path(), &DiskUsageOptions::default());
+        let root = report.root;
+
+        assert_eq!(root.size, 6160);
+        assert_eq!(root.file_count, 5);
+        assert_eq!(root.dir_count, 3);
+        assert_eq!(
+            root.children.iter().map(|child| child.name.as_str()).collect::<Vec<_>>(),
+            vec!["videos", "docs", "readme.md"]
+        );
+
+        let videos = &root.children[0];
+        assert_eq!(videos.size, 6000);
+        assert_eq!(videos.children[0].name, "2024");
+        assert_eq!(videos.children[0].children[0].name, "movie.mp4");
+        assert_eq!(report.skipped, 0);
+    }
+
+    #[test]
+    fn test_top_n_and_depth_limit() {
+        let temp_dir = setup();
+        let service = DiskUsageService::default();
+
+        let report = analyze(&service, temp_dir.path(), &DiskUsageOptions { max_depth: 3, top_n: 1 });
+        assert_eq!(report.root.children.len(), 1);
+        assert_eq!(report.root.other_size, 160);
+        assert_eq!(report.root.other_count, 2);
+
+        let report = analyze(&service, temp_dir.path(), &DiskUsageOptions { max_depth: 1, top_n: 10 });
+        let videos = &report.root.children[0];
+        assert!(videos.children.is_empty());
+        assert_eq!(videos.other_size, 6000);
+        assert_eq!(videos.other_count, 2);
+    }
+
+    #[test]
+    fn test_cache_invalidated_by_mtime() {
+        let temp_dir = setup();
+        let service = DiskUsageService::default();
+        let options = DiskUsageOptions::default();
+
+        let first = analyze(&service, temp_dir.path(), &options);
+        let docs = cached(&service, &temp_dir.path().join("docs")).unwrap().listing.clone();
+        assert_eq!(analyze(&service, temp_dir.path(), &options).root, first.root);
+        assert!(Arc::ptr_eq(&docs, &cached(&service, &temp_dir.path().join("docs")).unwrap().listing));
+
+        // 更新日時の粒度が粗いファイルシステムでも変化が分かるように待つ
+        thread::sleep(Duration::from_millis(20));
+        fs::write(temp_dir.path().join("videos/2024/extra.bin"), vec![0u8; 2000]).unwrap();
+        let second = analyze(&service, temp_dir.path(), &options);
+        assert_eq!(second.root.size, first.root.size + 2000);
+        // 変更のないディレクトリは読み直さない
+        assert!(Arc::ptr_eq(&docs, &cached(&service, &temp_dir.path().join("docs")).unwrap().listing));
+
+        service.clear_cache(Some(&temp_dir.path().join("docs"))).unwrap();
+        assert!(cached(&service, &temp_dir.path().join("docs")).is_none());
+        assert!(cached(&service, temp_dir.path()).is_some());
+    }
+
+    #[test]
+    fn test_cache_invalidated_by_overwritten_file() {
+        let temp_dir = setup();
+        let service = DiskUsageService::default();
+        let options = DiskUsageOptions::default();
+
+        let first = analyze(&service, temp_dir.path(), &options);
+        // 既存ファイルの上書きはディレクトリの更新日時を変えない
+        thread::sleep(Duration::from_millis(20));
+        fs::write(temp_dir.path().join("docs/a.txt"), vec![0u8; 300]).unwrap();
+        let second = analyze(&service, temp_dir.path(), &options);
+        assert_eq!(second.root.size, first.root.size + 200);
+    }
+
+    #[test]
+    fn test_evict_keeps_recent_and_shallow_entries() {
+        let temp_dir = setup();
+        let service = DiskUsageService::default();
+        let options = DiskUsageOptions::default();
+
+        analyze(&service, &temp_dir.path().join("docs"), &options);
+        analyze(&service, &temp_dir.path().join("videos"), &options);
+        assert_eq!(service.cache.lock().unwrap().len(), 3);
+
+        service.evict(1);
+        assert!(cached(&service, &temp_dir.path().join("videos")).is_some());
+        assert!(cached(&service, &temp_dir.path().join("videos/2024")).is_none());
+        assert!(cached(&service, &temp_dir.path().join("docs")).is_none());
+    }
+
+    #[test]
+    fn test_evict_releases_memory() {
+        let temp_dir = setup();
+        let service = DiskUsageService::default();
+        analyze(&service, temp_dir.path(), &DiskUsageOptions::default());
+
+        let deep = Arc::downgrade(&cached(&service, &temp_dir.path().join("videos/2024")).unwrap());
+        service.evict(1);
+        // 親が残っていても、捨てたディレクトリはどこからも参照されない
+        assert!(cached(&service, temp_dir.path()).is_some());
+        assert!(deep.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_analyze_cancelled() {
+        let temp_dir = setup();
+        let result = DiskUsageService::default().analyze(temp_dir.path(), &DiskUsageOptions::default(), &AtomicBool::new(true));
+        assert_eq!(result.unwrap_err(), "集計が中断されました");
+    }
+
+    #[test]
+    fn test_analyze_nonexistent_directory() {
+        let result = DiskUsageService::default().analyze(Path::new("/nonexistent/directory"), &DiskUsageOptions::default(), &AtomicBool::new(false));
+        assert_eq!(result.unwrap_err(), "ディレクトリが見つかりません");
+    }
+}
@@ -0,0 +1,377 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+/// cgroup v1 でこの値以上のメモリ上限は「無制限」とみなす
+const CGROUP_V1_UNLIMITED: u64 = 1 << 60;
+
+/// コンテナ・仮想化環境の検出結果
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct EnvironmentInfo {
+    /// コンテナランタイム名（docker, podman, kubernetes, lxc, containerd など）
+    pub container: Option<String>,
+    /// ハイパーバイザー名（KVM, VMware, VirtualBox など）
+    pub hypervisor: Option<String>,
+    pub wsl: bool,
+    pub cgroup_version: Option<u8>,
+    /// cgroup で制限されたCPU数（コア数換算）
+    pub cpu_limit: Option<f64>,
+    /// cgroup で制限されたメモリ上限（バイト）
+    pub memory_limit: Option<u64>,
+    /// cgroup 内のメモリ使用量（バイト）
+    pub memory_usage: Option<u64>,
+}
+
+impl EnvironmentInfo {
+    /// ホストのメモリ量と cgroup の制限から、実際に使える総量と使用量を求める
+    ///
+    /// 上限がホストの物理メモリより小さい場合のみ cgroup の値を採用する。
+    pub fn effective_memory(&self, host_total: u64, host_used: u64) -> (u64, u64) {
+        match self.memory_limit {
+            Some(limit) if limit > 0 && (host_total == 0 || limit < host_total) => {
+                (limit, self.memory_usage.unwrap_or(host_used))
+            }
+            _ => (host_total, host_used),
+        }
+    }
+}
+
+/// コンテナ・仮想化環境の検出を担当するサービスクラス
+pub struct EnvironmentService;
+
+impl EnvironmentService {
+
+    /// 実行中の環境を検出
+    pub fn detect() -> EnvironmentInfo {
+        if cfg!(target_os = "linux") {
+            Self::detect_from_root(Path::new("/"))
+        } else {
+            EnvironmentInfo::default()
+        }
+    }
+
+    /// 指定したルートディレクトリ配下の /proc, /sys などを読んで環境を検出
+    ///
+    /// テストでは一時ディレクトリに疑似ファイルを置いて呼び出す。
+    pub fn detect_from_root(root: &Path) -> EnvironmentInfo {
+        let mut info = EnvironmentInfo {
+            container: Self::detect_container(root),
+            hypervisor: Self::detect_hypervisor(root),
+            wsl: Self::detect_wsl(root),
+            ..Default::default()
+        };
+
+        if root.join("sys/fs/cgroup/cgroup.controllers").exists() {
+            info.cgroup_version = Some(2);
+            let dir = Self::cgroup_v2_dir(root);
+            info.cpu_limit = read_trimmed(&dir.join("cpu.max")).and_then(|s| parse_cpu_max(&s));
+            info.memory_limit = read_trimmed(&dir.join("memory.max")).and_then(|s| parse_memory_limit(&s));
+        } else if root.join("sys/fs/cgroup/memory").is_dir() || root.join("sys/fs/cgroup/cpu").is_dir() {
+            info.cgroup_version = Some(1);
+            let cgroup = root.join("sys/fs/cgroup");
+            let quota = read_trimmed(&cgroup.join("cpu/cpu.cfs_quota_us"));
+            let period = read_trimmed(&cgroup.join("cpu/cpu.cfs_period_us"));
+            info.cpu_limit = match (quota, period) {
+                (Some(quota), Some(period)) => parse_cfs_quota(&quota, &period),
+                _ => None,
+            };
+            info.memory_limit = read_trimmed(&cgroup.join("memory/memory.limit_in_bytes"))
+                .and_then(|s| parse_memory_limit(&s));
+        }
+        info.memory_usage = Self::memory_usage_file(root).and_then(|file| Self::read_memory_usage(&file));
+
+        info
+    }
+
+    /// cgroup のメモリ使用量が書かれたファイルを求める（cgroup がない場合は `None`）
+    ///
+    /// 使用量は刻々と変わるため、定期更新ではこのファイルだけを読み直す。
+    pub fn memory_usage_file(root: &Path) -> Option<PathBuf> {
+        if root.join("sys/fs/cgroup/cgroup.controllers").exists() {
+            Some(Self::cgroup_v2_dir(root).join("memory.current"))
+        } else if root.join("sys/fs/cgroup/memory").is_dir() {
+            Some(root.join("sys/fs/cgroup/memory/memory.usage_in_bytes"))
+        } else {
+            None
+        }
+    }
+
+    /// cgroup のメモリ使用量（バイト）を読む
+    pub fn read_memory_usage(file: &Path) -> Option<u64> {
+        read_trimmed(file).and_then(|s| s.parse().ok())
+    }
+
+    /// 自プロセスが属する cgroup v2 のディレクトリを求める
+    ///
+    /// コンテナ内では通常ルート (`0::/`) になるが、systemd 配下などではサブディレクトリを指す。
+    fn cgroup_v2_dir(root: &Path) -> PathBuf {
+        let base = root.join("sys/fs/cgroup");
+        let relative = read_trimmed(&root.join("proc/self/cgroup")).and_then(|content| {
+            content
+                .lines()
+                .find_map(|line| line.strip_prefix("0::"))
+                .map(|path| path.trim_start_matches('/').to_string())
+        });
+
+        match relative {
+            Some(relative) if !relative.is_empty() && base.join(&relative).is_dir() => base.join(relative),
+            _ => base,
+        }
+    }
+
+    /// マーカーファイルと cgroup の内容からコンテナランタイムを判定
+    fn detect_container(root: &Path) -> Option<String> {
+        if root.join(".dockerenv").exists() {
+            return Some("docker".to_string());
+        }
+        if root.join("run/.containerenv").exists() {
+            return Some("podman".to_string());
+        }
+        if let Some(kind) = read_trimmed(&root.join("run/systemd/container")) {
+            if !kind.is_empty() {
+                return Some(kind);
+            }
+        }
+
+        read_trimmed(&root.join("proc/1/cgroup"))
+            .and_then(|content| container_from_cgroup(&content).map(str::to_string))
+    }
+
+    /// CPUフラグとDMI情報からハイパーバイザーを判定
+    fn detect_hypervisor(root: &Path) -> Option<String> {
+        let vendor = read_trimmed(&root.join("sys/class/dmi/id/sys_vendor")).unwrap_or_default();
+        let product = read_trimmed(&root.join("sys/class/dmi/id/product_name")).unwrap_or_default();
+        if let Some(name) = hypervisor_from_dmi(&vendor, &product) {
+            return Some(name.to_string());
+        }
+
+        if let Some(kind) = read_trimmed(&root.join("sys/hypervisor/type")) {
+            if !kind.is_empty() {
+                return Some(kind);
+            }
+        }
+
+        let has_flag = read_trimmed(&root.join("proc/cpuinfo"))
+            .map(|cpuinfo| cpuinfo_has_hypervisor_flag(&cpuinfo))
+            .unwrap_or(false);
+        has_flag.then(|| "unknown".to_string())
+    }
+
+    /// カーネルのリリース文字列から WSL を判定
+    fn detect_wsl(root: &Path) -> bool {
+        read_trimmed(&root.join("proc/sys/kernel/osrelease"))
+            .map(|release| {
+                let release = release.to_lowercase();
+                release.contains("microsoft") || release.contains("wsl")
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// ファイルを読み込んで前後の空白を除去する。読めない場合は None
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// /proc/1/cgroup の内容からコンテナランタイムを推定
+fn container_from_cgroup(content: &str) -> Option<&'static str> {
+    const MARKERS: [(&str, &str); 5] = [
+        ("kubepods", "kubernetes"),
+        ("docker", "docker"),
+        ("libpod", "podman"),
+        ("containerd", "containerd"),
+        ("lxc", "lxc"),
+    ];
+
+    content.lines().find_map(|line| {
+        MARKERS
+            .iter()
+            .find(|(marker, _)| line.contains(marker))
+            .map(|(_, name)| *name)
+    })
+}
+
+/// DMI のベンダー名・製品名からハイパーバイザー名を推定
+fn hypervisor_from_dmi(vendor: &str, product: &str) -> Option<&'static str> {
+    let text = format!("{} {}", vendor, product).to_lowercase();
+    if text.contains("kvm") {
+        Some("KVM")
+    } else if text.contains("qemu") {
+        Some("QEMU")
+    } else if text.contains("vmware") {
+        Some("VMware")
+    } else if text.contains("virtualbox") || text.contains("innotek") {
+        Some("VirtualBox")
+    } else if text.contains("xen") {
+        Some("Xen")
+    } else if text.contains("microsoft") && text.contains("virtual machine") {
+        Some("Hyper-V")
+    } else if text.contains("amazon ec2") {
+        Some("Amazon EC2")
+    } else if text.contains("google compute engine") {
+        Some("Google Compute Engine")
+    } else if text.contains("parallels") {
+        Some("Parallels")
+    } else {
+        None
+    }
+}
+
+/// /proc/cpuinfo の flags に hypervisor が含まれるか
+fn cpuinfo_has_hypervisor_flag(cpuinfo: &str) -> bool {
+    cpuinfo
+        .lines()
+        .filter(|line| line.starts_with("flags"))
+        .any(|line| line.split_whitespace().any(|flag| flag == "hypervisor"))
+}
+
+/// cgroup v2 の cpu.max（"quota period" または "max period"）をコア数に変換
+fn parse_cpu_max(content: &str) -> Option<f64> {
+    let mut parts = content.split_whitespace();
+    let quota = parts.next()?;
+    let period: f64 = parts.next().unwrap_or("100000").parse().ok()?;
+    if quota == "max" || period <= 0.0 {
+        return None;
+    }
+    let quota: f64 = quota.parse().ok()?;
+    Some(quota / period)
+}
+
+/// cgroup v1 の cfs_quota_us / cfs_period_us をコア数に変換（-1 は無制限）
+fn parse_cfs_quota(quota: &str, period: &str) -> Option<f64> {
+    let quota: i64 = quota.parse().ok()?;
+    let period: i64 = period.parse().ok()?;
+    if quota <= 0 || period <= 0 {
+        return None;
+    }
+    Some(quota as f64 / period as f64)
+}
+
+/// memory.max / memory.limit_in_bytes をバイト数に変換（無制限は None）
+fn parse_memory_limit(content: &str) -> Option<u64> {
+    if content == "max" {
+        return None;
+    }
+    content
+        .parse::<u64>()
+        .ok()
+        .filter(|limit| *limit < CGROUP_V1_UNLIMITED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, relative: &str, content: &str) {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_bare_metal_has_no_container_or_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        write(temp_dir.path(), "proc/cpuinfo", "flags\t\t: fpu vme sse2\n");
+
+        let info = EnvironmentService::detect_from_root(temp_dir.path());
+        assert_eq!(info, EnvironmentInfo::default());
+    }
+
+    #[test]
+    fn test_docker_with_cgroup_v2_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(root, ".dockerenv", "");
+        write(root, "proc/self/cgroup", "0::/\n");
+        write(root, "sys/fs/cgroup/cgroup.controllers", "cpu memory\n");
+        write(root, "sys/fs/cgroup/cpu.max", "150000 100000\n");
+        write(root, "sys/fs/cgroup/memory.max", "536870912\n");
+        write(root, "sys/fs/cgroup/memory.current", "134217728\n");
+
+        let info = EnvironmentService::detect_from_root(root);
+        assert_eq!(info.container.as_deref(), Some("docker"));
+        assert_eq!(info.cgroup_version, Some(2));
+        assert_eq!(info.cpu_limit, Some(1.5));
+        assert_eq!(info.memory_limit, Some(536_870_912));
+        assert_eq!(info.memory_usage, Some(134_217_728));
+
+        let usage_file = EnvironmentService::memory_usage_file(root).unwrap();
+        assert_eq!(usage_file, root.join("sys/fs/cgroup/memory.current"));
+        write(root, "sys/fs/cgroup/memory.current", "268435456\n");
+        assert_eq!(EnvironmentService::read_memory_usage(&usage_file), Some(268_435_456));
+    }
+
+    #[test]
+    fn test_cgroup_v2_unlimited_and_nested_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(root, "proc/self/cgroup", "0::/user.slice/app.scope\n");
+        write(root, "sys/fs/cgroup/cgroup.controllers", "cpu memory\n");
+        write(root, "sys/fs/cgroup/user.slice/app.scope/cpu.max", "max 100000\n");
+        write(root, "sys/fs/cgroup/user.slice/app.scope/memory.max", "max\n");
+        write(root, "sys/fs/cgroup/user.slice/app.scope/memory.current", "1024\n");
+
+        let info = EnvironmentService::detect_from_root(root);
+        assert_eq!(info.cpu_limit, None);
+        assert_eq!(info.memory_limit, None);
+        assert_eq!(info.memory_usage, Some(1024));
+    }
+
+    #[test]
+    fn test_kubernetes_with_cgroup_v1_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(root, "proc/1/cgroup", "12:memory:/kubepods/burstable/pod1234/abcd\n");
+        write(root, "sys/fs/cgroup/cpu/cpu.cfs_quota_us", "50000\n");
+        write(root, "sys/fs/cgroup/cpu/cpu.cfs_period_us", "100000\n");
+        write(root, "sys/fs/cgroup/memory/memory.limit_in_bytes", "9223372036854771712\n");
+
+        let info = EnvironmentService::detect_from_root(root);
+        assert_eq!(info.container.as_deref(), Some("kubernetes"));
+        assert_eq!(info.cgroup_version, Some(1));
+        assert_eq!(info.cpu_limit, Some(0.5));
+        assert_eq!(info.memory_limit, None);
+    }
+
+    #[test]
+    fn test_hypervisor_and_wsl_detection() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(root, "sys/class/dmi/id/sys_vendor", "QEMU\n");
+        write(root, "sys/class/dmi/id/product_name", "Standard PC (Q35 + ICH9, 2009)\n");
+        write(root, "proc/sys/kernel/osrelease", "5.15.90.1-microsoft-standard-WSL2\n");
+
+        let info = EnvironmentService::detect_from_root(root);
+        assert_eq!(info.hypervisor.as_deref(), Some("QEMU"));
+        assert!(info.wsl);
+    }
+
+    #[test]
+    fn test_hypervisor_flag_without_dmi() {
+        let temp_dir = TempDir::new().unwrap();
+        write(temp_dir.path(), "proc/cpuinfo", "flags\t\t: fpu sse2 hypervisor\n");
+
+        let info = EnvironmentService::detect_from_root(temp_dir.path());
+        assert_eq!(info.hypervisor.as_deref(), Some("unknown"));
+    }
+
+    #[test]
+    fn test_effective_memory_uses_cgroup_limit_when_smaller() {
+        let limited = EnvironmentInfo {
+            memory_limit: Some(1_000),
+            memory_usage: Some(250),
+            ..Default::default()
+        };
+        assert_eq!(limited.effective_memory(8_000, 4_000), (1_000, 250));
+
+        let larger_than_host = EnvironmentInfo {
+            memory_limit: Some(16_000),
+            memory_usage: Some(250),
+            ..Default::default()
+        };
+        assert_eq!(larger_than_host.effective_memory(8_000, 4_000), (8_000, 4_000));
+
+        assert_eq!(EnvironmentInfo::default().effective_memory(8_000, 4_000), (8_000, 4_000));
+    }
+}
@@ -0,0 +1,950 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use chrono::Local;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use crate::progress::{Progress, ProgressTracker};
+
+/// コピー・移動の進捗を送るイベント名
+pub const FILE_OPERATION_PROGRESS_EVENT: &str = "file-operation-progress";
+
+/// 取り消せる操作の履歴数
+const MAX_HISTORY: usize = 20;
+
+/// コピー時の読み書き単位
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// .trashinfo の Path に使うエンコード対象（スラッシュと非予約文字は残す）
+const TRASH_PATH_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// 同名のファイルが既にある場合の扱い
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// 既存のファイルを残して飛ばす
+    #[default]
+    Skip,
+    /// 既存のファイルをゴミ箱に移してから置き換える（取り消しで元に戻せる）
+    Overwrite,
+    /// 「名前 (1).拡張子」のように別名を付ける
+    Rename,
+}
+
+/// 操作の種類
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Mkdir,
+    Rename,
+    Copy,
+    Move,
+    Trash,
+}
+
+/// file-operation-progress イベントの内容
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OperationProgress {
+    pub operation_id: u64,
+    pub kind: OperationKind,
+    pub current_path: String,
+    pub processed_bytes: u64,
+    pub total_bytes: u64,
+    pub processed_files: usize,
+    pub total_files: usize,
+}
+
+/// コピー・移動・削除の結果
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct OperationOutcome {
+    pub operation_id: u64,
+    /// 作成・移動後のパス（ゴミ箱の場合は元のパス）
+    pub completed: Vec<String>,
+    /// 競合のため飛ばしたパス
+    pub skipped: Vec<String>,
+}
+
+/// 取り消し候補として表示する操作の概要
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OperationSummary {
+    pub kind: OperationKind,
+    pub paths: Vec<String>,
+}
+
+/// ゴミ箱に入れた1件（取り消し用）
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrashedItem {
+    pub original: PathBuf,
+    pub trashed: PathBuf,
+    pub info: PathBuf,
+}
+
+/// 取り消しに必要な情報を持つ操作の記録
+#[derive(Clone, Debug)]
+enum OperationRecord {
+    Mkdir { path: PathBuf },
+    Rename { from: PathBuf, to: PathBuf },
+    Copy { created: Vec<PathBuf>, replaced: Vec<TrashedItem> },
+    Move { moves: Vec<(PathBuf, PathBuf)>, replaced: Vec<TrashedItem> },
+    Trash { items: Vec<TrashedItem> },
+}
+
+impl OperationRecord {
+    fn summary(&self) -> OperationSummary {
+        let to_strings = |paths: Vec<&PathBuf>| paths.iter().map(|p| p.to_string_lossy().to_string()).collect();
+        match self {
+            OperationRecord::Mkdir { path } => OperationSummary { kind: OperationKind::Mkdir, paths: to_strings(vec![path]) },
+            OperationRecord::Rename { from, to } => OperationSummary { kind: OperationKind::Rename, paths: to_strings(vec![from, to]) },
+            OperationRecord::Copy { created, .. } => OperationSummary { kind: OperationKind::Copy, paths: to_strings(created.iter().collect()) },
+            OperationRecord::Move { moves, .. } => OperationSummary { kind: OperationKind::Move, paths: to_strings(moves.iter().map(|(_, to)| to).collect()) },
+            OperationRecord::Trash { items } => OperationSummary { kind: OperationKind::Trash, paths: to_strings(items.iter().map(|item| &item.original).collect()) },
+        }
+    }
+}
+
+impl OperationProgress {
+    /// `sources` 配下の合計を数えた、処理前の進捗
+    fn start(operation_id: u64, kind: OperationKind, sources: &[PathBuf]) -> Self {
+        let (total_bytes, total_files) = sources
+            .iter()
+            .map(|source| measure(source))
+            .fold((0, 0), |(bytes, files), (b, f)| (bytes + b, files + f));
+        Self {
+            operation_id,
+            kind,
+            current_path: String::new(),
+            processed_bytes: 0,
+            total_bytes,
+            processed_files: 0,
+            total_files,
+        }
+    }
+}
+
+impl Progress for OperationProgress {
+    fn set_current_path(&mut self, path: String) {
+        self.current_path = path;
+    }
+
+    fn add_processed(&mut self, files: usize, bytes: u64) {
+        self.processed_files += files;
+        self.processed_bytes += bytes;
+    }
+}
+
+/// ファイルの作成・名前変更・コピー・移動・ゴミ箱への削除と、その取り消しを担当するサービスクラス
+pub struct FileOperationService {
+    trash_dir: PathBuf,
+    history: Mutex<Vec<OperationRecord>>,
+    next_id: AtomicU64,
+}
+
+impl FileOperationService {
+    /// freedesktop 仕様のホームのゴミ箱（$XDG_DATA_HOME/Trash）を使うインスタンスを作成
+    pub fn new() -> Result<Self, String> {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+            .ok_or_else(|| "ホームディレクトリの取得に失敗しました".to_string())?;
+        Ok(Self::with_trash_dir(data_home.join("Trash")))
+    }
+
+    /// ゴミ箱ディレクトリを指定して作成
+    pub fn with_trash_dir(trash_dir: PathBuf) -> Self {
+        Self {
+            trash_dir,
+            history: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    fn lock_history(&self) -> Result<MutexGuard<'_, Vec<OperationRecord>>, String> {
+        self.history
+            .lock()
+            .map_err(|e| format!("操作履歴のロックに失敗しました: {}", e))
+    }
+
+    fn record(&self, record: OperationRecord) -> Result<(), String> {
+        let mut history = self.lock_history()?;
+        history.push(record);
+        if history.len() > MAX_HISTORY {
+            history.remove(0);
+        }
+        Ok(())
+    }
+
+    fn next_operation_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// ディレクトリを作成
+    pub fn create_directory(&self, parent: &Path, name: &str) -> Result<String, String> {
+        validate_name(name)?;
+        let path = parent.join(name);
+        if path.exists() {
+            return Err(format!("同じ名前のファイルが既にあります: {}", name));
+        }
+        fs::create_dir(&path)
+            .map_err(|e| format!("ディレクトリの作成に失敗しました: {}", e))?;
+        self.record(OperationRecord::Mkdir { path: path.clone() })?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    /// 同じディレクトリ内で名前を変更
+    pub fn rename(&self, path: &Path, new_name: &str) -> Result<String, String> {
+        validate_name(new_name)?;
+        if fs::symlink_metadata(path).is_err() {
+            return Err("ファイルが見つかりません".to_string());
+        }
+        let target = path.with_file_name(new_name);
+        if target == path {
+            return Ok(target.to_string_lossy().to_string());
+        }
+        if fs::symlink_metadata(&target).is_ok() {
+            return Err(format!("同じ名前のファイルが既にあります: {}", new_name));
+        }
+        fs::rename(path, &target)
+            .map_err(|e| format!("名前の変更に失敗しました: {}", e))?;
+        self.record(OperationRecord::Rename { from: path.to_path_buf(), to: target.clone() })?;
+        Ok(target.to_string_lossy().to_string())
+    }
+
+    /// ファイル・ディレクトリを `dest_dir` に再帰的にコピー
+    pub fn copy<F>(&self, sources: &[PathBuf], dest_dir: &Path, policy: ConflictPolicy, mut on_progress: F) -> Result<OperationOutcome, String>
+    where
+        F: FnMut(&OperationProgress),
+    {
+        validate_transfer(sources, dest_dir)?;
+        let operation_id = self.next_operation_id();
+        let mut tracker = ProgressTracker::new(OperationProgress::start(operation_id, OperationKind::Copy, sources), &mut on_progress);
+        let mut outcome = OperationOutcome { operation_id, ..Default::default() };
+        let mut created = Vec::new();
+        let mut replaced = Vec::new();
+
+        let result: Result<(), String> = (|| {
+            for source in sources {
+                // 同じ場所へのコピーは複製として別名を付ける
+                let policy = if source.parent() == Some(dest_dir) { ConflictPolicy::Rename } else { policy };
+                let Some(target) = self.resolve_conflict(source, dest_dir, policy, &mut replaced)? else {
+                    outcome.skipped.push(source.to_string_lossy().to_string());
+                    continue;
+                };
+                if let Err(e) = copy_recursive(source, &target, &mut tracker) {
+                    // 中途半端なコピーは残さない
+                    let _ = remove_path(&target);
+                    return Err(e);
+                }
+                outcome.completed.push(target.to_string_lossy().to_string());
+                created.push(target);
+            }
+            Ok(())
+        })();
+
+        tracker.report(true);
+        // 途中で失敗しても、それまでの結果は取り消せるように記録する
+        if !created.is_empty() || !replaced.is_empty() {
+            self.record(OperationRecord::Copy { created, replaced })?;
+        }
+        result.map(|_| outcome)
+    }
+
+    /// ファイル・ディレクトリを `dest_dir` に移動（別デバイスへはコピー後に削除）
+    pub fn move_items<F>(&self, sources: &[PathBuf], dest_dir: &Path, policy: ConflictPolicy, mut on_progress: F) -> Result<OperationOutcome, String>
+    where
+        F: FnMut(&OperationProgress),
+    {
+        validate_transfer(sources, dest_dir)?;
+        let operation_id = self.next_operation_id();
+        let mut tracker = ProgressTracker::new(OperationProgress::start(operation_id, OperationKind::Move, sources), &mut on_progress);
+        let mut outcome = OperationOutcome { operation_id, ..Default::default() };
+        let mut moves = Vec::new();
+        let mut replaced = Vec::new();
+
+        let result: Result<(), String> = (|| {
+            for source in sources {
+                if source.parent() == Some(dest_dir) {
+                    outcome.skipped.push(source.to_string_lossy().to_string());
+                    continue;
+                }
+                let Some(target) = self.resolve_conflict(source, dest_dir, policy, &mut replaced)? else {
+                    outcome.skipped.push(source.to_string_lossy().to_string());
+                    continue;
+                };
+                move_path(source, &target, &mut tracker)?;
+                outcome.completed.push(target.to_string_lossy().to_string());
+                moves.push((source.clone(), target));
+            }
+            Ok(())
+        })();
+
+        tracker.report(true);
+        if !moves.is_empty() || !replaced.is_empty() {
+            self.record(OperationRecord::Move { moves, replaced })?;
+        }
+        result.map(|_| outcome)
+    }
+
+    /// ファイル・ディレクトリをゴミ箱に移動
+    pub fn trash(&self, paths: &[PathBuf]) -> Result<OperationOutcome, String> {
+        let operation_id = self.next_operation_id();
+        let mut outcome = OperationOutcome { operation_id, ..Default::default() };
+        let mut items = Vec::new();
+
+        let result: Result<(), String> = (|| {
+            for path in paths {
+                let item = self.trash_path(path)?;
+                outcome.completed.push(path.to_string_lossy().to_string());
+                items.push(item);
+            }
+            Ok(())
+        })();
+
+        if !items.is_empty() {
+            self.record(OperationRecord::Trash { items })?;
+        }
+        result.map(|_| outcome)
+    }
+
+    /// 直前の操作の概要（取り消しボタンの表示用）
+    pub fn last_operation(&self) -> Result<Option<OperationSummary>, String> {
+        Ok(self.lock_history()?.last().map(|record| record.summary()))
+    }
+
+    /// 直前の操作を取り消す。履歴が無ければ None
+    pub fn undo_last(&self) -> Result<Option<OperationSummary>, String> {
+        let Some(record) = self.lock_history()?.pop() else {
+            return Ok(None);
+        };
+        let summary = record.summary();
+
+        let result = match &record {
+            OperationRecord::Mkdir { path } => fs::remove_dir(path)
+                .map_err(|e| format!("ディレクトリの削除に失敗しました（空でない可能性があります）: {}", e)),
+            OperationRecord::Rename { from, to } => {
+                if fs::symlink_metadata(from).is_ok() {
+                    Err(format!("元の名前のファイルが既にあります: {}", from.display()))
+                } else {
+                    fs::rename(to, from).map_err(|e| format!("名前の変更に失敗しました: {}", e))
+                }
+            }
+            OperationRecord::Copy { created, replaced } => created
+                .iter()
+                .rev()
+                .try_for_each(|path| remove_path(path))
+                .and_then(|_| replaced.iter().try_for_each(restore_trashed)),
+            OperationRecord::Move { moves, replaced } => moves
+                .iter()
+                .rev()
+                .try_for_each(|(from, to)| {
+                    if fs::symlink_metadata(from).is_ok() {
+                        return Err(format!("元の場所に同じ名前のファイルが既にあります: {}", from.display()));
+                    }
+                    move_quietly(to, from)
+                })
+                .and_then(|_| replaced.iter().try_for_each(restore_trashed)),
+            OperationRecord::Trash { items } => items.iter().rev().try_for_each(restore_trashed),
+        };
+
+        match result {
+            Ok(()) => Ok(Some(summary)),
+            Err(e) => {
+                // 取り消せなかった操作は履歴に戻して再試行できるようにする
+                self.lock_history()?.push(record);
+                Err(e)
+            }
+        }
+    }
+
+    /// 競合方針に従ってコピー・移動先を決める（飛ばす場合は None）
+    fn resolve_conflict(
+        &self,
+        source: &Path,
+        dest_dir: &Path,
+        policy: ConflictPolicy,
+        replaced: &mut Vec<TrashedItem>,
+    ) -> Result<Option<PathBuf>, String> {
+        let name = source
+            .file_name()
+            .ok_or_else(|| format!("ファイル名を取得できません: {}", source.display()))?;
+        let target = dest_dir.join(name);
+        if fs::symlink_metadata(&target).is_err() {
+            return Ok(Some(target));
+        }
+
+        match policy {
+            ConflictPolicy::Skip => Ok(None),
+            ConflictPolicy::Rename => Ok(Some(unique_path(dest_dir, &name.to_string_lossy()))),
+            ConflictPolicy::Overwrite => {
+                replaced.push(self.trash_path(&target)?);
+                Ok(Some(target))
+            }
+        }
+    }
+
+    /// 対象と同じボリュームにあるゴミ箱と、.trashinfo の Path の基準ディレクトリを決める
+    ///
+    /// freedesktop 仕様に従い、ホームのゴミ箱と別のデバイスにある場合は
+    /// `$topdir/.Trash/$uid`（`.Trash` がスティッキービット付きの実ディレクトリの場合）か
+    /// `$topdir/.Trash-$uid` を使う。どちらも使えない場合だけホームのゴミ箱にコピーする。
+    #[cfg(unix)]
+    fn trash_dir_for(&self, absolute: &Path) -> (PathBuf, Option<PathBuf>) {
+        use std::os::unix::fs::MetadataExt;
+
+        let home = (self.trash_dir.clone(), None);
+        let Ok(metadata) = fs::symlink_metadata(absolute) else {
+            return home;
+        };
+        // ホームのゴミ箱がまだ無い場合は、存在する最も近い親のデバイスを使う
+        let home_device = self
+            .trash_dir
+            .ancestors()
+            .find_map(|dir| fs::metadata(dir).ok())
+            .map(|metadata| metadata.dev());
+        if home_device == Some(metadata.dev()) {
+            return home;
+        }
+
+        let (Some(topdir), Some(uid)) = (mount_point(absolute, metadata.dev()), current_uid()) else {
+            return home;
+        };
+        match volume_trash_dir(&topdir, uid) {
+            Some(trash_dir) => (trash_dir, Some(topdir)),
+            None => home,
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn trash_dir_for(&self, _absolute: &Path) -> (PathBuf, Option<PathBuf>) {
+        (self.trash_dir.clone(), None)
+    }
+
+    /// freedesktop 仕様に従い、.trashinfo を作成してから files/ に移動する
+    fn trash_path(&self, path: &Path) -> Result<TrashedItem, String> {
+        if fs::symlink_metadata(path).is_err() {
+            return Err("ファイルが見つかりません".to_string());
+        }
+        let absolute = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir()
+                .map_err(|e| format!("カレントディレクトリの取得に失敗しました: {}", e))?
+                .join(path)
+        };
+        let (trash_dir, topdir) = self.trash_dir_for(&absolute);
+        for trash in [&self.trash_dir, &trash_dir] {
+            if trash.starts_with(&absolute) || absolute.starts_with(trash) {
+                return Err("ゴミ箱自体やゴミ箱の中身は削除できません".to_string());
+            }
+        }
+
+        let files_dir = trash_dir.join("files");
+        let info_dir = trash_dir.join("info");
+        for dir in [&files_dir, &info_dir] {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("ゴミ箱の作成に失敗しました: {}", e))?;
+        }
+
+        let name = absolute
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| format!("ファイル名を取得できません: {}", absolute.display()))?;
+        // ボリュームのゴミ箱では、マウント先が変わっても戻せるよう topdir からの相対パスで記録する
+        let recorded = topdir
+            .as_deref()
+            .and_then(|topdir| absolute.strip_prefix(topdir).ok())
+            .unwrap_or(&absolute);
+        let contents = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            utf8_percent_encode(&recorded.to_string_lossy(), TRASH_PATH_ENCODE_SET),
+            Local::now().format("%Y-%m-%dT%H:%M:%S")
+        );
+
+        // .trashinfo を排他的に作成して名前を予約する
+        let (trash_name, info) = (0..)
+            .map(|n| if n == 0 { name.clone() } else { numbered_name(&name, n, absolute.is_dir()) })
+            .find_map(|candidate| {
+                let info = info_dir.join(format!("{}.trashinfo", candidate));
+                if fs::symlink_metadata(files_dir.join(&candidate)).is_ok() {
+                    return None;
+                }
+                match OpenOptions::new().write(true).create_new(true).open(&info) {
+                    Ok(mut file) => Some(file.write_all(contents.as_bytes()).map(|_| (candidate, info.clone()))),
+                    Err(e) if e.kind() == io::ErrorKind::AlreadyExists => None,
+                    Err(e) => Some(Err(e)),
+                }
+            })
+            .expect("空き名は必ず見つかる")
+            .map_err(|e| format!("ゴミ箱情報の作成に失敗しました: {}", e))?;
+
+        let trashed = files_dir.join(&trash_name);
+        if let Err(e) = move_quietly(&absolute, &trashed) {
+            let _ = fs::remove_file(&info);
+            return Err(e);
+        }
+
+        Ok(TrashedItem { original: absolute, trashed, info })
+    }
+}
+
+/// 同じデバイスのまま辿れる最も上のディレクトリ（マウントポイント）を求める
+#[cfg(unix)]
+fn mount_point(path: &Path, device: u64) -> Option<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut topdir = path.parent()?.to_path_buf();
+    while let Some(parent) = topdir.parent() {
+        match fs::metadata(parent) {
+            Ok(metadata) if metadata.dev() == device => topdir = parent.to_path_buf(),
+            _ => break,
+        }
+    }
+    Some(topdir)
+}
+
+/// 実行中のユーザーID
+#[cfg(unix)]
+fn current_uid() -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    // /proc/self は実行中プロセスのユーザーが所有する（/proc が無い環境ではホームの所有者）
+    fs::metadata("/proc/self")
+        .ok()
+        .or_else(|| fs::metadata(std::env::var_os("HOME")?).ok())
+        .map(|metadata| metadata.uid())
+}
+
+/// ボリュームのゴミ箱を選び、必要なら作成する（使えない場合は None）
+#[cfg(unix)]
+fn volume_trash_dir(topdir: &Path, uid: u32) -> Option<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    const STICKY_BIT: u32 = 0o1000;
+
+    let create_private = |dir: &Path| -> Option<PathBuf> {
+        match fs::symlink_metadata(dir) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => return None,
+            Err(_) => fs::DirBuilder::new().mode(0o700).create(dir).ok()?,
+        }
+        Some(dir.to_path_buf())
+    };
+
+    // 管理者が用意した $topdir/.Trash はリンクでなく、スティッキービットが立っている場合のみ使う
+    let shared = topdir.join(".Trash");
+    if let Ok(metadata) = fs::symlink_metadata(&shared) {
+        if metadata.is_dir() && metadata.permissions().mode() & STICKY_BIT != 0 {
+            if let Some(dir) = create_private(&shared.join(uid.to_string())) {
+                return Some(dir);
+            }
+        }
+    }
+
+    create_private(&topdir.join(format!(".Trash-{}", uid)))
+}
+
+/// ゴミ箱から元の場所に戻す
+fn restore_trashed(item: &TrashedItem) -> Result<(), String> {
+    if fs::symlink_metadata(&item.original).is_ok() {
+        return Err(format!("元の場所に同じ名前のファイルが既にあります: {}", item.original.display()));
+    }
+    move_quietly(&item.trashed, &item.original)?;
+    let _ = fs::remove_file(&item.info);
+    Ok(())
+}
+
+/// ファイル名として使えるかを確認（区切り文字や . / .. は不可）
+fn validate_name(name: &str) -> Result<(), String> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) if !name.contains('/') && !name.contains('\\') && !name.contains('\0') => Ok(()),
+        _ => Err(format!("ファイル名として使えません: {}", name)),
+    }
+}
+
+/// コピー・移動の前提条件を確認
+fn validate_transfer(sources: &[PathBuf], dest_dir: &Path) -> Result<(), String> {
+    if !dest_dir.is_dir() {
+        return Err("ディレクトリが見つかりません".to_string());
+    }
+    for source in sources {
+        if fs::symlink_metadata(source).is_err() {
+            return Err(format!("ファイルが見つかりません: {}", source.display()));
+        }
+        if dest_dir.starts_with(source) {
+            return Err(format!("コピー先・移動先が元のフォルダの中にあります: {}", source.display()));
+        }
+    }
+    Ok(())
+}
+
+/// 「名前 (n).拡張子」形式の名前（ディレクトリは拡張子を分けない）
+fn numbered_name(name: &str, n: usize, is_dir: bool) -> String {
+    let path = Path::new(name);
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) if !is_dir && !stem.is_empty() => {
+            format!("{} ({}).{}", stem.to_string_lossy(), n, ext.to_string_lossy())
+        }
+        _ => format!("{} ({})", name, n),
+    }
+}
+
+/// `dir` 内で使われていない「名前 (n)」のパスを探す
+fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let is_dir = dir.join(name).is_dir();
+    (1..)
+        .map(|n| dir.join(numbered_name(name, n, is_dir)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("空き名は必ず見つかる")
+}
+
+/// 合計バイト数とファイル数を数える（シンボリックリンクは辿らない）
+fn measure(path: &Path) -> (u64, usize) {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return (0, 0);
+    };
+    if !metadata.is_dir() {
+        return (metadata.len(), 1);
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| measure(&entry.path()))
+                .fold((0, 0), |(bytes, files), (b, f)| (bytes + b, files + f))
+        })
+        .unwrap_or((0, 0))
+}
+
+/// ディレクトリごと再帰的にコピー（シンボリックリンクはリンクのまま複製）
+///
+/// FIFO・ソケット・デバイスファイルは開くと読み込みが終わらないか中身を持たないため、エラーにする。
+fn copy_recursive(source: &Path, target: &Path, tracker: &mut ProgressTracker<OperationProgress>) -> Result<(), String> {
+    let metadata = fs::symlink_metadata(source)
+        .map_err(|e| format!("ファイル情報の取得に失敗しました: {}", e))?;
+    tracker.set_current(source);
+
+    if metadata.file_type().is_symlink() {
+        copy_symlink(source, target)?;
+        tracker.add_files(1, metadata.len());
+    } else if metadata.is_dir() {
+        fs::create_dir(target)
+            .map_err(|e| format!("ディレクトリの作成に失敗しました: {}", e))?;
+        let entries = fs::read_dir(source)
+            .map_err(|e| format!("ディレクトリの読み込みに失敗しました: {}", e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("ディレクトリの読み込みに失敗しました: {}", e))?;
+            copy_recursive(&entry.path(), &target.join(entry.file_name()), tracker)?;
+        }
+        let _ = fs::set_permissions(target, metadata.permissions());
+    } else if metadata.is_file() {
+        copy_file(source, target, tracker)?;
+        let _ = fs::set_permissions(target, metadata.permissions());
+        tracker.add_files(1, 0);
+    } else {
+        return Err(format!("通常のファイル以外はコピーできません: {}", source.display()));
+    }
+    Ok(())
+}
+
+/// 進捗を報告しながらファイルの中身をコピー
+fn copy_file(source: &Path, target: &Path, tracker: &mut ProgressTracker<OperationProgress>) -> Result<(), String> {
+    let mut reader = File::open(source)
+        .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+    let mut writer = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)
+        .map_err(|e| format!("ファイルの作成に失敗しました: {}", e))?;
+
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        writer
+            .write_all(&buffer[..read])
+            .map_err(|e| format!("ファイルの書き込みに失敗しました: {}", e))?;
+        tracker.add_bytes(read as u64);
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> Result<(), String> {
+    let link = fs::read_link(source)
+        .map_err(|e| format!("リンク先の取得に失敗しました: {}", e))?;
+    std::os::unix::fs::symlink(link, target)
+        .map_err(|e| format!("シンボリックリンクの作成に失敗しました: {}", e))
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, target: &Path) -> Result<(), String> {
+    fs::copy(source, target)
+        .map(|_| ())
+        .map_err(|e| format!("ファイルのコピーに失敗しました: {}", e))
+}
+
+/// 移動する。別デバイス間で rename できない場合はコピーしてから元を削除する
+fn move_path(source: &Path, target: &Path, tracker: &mut ProgressTracker<OperationProgress>) -> Result<(), String> {
+    tracker.set_current(source);
+    match fs::rename(source, target) {
+        Ok(()) => {
+            let (bytes, files) = measure(target);
+            tracker.add_files(files, bytes);
+            Ok(())
+        }
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_recursive(source, target, tracker) {
+                // 中途半端なコピーは消して元を残す
+                let _ = remove_path(target);
+                return Err(e);
+            }
+            remove_path(source)
+        }
+        Err(e) => Err(format!("移動に失敗しました: {}", e)),
+    }
+}
+
+/// 進捗を報告せずに移動（ゴミ箱への出し入れと取り消し用）
+fn move_quietly(source: &Path, target: &Path) -> Result<(), String> {
+    let mut ignore = |_: &OperationProgress| {};
+    move_path(source, target, &mut ProgressTracker::new(OperationProgress::start(0, OperationKind::Move, &[]), &mut ignore))
+}
+
+/// ファイル・ディレクトリ・リンクを削除（リンク先は消さない）
+fn remove_path(path: &Path) -> Result<(), String> {
+    let metadata = fs::symlink_metadata(path)
+        .map_err(|e| format!("ファイル情報の取得に失敗しました: {}", e))?;
+    if metadata.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+    .map_err(|e| format!("削除に失敗しました: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use percent_encoding::percent_decode_str;
+    use tempfile::TempDir;
+
+    fn setup() -> (FileOperationService, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("work")).unwrap();
+        let service = FileOperationService::with_trash_dir(temp_dir.path().join("Trash"));
+        (service, temp_dir)
+    }
+
+    fn no_progress(_: &OperationProgress) {}
+
+    /// .trashinfo の Path を読み取る
+    fn read_trash_info_path(info: &Path) -> Option<PathBuf> {
+        let contents = fs::read_to_string(info).ok()?;
+        let encoded = contents.lines().find_map(|line| line.strip_prefix("Path="))?;
+        Some(PathBuf::from(percent_decode_str(encoded).decode_utf8_lossy().to_string()))
+    }
+
+    #[test]
+    fn test_mkdir_and_rename_with_undo() {
+        let (service, temp_dir) = setup();
+        let work = temp_dir.path().join("work");
+
+        let created = service.create_directory(&work, "photos").unwrap();
+        assert!(Path::new(&created).is_dir());
+        assert!(service.create_directory(&work, "photos").is_err());
+        assert!(service.create_directory(&work, "../escape").is_err());
+
+        let renamed = service.rename(Path::new(&created), "pictures").unwrap();
+        assert!(Path::new(&renamed).is_dir());
+        assert!(!Path::new(&created).exists());
+        assert_eq!(service.last_operation().unwrap().unwrap().kind, OperationKind::Rename);
+
+        assert_eq!(service.undo_last().unwrap().unwrap().kind, OperationKind::Rename);
+        assert!(Path::new(&created).is_dir());
+        assert_eq!(service.undo_last().unwrap().unwrap().kind, OperationKind::Mkdir);
+        assert!(!Path::new(&created).exists());
+        assert!(service.undo_last().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_recursive_copy_with_progress() {
+        let (service, temp_dir) = setup();
+        let work = temp_dir.path().join("work");
+        let source = temp_dir.path().join("album");
+        fs::create_dir_all(source.join("nested")).unwrap();
+        fs::write(source.join("a.txt"), "aaaa").unwrap();
+        fs::write(source.join("nested/b.txt"), "bb").unwrap();
+
+        let mut reports = Vec::new();
+        let outcome = service
+            .copy(std::slice::from_ref(&source), &work, ConflictPolicy::Skip, |p| reports.push(p.clone()))
+            .unwrap();
+        assert_eq!(outcome.completed, vec![work.join("album").to_string_lossy().to_string()]);
+        assert_eq!(fs::read_to_string(work.join("album/nested/b.txt")).unwrap(), "bb");
+
+        let last = reports.last().unwrap();
+        assert_eq!((last.total_files, last.total_bytes), (2, 6));
+        assert_eq!((last.processed_files, last.processed_bytes), (2, 6));
+
+        // 自分自身の中へのコピーは拒否する
+        assert!(service.copy(std::slice::from_ref(&work), &work.join("album"), ConflictPolicy::Skip, no_progress).is_err());
+
+        service.undo_last().unwrap();
+        assert!(!work.join("album").exists());
+        assert!(source.join("a.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_rejects_fifo() {
+        let (service, temp_dir) = setup();
+        let work = temp_dir.path().join("work");
+        let source = temp_dir.path().join("album");
+        fs::create_dir(&source).unwrap();
+        fs::write(source.join("a.txt"), "aaaa").unwrap();
+        let status = std::process::Command::new("mkfifo").arg(source.join("pipe")).status().unwrap();
+        assert!(status.success());
+
+        // FIFO を開いて書き込み側を待ち続けずにエラーを返す
+        let error = service
+            .copy(std::slice::from_ref(&source), &work, ConflictPolicy::Skip, no_progress)
+            .unwrap_err();
+        assert!(error.starts_with("通常のファイル以外はコピーできません"));
+        // 途中までのコピーは残さない
+        assert!(!work.join("album").exists());
+        assert!(source.join("pipe").exists());
+    }
+
+    #[test]
+    fn test_conflict_policies() {
+        let (service, temp_dir) = setup();
+        let work = temp_dir.path().join("work");
+        let source = temp_dir.path().join("note.txt");
+        fs::write(&source, "new").unwrap();
+        fs::write(work.join("note.txt"), "old").unwrap();
+
+        let skipped = service.copy(std::slice::from_ref(&source), &work, ConflictPolicy::Skip, no_progress).unwrap();
+        assert_eq!(skipped.skipped.len(), 1);
+
+        let renamed = service.copy(std::slice::from_ref(&source), &work, ConflictPolicy::Rename, no_progress).unwrap();
+        assert_eq!(renamed.completed, vec![work.join("note (1).txt").to_string_lossy().to_string()]);
+
+        service.copy(std::slice::from_ref(&source), &work, ConflictPolicy::Overwrite, no_progress).unwrap();
+        assert_eq!(fs::read_to_string(work.join("note.txt")).unwrap(), "new");
+
+        // 上書きの取り消しでゴミ箱から元のファイルが戻る
+        service.undo_last().unwrap();
+        assert_eq!(fs::read_to_string(work.join("note.txt")).unwrap(), "old");
+    }
+
+    #[test]
+    fn test_copy_into_same_directory_duplicates() {
+        let (service, temp_dir) = setup();
+        let work = temp_dir.path().join("work");
+        fs::write(work.join("a.txt"), "a").unwrap();
+
+        let outcome = service.copy(&[work.join("a.txt")], &work, ConflictPolicy::Overwrite, no_progress).unwrap();
+        assert_eq!(outcome.completed, vec![work.join("a (1).txt").to_string_lossy().to_string()]);
+        assert_eq!(fs::read_to_string(work.join("a.txt")).unwrap(), "a");
+    }
+
+    #[test]
+    fn test_move_and_undo() {
+        let (service, temp_dir) = setup();
+        let work = temp_dir.path().join("work");
+        let source = temp_dir.path().join("report.pdf");
+        fs::write(&source, "pdf").unwrap();
+
+        let outcome = service.move_items(std::slice::from_ref(&source), &work, ConflictPolicy::Skip, no_progress).unwrap();
+        assert_eq!(outcome.completed.len(), 1);
+        assert!(!source.exists());
+        assert!(work.join("report.pdf").exists());
+
+        service.undo_last().unwrap();
+        assert!(source.exists());
+        assert!(!work.join("report.pdf").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_volume_trash_dir_selection() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // スティッキービットの無い .Trash は使わず .Trash-$uid を作る
+        let topdir = TempDir::new().unwrap();
+        fs::create_dir(topdir.path().join(".Trash")).unwrap();
+        let trash = volume_trash_dir(topdir.path(), 1000).unwrap();
+        assert_eq!(trash, topdir.path().join(".Trash-1000"));
+        assert_eq!(fs::metadata(&trash).unwrap().permissions().mode() & 0o777, 0o700);
+
+        // スティッキービット付きの .Trash があれば .Trash/$uid を使う
+        let topdir = TempDir::new().unwrap();
+        let shared = topdir.path().join(".Trash");
+        fs::create_dir(&shared).unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o1777)).unwrap();
+        assert_eq!(volume_trash_dir(topdir.path(), 1000).unwrap(), shared.join("1000"));
+
+        // リンクの .Trash は信用しない
+        let topdir = TempDir::new().unwrap();
+        std::os::unix::fs::symlink(&shared, topdir.path().join(".Trash")).unwrap();
+        assert_eq!(volume_trash_dir(topdir.path(), 1000).unwrap(), topdir.path().join(".Trash-1000"));
+
+        // .Trash-$uid がファイルの場合は使えない
+        let topdir = TempDir::new().unwrap();
+        fs::write(topdir.path().join(".Trash-1000"), "").unwrap();
+        assert_eq!(volume_trash_dir(topdir.path(), 1000), None);
+    }
+
+    #[test]
+    fn test_trash_follows_freedesktop_layout_and_restores() {
+        let (service, temp_dir) = setup();
+        let work = temp_dir.path().join("work");
+        let first = work.join("memo 1.txt");
+        fs::write(&first, "one").unwrap();
+
+        service.trash(std::slice::from_ref(&first)).unwrap();
+        assert!(!first.exists());
+        let trash = temp_dir.path().join("Trash");
+        assert_eq!(fs::read_to_string(trash.join("files/memo 1.txt")).unwrap(), "one");
+        let info = trash.join("info/memo 1.txt.trashinfo");
+        let contents = fs::read_to_string(&info).unwrap();
+        assert!(contents.starts_with("[Trash Info]\n"));
+        assert!(contents.contains("memo%201.txt"));
+        assert!(contents.contains("DeletionDate="));
+        assert_eq!(read_trash_info_path(&info).unwrap(), first);
+
+        // 同名のファイルは別名でゴミ箱に入る
+        fs::write(&first, "two").unwrap();
+        service.trash(std::slice::from_ref(&first)).unwrap();
+        assert!(trash.join("files/memo 1 (1).txt").exists());
+        assert!(trash.join("info/memo 1 (1).txt.trashinfo").exists());
+
+        service.undo_last().unwrap();
+        assert_eq!(fs::read_to_string(&first).unwrap(), "two");
+        assert!(!trash.join("info/memo 1 (1).txt.trashinfo").exists());
+
+        // 元の場所にファイルがあると復元できず、履歴は残る
+        assert!(service.undo_last().is_err());
+        assert_eq!(service.last_operation().unwrap().unwrap().kind, OperationKind::Trash);
+    }
+
+    #[test]
+    fn test_cannot_trash_missing_or_trash_itself() {
+        let (service, temp_dir) = setup();
+        assert!(service.trash(&[temp_dir.path().join("missing")]).is_err());
+        fs::create_dir_all(temp_dir.path().join("Trash/files")).unwrap();
+        assert!(service.trash(&[temp_dir.path().join("Trash")]).is_err());
+    }
+}
@@ -1,43 +1,394 @@
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use base64::{Engine as _, engine::general_purpose};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveDateTime};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
-use tauri_plugin_dialog::DialogExt;
+#[cfg(unix)]
+use sysinfo::{Groups, Users};
+use crate::image_service::ImageService;
+use crate::walker::{build_walker, SearchSummary};
+use crate::collation::{CollationKey, Collator};
 
-#[derive(Serialize, Deserialize, Clone)]
+/// 内容検索で既定の最大ヒット件数
+const DEFAULT_CONTENT_MAX_RESULTS: usize = 1000;
+
+/// 内容検索で既定の読み込み上限サイズ（これより大きいファイルは飛ばす）
+const DEFAULT_CONTENT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// ハッシュ計算の進捗を送るイベント名
+pub const HASH_PROGRESS_EVENT: &str = "hash-progress";
+
+/// ハッシュ計算で一度に読み込むサイズ
+const HASH_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// ハッシュ計算の進捗を通知する間隔（バイト）
+const HASH_PROGRESS_INTERVAL: u64 = 16 * 1024 * 1024;
+
+/// 重複検出の部分ハッシュで読む先頭のサイズ
+const PARTIAL_HASH_LENGTH: u64 = 64 * 1024;
+
+/// 前後に付ける文脈行の上限
+const MAX_CONTEXT_LINES: usize = 10;
+
+/// 結果に含める1行の最大文字数（長い行は切り詰める）
+const MAX_LINE_CHARS: usize = 1000;
+
+/// バイナリ判定のために先頭から調べるバイト数
+const BINARY_CHECK_LENGTH: usize = 8192;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct FileInfo {
     pub name: String,
     pub size: u64,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct DirectoryEntry {
     pub name: String,
     pub is_dir: bool,
     pub size: Option<u64>,
     pub path: String,
     pub modified: Option<u64>,
+    pub is_symlink: bool,
+    pub link_target: Option<String>,
+    /// リンク先が存在しないシンボリックリンク
+    pub is_broken_link: bool,
+    pub is_hidden: bool,
+    /// Unix のパーミッションビット（例: 0o755 = 493）
+    pub permissions: Option<u32>,
+    /// `rwxr-xr-x` 形式のパーミッション
+    pub permissions_text: Option<String>,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub created: Option<u64>,
+    pub accessed: Option<u64>,
+    pub mime_type: Option<String>,
 }
 
-/// ファイル操作を担当するサービスクラス
-pub struct FileService;
+/// ディレクトリ一覧の並べ替えキー
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// 名前の自然順（"file2" < "file10"）
+    #[default]
+    Name,
+    Size,
+    Modified,
+    /// 拡張子順
+    Type,
+}
 
-impl FileService {
-    
-    /// 画像ファイル選択ダイアログを表示
-    pub async fn select_image_file(app: &tauri::AppHandle) -> Result<Option<String>, String> {
-        let dialog = app.dialog()
-            .file()
-            .add_filter("Images", &["png", "jpg", "jpeg", "gif", "bmp", "webp"])
-            .blocking_pick_file();
+/// 並べ替えの向き
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
 
-        match dialog {
-            Some(path) => Ok(Some(path.to_string())),
-            None => Ok(None),
+/// ディレクトリ一覧の取得条件
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct ListOptions {
+    pub show_hidden: bool,
+    /// false の場合、シンボリックリンクはリンク自体の情報を返す（is_dir は false）
+    pub follow_symlinks: bool,
+    pub sort_by: SortKey,
+    pub sort_direction: SortDirection,
+    /// 名前の比較で大文字・小文字を区別する
+    pub case_sensitive: bool,
+    /// 並べ替えの向きに関係なくディレクトリを先に並べる
+    pub directories_first: bool,
+    /// 名前の照合に使うロケール（"ja_JP" など）。未指定なら環境変数から決める
+    pub locale: Option<String>,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        Self {
+            show_hidden: true,
+            follow_symlinks: true,
+            sort_by: SortKey::default(),
+            sort_direction: SortDirection::default(),
+            case_sensitive: false,
+            directories_first: true,
+            locale: None,
         }
     }
+}
+
+/// ページ単位で取得したディレクトリ内容
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DirectoryPage {
+    pub entries: Vec<DirectoryEntry>,
+    /// 絞り込み後の全件数
+    pub total: usize,
+    pub offset: usize,
+    pub has_more: bool,
+}
+
+/// 内容検索の条件
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ContentSearchOptions {
+    pub query: String,
+    /// false の場合は query を文字列としてそのまま探す
+    pub is_regex: bool,
+    pub case_sensitive: bool,
+    /// ヒット行の前後に付ける行数（最大10）
+    pub context_lines: usize,
+    /// 省略時は 1000 件
+    pub max_results: Option<usize>,
+    /// 省略時は 10MB。これより大きいファイルは読まない
+    pub max_file_size: Option<u64>,
+    /// 対象ファイル名のグロブ（例: *.log）
+    pub file_pattern: Option<String>,
+    pub include_hidden: bool,
+    pub respect_gitignore: bool,
+    pub max_depth: Option<usize>,
+}
+
+/// 行内で一致した範囲（文字単位）
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 内容検索の1行分の結果
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ContentMatch {
+    pub path: String,
+    /// 1始まりの行番号
+    pub line_number: usize,
+    pub line: String,
+    pub ranges: Vec<MatchRange>,
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+}
+
+/// ハッシュアルゴリズム
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    #[default]
+    Sha256,
+    Blake3,
+}
 
+/// ファイルのハッシュ値
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FileHash {
+    pub path: String,
+    pub algorithm: HashAlgorithm,
+    /// 16進数の小文字表記
+    pub hash: String,
+    pub size: u64,
+}
+
+/// ハッシュ計算の進捗
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HashProgress {
+    pub path: String,
+    pub processed_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// 重複ファイル検出の条件
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DuplicateOptions {
+    /// これより小さいファイルは対象外（既定は 1 バイトで空ファイルを除く）
+    pub min_size: u64,
+    pub include_hidden: bool,
+    pub respect_gitignore: bool,
+    pub max_depth: Option<usize>,
+}
+
+impl Default for DuplicateOptions {
+    fn default() -> Self {
+        Self {
+            min_size: 1,
+            include_hidden: false,
+            respect_gitignore: false,
+            max_depth: None,
+        }
+    }
+}
+
+/// 内容が同じファイルのまとまり
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DuplicateGroup {
+    /// 1ファイルあたりのサイズ
+    pub size: u64,
+    /// BLAKE3 ハッシュ
+    pub hash: String,
+    pub paths: Vec<String>,
+    /// 1つを残して削除した場合に空く容量
+    pub reclaimable_bytes: u64,
+}
+
+/// 重複ファイル検出の結果
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DuplicateReport {
+    /// 空けられる容量の大きい順
+    pub groups: Vec<DuplicateGroup>,
+    pub reclaimable_bytes: u64,
+    pub scanned_files: u64,
+    /// 読み込めなかったファイル数
+    pub skipped_files: u64,
+}
+
+/// 一括名前変更で番号・日付を入れる位置
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InsertPosition {
+    Prefix,
+    #[default]
+    Suffix,
+    /// 元の名前を置き換える
+    Replace,
+}
+
+/// 大文字・小文字の変換方法
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LetterCase {
+    Lower,
+    Upper,
+    /// 単語の先頭だけ大文字にする
+    Title,
+}
+
+/// 名前に入れる日付の取得元
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DateSource {
+    /// EXIF の撮影日時（無ければ更新日時）
+    #[default]
+    Exif,
+    Modified,
+}
+
+/// 一括名前変更の規則（指定した順に適用する）
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RenameRule {
+    /// 正規表現で置換する（置換後の文字列では $1 などでグループを参照できる）
+    Replace {
+        pattern: String,
+        replacement: String,
+        #[serde(default)]
+        case_insensitive: bool,
+    },
+    /// 指定した順に連番を付ける
+    Number {
+        #[serde(default = "default_number_start")]
+        start: u64,
+        #[serde(default = "default_number_step")]
+        step: u64,
+        /// ゼロ埋めの桁数
+        #[serde(default)]
+        width: usize,
+        #[serde(default)]
+        position: InsertPosition,
+        #[serde(default = "default_rename_separator")]
+        separator: String,
+    },
+    /// 大文字・小文字を変換する
+    Case { case: LetterCase },
+    /// 撮影日時・更新日時を付ける
+    Date {
+        #[serde(default)]
+        source: DateSource,
+        /// strftime 形式（既定は "%Y%m%d"）
+        #[serde(default = "default_date_format")]
+        format: String,
+        #[serde(default)]
+        position: InsertPosition,
+        #[serde(default = "default_rename_separator")]
+        separator: String,
+    },
+}
+
+fn default_number_start() -> u64 {
+    1
+}
+
+fn default_number_step() -> u64 {
+    1
+}
+
+fn default_rename_separator() -> String {
+    "_".to_string()
+}
+
+fn default_date_format() -> String {
+    "%Y%m%d".to_string()
+}
+
+/// 一括名前変更の設定
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct BatchRenameOptions {
+    pub rules: Vec<RenameRule>,
+    /// true の場合は拡張子も含めた名前全体に規則を適用する
+    pub include_extension: bool,
+}
+
+/// 一括名前変更で変更できない理由
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RenameConflict {
+    /// 名前が空、または使えない文字を含む
+    InvalidName,
+    /// 変更後の名前が他の項目と重複する
+    Duplicate,
+    /// 変更後の名前のファイルが既にある
+    Exists,
+}
+
+/// 一括名前変更のプレビューの1件
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RenamePreviewItem {
+    pub path: String,
+    pub new_name: String,
+    pub new_path: String,
+    pub changed: bool,
+    pub conflict: Option<RenameConflict>,
+}
+
+/// 一括名前変更のプレビュー
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BatchRenamePreview {
+    /// 指定された順
+    pub items: Vec<RenamePreviewItem>,
+    pub changed_count: usize,
+    pub conflict_count: usize,
+}
+
+/// 名前を変更した1件
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RenamedPath {
+    pub from: String,
+    pub to: String,
+}
+
+/// ファイル操作を担当するサービスクラス
+pub struct FileService;
+
+impl FileService {
     /// 画像ファイルを読み込んでBase64エンコードして返す
     pub fn read_image_file(file_path: &str) -> Result<String, String> {
         // ファイルの存在確認
@@ -45,8 +396,8 @@ impl FileService {
             return Err("ファイルが見つかりません".to_string());
         }
 
-        // ファイル拡張子の確認
-        let allowed_extensions = ["jpg", "jpeg", "png", "gif", "webp"];
+        // ファイル拡張子の確認（ブラウザでそのまま表示できる形式のみ。TIFF などは render_image で PNG に変換する）
+        let allowed_extensions = ["jpg", "jpeg", "png", "gif", "webp", "bmp", "svg", "ico"];
         let extension = Path::new(file_path)
             .extension()
             .and_then(|ext| ext.to_str())
@@ -93,7 +444,23 @@ impl FileService {
     }
 
     /// ディレクトリ内容を取得
-    pub fn list_directory(dir_path: &str) -> Result<Vec<DirectoryEntry>, String> {
+    pub fn list_directory(dir_path: &str, options: &ListOptions) -> Result<Vec<DirectoryEntry>, String> {
+        Ok(Self::list_directory_page(dir_path, options, 0, None)?.entries)
+    }
+
+    /// ディレクトリ内容を並べ替えたうえで、`offset` から最大 `limit` 件を取得
+    ///
+    /// 並べ替えに必要な情報だけを全件分読み込み、所有者名や MIME タイプなどの詳細は
+    /// 返すページの分だけ作るため、大量のエントリがあっても応答を保てる。
+    /// 並べ替えた一覧はディレクトリ・取得条件・更新日時が同じ間は使い回すため、続くページの取得では読み直さない。
+    ///
+    /// 名前はロケールに応じた照合順序で比べる（数字部分は数値として比べる）。
+    pub fn list_directory_page(
+        dir_path: &str,
+        options: &ListOptions,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<DirectoryPage, String> {
         let path = Path::new(dir_path);
         
         if !path.exists() {
@@ -104,49 +471,23 @@ impl FileService {
             return Err("指定されたパスはディレクトリではありません".to_string());
         }
 
-        let mut entries = Vec::new();
-        
-        let read_dir = fs::read_dir(path)
-            .map_err(|e| format!("ディレクトリの読み込みに失敗しました: {}", e))?;
-
-        for entry in read_dir {
-            let entry = entry.map_err(|e| format!("エントリの読み込みに失敗しました: {}", e))?;
-            let entry_path = entry.path();
-            let metadata = entry.metadata().ok();
-            
-            let name = entry_path
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("不明")
-                .to_string();
-                
-            let is_dir = entry_path.is_dir();
-            let size = if is_dir { None } else { metadata.as_ref().map(|m| m.len()) };
-            let modified = metadata.and_then(|m| {
-                m.modified().ok().and_then(|time| {
-                    time.duration_since(std::time::UNIX_EPOCH).ok().map(|d| d.as_secs())
-                })
-            });
-            
-            entries.push(DirectoryEntry {
-                name,
-                is_dir,
-                size,
-                path: entry_path.to_string_lossy().to_string(),
-                modified,
-            });
-        }
+        let items = sorted_listing(path, options)?;
+        let total = items.len();
+        let end = limit.map_or(total, |limit| offset.saturating_add(limit).min(total));
+        let owners = OwnerNames::cached();
+        let entries = items
+            .iter()
+            .take(end)
+            .skip(offset)
+            .map(|item| item.to_entry(options.follow_symlinks, &owners))
+            .collect();
         
-        // ディレクトリを先に、ファイルを後に、それぞれ名前順でソート
-        entries.sort_by(|a, b| {
-            match (a.is_dir, b.is_dir) {
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                _ => a.name.cmp(&b.name),
-            }
-        });
-        
-        Ok(entries)
+        Ok(DirectoryPage {
+            entries,
+            total,
+            offset,
+            has_more: end < total,
+        })
     }
 
     /// ホームディレクトリのパスを取得
@@ -164,16 +505,943 @@ impl FileService {
     }
 
     /// ディレクトリを読み込み（React用エイリアス）
-    pub fn read_directory(path: &str) -> Result<Vec<DirectoryEntry>, String> {
-        Self::list_directory(path)
+    pub fn read_directory(path: &str, options: &ListOptions) -> Result<Vec<DirectoryEntry>, String> {
+        Self::list_directory(path, options)
+    }
+
+    /// 内容検索の条件が正しいかを確認
+    pub fn validate_content_search(options: &ContentSearchOptions) -> Result<(), String> {
+        content_regex(options)?;
+        file_pattern_matcher(options)?;
+        Ok(())
+    }
+
+    /// `root` 以下のファイル内容を検索し、ヒットした行ごとに `on_match` を呼ぶ
+    ///
+    /// NUL を含むファイルはバイナリとみなして飛ばす。`cancel` が立つと中断する。
+    pub fn search_contents<F>(
+        root: &Path,
+        options: &ContentSearchOptions,
+        cancel: &AtomicBool,
+        mut on_match: F,
+    ) -> Result<SearchSummary, String>
+    where
+        F: FnMut(ContentMatch),
+    {
+        if !root.is_dir() {
+            return Err("ディレクトリが見つかりません".to_string());
+        }
+        let regex = content_regex(options)?;
+        let file_pattern = file_pattern_matcher(options)?;
+        let max_results = options.max_results.unwrap_or(DEFAULT_CONTENT_MAX_RESULTS);
+        let max_file_size = options.max_file_size.unwrap_or(DEFAULT_CONTENT_MAX_FILE_SIZE);
+        let context_lines = options.context_lines.min(MAX_CONTEXT_LINES);
+
+        let mut summary = SearchSummary::default();
+        let walker = build_walker(root, options.include_hidden, options.respect_gitignore, options.max_depth);
+        for entry in walker {
+            if cancel.load(Ordering::Relaxed) {
+                summary.cancelled = true;
+                break;
+            }
+
+            let Ok(entry) = entry else { continue };
+            if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+                continue;
+            }
+            if let Some(glob) = &file_pattern {
+                if !glob.is_match(entry.file_name()) {
+                    continue;
+                }
+            }
+
+            summary.scanned += 1;
+            if entry.metadata().map(|m| m.len() > max_file_size).unwrap_or(true) {
+                summary.skipped += 1;
+                continue;
+            }
+            let Ok(bytes) = fs::read(entry.path()) else {
+                summary.skipped += 1;
+                continue;
+            };
+            if bytes[..bytes.len().min(BINARY_CHECK_LENGTH)].contains(&0) {
+                summary.skipped += 1;
+                continue;
+            }
+
+            let text = String::from_utf8_lossy(&bytes);
+            let lines: Vec<&str> = text.lines().collect();
+            let path = entry.path().to_string_lossy().to_string();
+            for (index, line) in lines.iter().enumerate() {
+                let ranges = match_ranges(&regex, line);
+                if ranges.is_empty() {
+                    continue;
+                }
+
+                let before = index.saturating_sub(context_lines);
+                let after = (index + 1 + context_lines).min(lines.len());
+                on_match(ContentMatch {
+                    path: path.clone(),
+                    line_number: index + 1,
+                    line: truncate_line(line),
+                    ranges,
+                    context_before: lines[before..index].iter().map(|l| truncate_line(l)).collect(),
+                    context_after: lines[index + 1..after].iter().map(|l| truncate_line(l)).collect(),
+                });
+                summary.matched += 1;
+
+                if summary.matched >= max_results {
+                    summary.truncated = true;
+                    return Ok(summary);
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// ファイルのハッシュ値を計算する
+    ///
+    /// 大きいファイルでは一定量を読むごとに `on_progress` を呼ぶ（最後に必ず1回呼ぶ）。
+    pub fn hash_file<F>(file_path: &str, algorithm: HashAlgorithm, mut on_progress: F) -> Result<FileHash, String>
+    where
+        F: FnMut(HashProgress),
+    {
+        let path = Path::new(file_path);
+        if !path.is_file() {
+            return Err("ファイルが見つかりません".to_string());
+        }
+
+        let total_bytes = fs::metadata(path)
+            .map_err(|e| format!("ファイル情報の取得に失敗しました: {}", e))?
+            .len();
+        let mut next_report = HASH_PROGRESS_INTERVAL;
+        let hash = hash_reader(path, algorithm, None, |processed_bytes| {
+            if processed_bytes >= next_report {
+                on_progress(HashProgress { path: file_path.to_string(), processed_bytes, total_bytes });
+                next_report = processed_bytes + HASH_PROGRESS_INTERVAL;
+            }
+        })
+        .map_err(|e| format!("ハッシュの計算に失敗しました: {}", e))?;
+        on_progress(HashProgress { path: file_path.to_string(), processed_bytes: total_bytes, total_bytes });
+
+        Ok(FileHash {
+            path: file_path.to_string(),
+            algorithm,
+            hash,
+            size: total_bytes,
+        })
+    }
+
+    /// `root` 以下の内容が同じファイルを探す
+    ///
+    /// サイズ、先頭部分のハッシュ、全体のハッシュの順に候補を絞り込む。
+    /// シンボリックリンクは辿らない。
+    pub fn find_duplicates(root: &Path, options: &DuplicateOptions) -> Result<DuplicateReport, String> {
+        if !root.is_dir() {
+            return Err("ディレクトリが見つかりません".to_string());
+        }
+
+        let mut report = DuplicateReport::default();
+        let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+        // ハードリンクは同じ実体なので、2つ目以降は候補に入れない
+        let mut seen_files = HashSet::new();
+        for entry in build_walker(root, options.include_hidden, options.respect_gitignore, options.max_depth) {
+            let Ok(entry) = entry else { continue };
+            if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                report.skipped_files += 1;
+                continue;
+            };
+            report.scanned_files += 1;
+            if file_identity(&metadata).is_some_and(|identity| !seen_files.insert(identity)) {
+                continue;
+            }
+            if metadata.len() >= options.min_size {
+                by_size.entry(metadata.len()).or_default().push(entry.into_path());
+            }
+        }
+
+        let candidates: Vec<(u64, PathBuf)> = by_size
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .flat_map(|(size, paths)| paths.into_iter().map(move |path| (size, path)))
+            .collect();
+
+        // 先頭部分で絞り込み、残ったものだけ全体を読む
+        // 先頭部分だけでファイル全体を読み終えたものは、そのハッシュで確定する
+        let (partial, skipped) = group_by_hash(candidates, Some(PARTIAL_HASH_LENGTH));
+        report.skipped_files += skipped;
+        let (complete, partial): (Vec<_>, Vec<_>) = partial
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .partition(|((size, _), _)| *size <= PARTIAL_HASH_LENGTH);
+        let candidates = partial
+            .into_iter()
+            .flat_map(|((size, _), paths)| paths.into_iter().map(move |path| (size, path)))
+            .collect();
+        let (full, skipped) = group_by_hash(candidates, None);
+        report.skipped_files += skipped;
+
+        report.groups = complete
+            .into_iter()
+            .chain(full.into_iter().filter(|(_, paths)| paths.len() > 1))
+            .map(|((size, hash), paths)| {
+                let mut paths: Vec<String> = paths.into_iter().map(|path| path.to_string_lossy().to_string()).collect();
+                paths.sort();
+                DuplicateGroup {
+                    size,
+                    hash,
+                    reclaimable_bytes: size * (paths.len() as u64 - 1),
+                    paths,
+                }
+            })
+            .collect();
+        report.groups.sort_by(|a, b| b.reclaimable_bytes.cmp(&a.reclaimable_bytes).then_with(|| a.paths.cmp(&b.paths)));
+        report.reclaimable_bytes = report.groups.iter().map(|group| group.reclaimable_bytes).sum();
+        Ok(report)
+    }
+
+    /// 一括名前変更の結果を実際には変更せずに求め、名前の重複や既存ファイルとの衝突を調べる
+    ///
+    /// 連番は `paths` の順に付ける。
+    pub fn preview_batch_rename(paths: &[PathBuf], options: &BatchRenameOptions) -> Result<BatchRenamePreview, String> {
+        if paths.is_empty() {
+            return Err("名前を変更するファイルが指定されていません".to_string());
+        }
+        let rules = compile_rename_rules(&options.rules)?;
+        let sources: HashSet<&PathBuf> = paths.iter().collect();
+        if sources.len() != paths.len() {
+            return Err("同じファイルが複数指定されています".to_string());
+        }
+
+        let mut planned = Vec::with_capacity(paths.len());
+        for (index, path) in paths.iter().enumerate() {
+            if fs::symlink_metadata(path).is_err() {
+                return Err(format!("ファイルが見つかりません: {}", path.display()));
+            }
+            let name = path
+                .file_name()
+                .ok_or_else(|| format!("ファイル名を取得できません: {}", path.display()))?
+                .to_string_lossy()
+                .to_string();
+            let new_name = apply_rename_rules(&rules, path, &name, index, options.include_extension);
+            let new_path = path.with_file_name(&new_name);
+            planned.push((path, new_name, new_path));
+        }
+
+        let mut target_counts: HashMap<&PathBuf, usize> = HashMap::new();
+        for (_, _, new_path) in &planned {
+            *target_counts.entry(new_path).or_default() += 1;
+        }
+        // 名前が変わる項目の元の場所は空くので、そこへの変更は衝突にしない
+        let vacated: HashSet<&PathBuf> = planned
+            .iter()
+            .filter(|(path, _, new_path)| *path != new_path)
+            .map(|(path, _, _)| *path)
+            .collect();
+
+        let items: Vec<RenamePreviewItem> = planned
+            .iter()
+            .map(|(path, new_name, new_path)| {
+                let changed = *path != new_path;
+                let conflict = if !is_valid_file_name(new_name) {
+                    Some(RenameConflict::InvalidName)
+                } else if target_counts[new_path] > 1 {
+                    Some(RenameConflict::Duplicate)
+                } else if changed
+                    && !vacated.contains(new_path)
+                    && fs::symlink_metadata(new_path).is_ok()
+                    && !is_same_file(path, new_path)
+                {
+                    Some(RenameConflict::Exists)
+                } else {
+                    None
+                };
+                RenamePreviewItem {
+                    path: path.to_string_lossy().to_string(),
+                    new_name: new_name.clone(),
+                    new_path: new_path.to_string_lossy().to_string(),
+                    changed,
+                    conflict,
+                }
+            })
+            .collect();
+
+        Ok(BatchRenamePreview {
+            changed_count: items.iter().filter(|item| item.changed).count(),
+            conflict_count: items.iter().filter(|item| item.conflict.is_some()).count(),
+            items,
+        })
+    }
+
+    /// プレビューと同じ規則で名前を一括変更する
+    ///
+    /// 衝突が1件でもあれば何も変更しない。途中で失敗した場合は変更済みの名前をすべて元に戻す。
+    pub fn batch_rename(paths: &[PathBuf], options: &BatchRenameOptions) -> Result<Vec<RenamedPath>, String> {
+        let preview = Self::preview_batch_rename(paths, options)?;
+        if preview.conflict_count > 0 {
+            return Err(format!("{} 件の名前が衝突しているため変更できません", preview.conflict_count));
+        }
+
+        let moves: Vec<(PathBuf, PathBuf)> = paths
+            .iter()
+            .zip(&preview.items)
+            .filter(|(_, item)| item.changed)
+            .map(|(path, item)| (path.clone(), PathBuf::from(&item.new_path)))
+            .collect();
+        rename_all(&moves)?;
+
+        Ok(moves
+            .into_iter()
+            .map(|(from, to)| RenamedPath {
+                from: from.to_string_lossy().to_string(),
+                to: to.to_string_lossy().to_string(),
+            })
+            .collect())
+    }
+}
+
+/// ハッシュ計算の途中状態
+enum Hasher {
+    Md5(md5::Md5),
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        use sha2::Digest;
+        match algorithm {
+            HashAlgorithm::Md5 => Hasher::Md5(md5::Md5::new()),
+            HashAlgorithm::Sha1 => Hasher::Sha1(sha1::Sha1::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        use sha2::Digest;
+        match self {
+            Hasher::Md5(hasher) => hasher.update(data),
+            Hasher::Sha1(hasher) => hasher.update(data),
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    fn finalize_hex(self) -> String {
+        use sha2::Digest;
+        let bytes: Vec<u8> = match self {
+            Hasher::Md5(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha1(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+        };
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+/// ファイルを先頭から（`limit` があればそのバイト数まで）読んでハッシュを計算する
+fn hash_reader<F>(path: &Path, algorithm: HashAlgorithm, limit: Option<u64>, mut on_read: F) -> std::io::Result<String>
+where
+    F: FnMut(u64),
+{
+    use std::io::Read;
+
+    let mut reader = fs::File::open(path)?.take(limit.unwrap_or(u64::MAX));
+    let mut hasher = Hasher::new(algorithm);
+    let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+    let mut processed = 0u64;
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buffer[..read]);
+        processed += read as u64;
+        on_read(processed);
+    }
+    Ok(hasher.finalize_hex())
+}
+
+/// サイズとハッシュの組ごとのファイル
+type HashGroups = HashMap<(u64, String), Vec<PathBuf>>;
+
+/// サイズとハッシュの組でファイルをまとめる。読めなかったファイル数も返す
+fn group_by_hash(files: Vec<(u64, PathBuf)>, limit: Option<u64>) -> (HashGroups, u64) {
+    use rayon::prelude::*;
+
+    let hashed: Vec<(u64, PathBuf, Option<String>)> = files
+        .into_par_iter()
+        .map(|(size, path)| {
+            let hash = hash_reader(&path, HashAlgorithm::Blake3, limit, |_| {}).ok();
+            (size, path, hash)
+        })
+        .collect();
+
+    let mut groups = HashGroups::new();
+    let mut skipped = 0;
+    for (size, path, hash) in hashed {
+        match hash {
+            Some(hash) => groups.entry((size, hash)).or_default().push(path),
+            None => skipped += 1,
+        }
+    }
+    (groups, skipped)
+}
+
+/// ファイルの実体を示す (デバイス, inode)。取得できない環境では None
+#[cfg(unix)]
+fn file_identity(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_identity(_metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// 並べ替え用に読み込んだエントリ（詳細はページに含まれる分だけ後で作る）
+#[derive(Clone)]
+struct ListedItem {
+    name: String,
+    path: PathBuf,
+    /// follow_symlinks に応じてリンク先かリンク自体のメタデータ
+    metadata: Option<fs::Metadata>,
+    is_symlink: bool,
+    is_broken_link: bool,
+    is_hidden: bool,
+    is_dir: bool,
+}
+
+impl ListedItem {
+    fn read(path: PathBuf, follow_symlinks: bool) -> Self {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("不明")
+            .to_string();
+
+        // リンク自体の情報と、辿った先の情報を分けて取得する
+        let link_metadata = path.symlink_metadata().ok();
+        let is_symlink = link_metadata.as_ref().is_some_and(|m| m.file_type().is_symlink());
+        let target_metadata = if is_symlink { fs::metadata(&path).ok() } else { None };
+        let is_broken_link = is_symlink && target_metadata.is_none();
+        let is_hidden = is_hidden(&name, link_metadata.as_ref());
+        let metadata = match (follow_symlinks, target_metadata) {
+            (true, Some(target)) => Some(target),
+            _ => link_metadata,
+        };
+        let is_dir = metadata.as_ref().is_some_and(|m| m.is_dir());
+
+        Self { name, path, metadata, is_symlink, is_broken_link, is_hidden, is_dir }
+    }
+
+    fn size(&self) -> Option<u64> {
+        if self.is_dir { None } else { self.metadata.as_ref().map(|m| m.len()) }
+    }
+
+    fn modified(&self) -> Option<u64> {
+        self.metadata.as_ref().and_then(|m| unix_seconds(m.modified()))
+    }
+
+    /// 拡張子（小文字）。ディレクトリや拡張子なしは空文字
+    fn extension(&self) -> String {
+        if self.is_dir {
+            return String::new();
+        }
+        Path::new(&self.name)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    }
+
+    fn to_entry(&self, follow_symlinks: bool, owners: &OwnerNames) -> DirectoryEntry {
+        let metadata = self.metadata.as_ref();
+        let link_target = if self.is_symlink {
+            fs::read_link(&self.path).ok().map(|target| target.to_string_lossy().to_string())
+        } else {
+            None
+        };
+        let mime_type = if self.is_dir {
+            Some("inode/directory".to_string())
+        } else if self.is_symlink && (!follow_symlinks || self.is_broken_link) {
+            Some("inode/symlink".to_string())
+        } else {
+            mime_guess::from_path(&self.path).first().map(|mime| mime.essence_str().to_string())
+        };
+        let (permissions, owner, group) = match metadata {
+            Some(metadata) => owners.describe(metadata),
+            None => (None, None, None),
+        };
+
+        DirectoryEntry {
+            size: self.size(),
+            modified: self.modified(),
+            created: metadata.and_then(|m| unix_seconds(m.created())),
+            accessed: metadata.and_then(|m| unix_seconds(m.accessed())),
+            path: self.path.to_string_lossy().to_string(),
+            name: self.name.clone(),
+            is_dir: self.is_dir,
+            is_symlink: self.is_symlink,
+            link_target,
+            is_broken_link: self.is_broken_link,
+            is_hidden: self.is_hidden,
+            permissions,
+            permissions_text: permissions.map(permissions_text),
+            owner,
+            group,
+            mime_type,
+        }
     }
 }
 
+/// 並べ替え済みの一覧を保持する件数
+const MAX_CACHED_LISTINGS: usize = 8;
+
+/// 更新日時がこれより新しいディレクトリの一覧は保持しない（更新日時の粒度が粗いファイルシステムで
+/// 同じ時刻のうちに続いた変更を見逃さないため）
+const LISTING_SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// 並べ替え済みのディレクトリ一覧（最後に使ったものが末尾）
+static LISTINGS: Mutex<Vec<CachedListing>> = Mutex::new(Vec::new());
+
+struct CachedListing {
+    path: PathBuf,
+    options: ListOptions,
+    modified: SystemTime,
+    items: Arc<Vec<ListedItem>>,
+}
+
+/// ディレクトリを読み、取得条件に従って並べ替えた一覧を返す
+///
+/// ディレクトリ・取得条件・ディレクトリの更新日時が同じなら前回の結果を返す。
+/// 既存ファイルの上書きはディレクトリの更新日時を変えないため、サイズなどはディレクトリが変わるまで前回のままとなる。
+fn sorted_listing(path: &Path, options: &ListOptions) -> Result<Arc<Vec<ListedItem>>, String> {
+    // 読み込み前に更新日時を取得し、読み込み中の変更は次回に読み直させる
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    if let Some(modified) = modified {
+        let mut listings = LISTINGS.lock().unwrap_or_else(|e| e.into_inner());
+        let found = listings
+            .iter()
+            .position(|cached| cached.path == path && cached.modified == modified && cached.options == *options);
+        if let Some(index) = found {
+            let cached = listings.remove(index);
+            let items = Arc::clone(&cached.items);
+            listings.push(cached);
+            return Ok(items);
+        }
+    }
+
+    let read_dir = fs::read_dir(path)
+        .map_err(|e| format!("ディレクトリの読み込みに失敗しました: {}", e))?;
+    let collator = options.locale.as_deref().map_or_else(Collator::from_env, Collator::new);
+    let mut items = Vec::new();
+    for entry in read_dir {
+        let entry = entry.map_err(|e| format!("エントリの読み込みに失敗しました: {}", e))?;
+        let item = ListedItem::read(entry.path(), options.follow_symlinks);
+        if item.is_hidden && !options.show_hidden {
+            continue;
+        }
+        items.push((collator.key(&item.name, options.case_sensitive), item));
+    }
+    items.sort_by(|a, b| compare_items(a, b, options));
+    let items = Arc::new(items.into_iter().map(|(_, item)| item).collect::<Vec<_>>());
+
+    let settled = modified.filter(|modified| modified.elapsed().is_ok_and(|elapsed| elapsed >= LISTING_SETTLE_TIME));
+    if let Some(modified) = settled {
+        let mut listings = LISTINGS.lock().unwrap_or_else(|e| e.into_inner());
+        listings.retain(|cached| !(cached.path == path && cached.options == *options));
+        if listings.len() >= MAX_CACHED_LISTINGS {
+            listings.remove(0);
+        }
+        listings.push(CachedListing {
+            path: path.to_path_buf(),
+            options: options.clone(),
+            modified,
+            items: Arc::clone(&items),
+        });
+    }
+    Ok(items)
+}
+
+/// 一覧の並べ替え条件に従って比較（名前は照合キーで比べる）
+fn compare_items(
+    (a_key, a): &(CollationKey, ListedItem),
+    (b_key, b): &(CollationKey, ListedItem),
+    options: &ListOptions,
+) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    if options.directories_first {
+        match (a.is_dir, b.is_dir) {
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            _ => {}
+        }
+    }
+
+    let by_name = || a_key.cmp(b_key);
+    let ordering = match options.sort_by {
+        SortKey::Name => by_name(),
+        SortKey::Size => a.size().cmp(&b.size()).then_with(by_name),
+        SortKey::Modified => a.modified().cmp(&b.modified()).then_with(by_name),
+        SortKey::Type => a.extension().cmp(&b.extension()).then_with(by_name),
+    };
+
+    match options.sort_direction {
+        SortDirection::Ascending => ordering,
+        SortDirection::Descending => ordering.reverse(),
+    }
+}
+
+/// ユーザー・グループ表を読み直すまでの間隔
+const OWNER_NAMES_TTL: Duration = Duration::from_secs(60);
+
+/// 読み込み済みのユーザー・グループ表と読み込んだ時刻
+static OWNER_NAMES: Mutex<Option<(Instant, Arc<OwnerNames>)>> = Mutex::new(None);
+
+/// UID・GID から名前を引く表
+struct OwnerNames {
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+}
+
+impl OwnerNames {
+    /// プロセス内で共有する表を返す。`OWNER_NAMES_TTL` を過ぎていれば読み直す
+    ///
+    /// 表の構築はシステムのユーザー・グループを全件列挙するため、一覧取得のたびには行わない。
+    fn cached() -> Arc<Self> {
+        let mut cache = OWNER_NAMES.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((loaded_at, owners)) = cache.as_ref() {
+            if loaded_at.elapsed() < OWNER_NAMES_TTL {
+                return Arc::clone(owners);
+            }
+        }
+        let owners = Arc::new(Self::load());
+        *cache = Some((Instant::now(), Arc::clone(&owners)));
+        owners
+    }
+
+    #[cfg(unix)]
+    fn load() -> Self {
+        let users = Users::new_with_refreshed_list()
+            .list()
+            .iter()
+            .map(|user| (**user.id(), user.name().to_string()))
+            .collect();
+        let groups = Groups::new_with_refreshed_list()
+            .list()
+            .iter()
+            .map(|group| (**group.id(), group.name().to_string()))
+            .collect();
+        Self { users, groups }
+    }
+
+    #[cfg(not(unix))]
+    fn load() -> Self {
+        Self { users: HashMap::new(), groups: HashMap::new() }
+    }
+
+    /// パーミッションビットと所有者・グループ名（名前が無ければ数値）
+    #[cfg(unix)]
+    fn describe(&self, metadata: &fs::Metadata) -> (Option<u32>, Option<String>, Option<String>) {
+        use std::os::unix::fs::MetadataExt;
+        let name = |names: &HashMap<u32, String>, id: u32| names.get(&id).cloned().unwrap_or_else(|| id.to_string());
+        (
+            Some(metadata.mode() & 0o7777),
+            Some(name(&self.users, metadata.uid())),
+            Some(name(&self.groups, metadata.gid())),
+        )
+    }
+
+    #[cfg(not(unix))]
+    fn describe(&self, _metadata: &fs::Metadata) -> (Option<u32>, Option<String>, Option<String>) {
+        (None, None, None)
+    }
+}
+
+/// 隠しファイルか（ドットファイル、Windows では隠し属性も見る）
+fn is_hidden(name: &str, metadata: Option<&fs::Metadata>) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if metadata.is_some_and(|m| m.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0) {
+            return true;
+        }
+    }
+    #[cfg(not(windows))]
+    let _ = metadata;
+    name.starts_with('.')
+}
+
+/// ファイル時刻をUNIX秒に変換（取得できない環境では None）
+fn unix_seconds(time: std::io::Result<std::time::SystemTime>) -> Option<u64> {
+    time.ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+}
+
+/// パーミッションビットを `rwxr-xr-x` 形式にする（setuid などは s / t で表す）
+fn permissions_text(mode: u32) -> String {
+    let mut text: Vec<char> = "rwxrwxrwx"
+        .chars()
+        .enumerate()
+        .map(|(i, c)| if mode & (0o400 >> i) != 0 { c } else { '-' })
+        .collect();
+    for (bit, index, set_char) in [(0o4000, 2, 's'), (0o2000, 5, 's'), (0o1000, 8, 't')] {
+        if mode & bit != 0 {
+            text[index] = if text[index] == 'x' { set_char } else { set_char.to_ascii_uppercase() };
+        }
+    }
+    text.into_iter().collect()
+}
+
+/// 検索文字列を正規表現にする（文字列検索はエスケープする）
+fn content_regex(options: &ContentSearchOptions) -> Result<Regex, String> {
+    if options.query.is_empty() {
+        return Err("検索文字列を入力してください".to_string());
+    }
+    let pattern = if options.is_regex {
+        options.query.clone()
+    } else {
+        regex::escape(&options.query)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| format!("正規表現が不正です: {}", e))
+}
+
+/// 対象ファイル名のグロブをコンパイル
+fn file_pattern_matcher(options: &ContentSearchOptions) -> Result<Option<GlobMatcher>, String> {
+    match options.file_pattern.as_deref().filter(|p| !p.is_empty()) {
+        Some(pattern) => GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .literal_separator(true)
+            .build()
+            .map(|glob| Some(glob.compile_matcher()))
+            .map_err(|e| format!("グロブパターンが不正です: {}", e)),
+        None => Ok(None),
+    }
+}
+
+/// 行内の一致範囲を文字単位で求める（表示上限を超える部分は除く）
+fn match_ranges(regex: &Regex, line: &str) -> Vec<MatchRange> {
+    regex
+        .find_iter(line)
+        .filter(|m| !m.is_empty())
+        .map(|m| {
+            let start = line[..m.start()].chars().count();
+            MatchRange { start, end: start + m.as_str().chars().count() }
+        })
+        .filter(|range| range.start < MAX_LINE_CHARS)
+        .map(|range| MatchRange { start: range.start, end: range.end.min(MAX_LINE_CHARS) })
+        .collect()
+}
+
+/// 表示用に行を最大文字数で切り詰める
+fn truncate_line(line: &str) -> String {
+    line.chars().take(MAX_LINE_CHARS).collect()
+}
+
+/// 正規表現などを事前に検証した名前変更規則
+enum CompiledRenameRule<'a> {
+    Replace(Regex, &'a str),
+    Other(&'a RenameRule),
+}
+
+fn compile_rename_rules(rules: &[RenameRule]) -> Result<Vec<CompiledRenameRule<'_>>, String> {
+    if rules.is_empty() {
+        return Err("名前変更の規則を指定してください".to_string());
+    }
+    rules
+        .iter()
+        .map(|rule| match rule {
+            RenameRule::Replace { pattern, replacement, case_insensitive } => {
+                let regex = RegexBuilder::new(pattern)
+                    .case_insensitive(*case_insensitive)
+                    .build()
+                    .map_err(|e| format!("正規表現が不正です: {}", e))?;
+                Ok(CompiledRenameRule::Replace(regex, replacement.as_str()))
+            }
+            RenameRule::Date { format, .. } => {
+                if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                    return Err(format!("日付の書式が不正です: {}", format));
+                }
+                Ok(CompiledRenameRule::Other(rule))
+            }
+            _ => Ok(CompiledRenameRule::Other(rule)),
+        })
+        .collect()
+}
+
+/// 規則を順に適用した新しい名前（拡張子は `include_extension` が false なら残す）
+fn apply_rename_rules(rules: &[CompiledRenameRule], path: &Path, name: &str, index: usize, include_extension: bool) -> String {
+    let (mut stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !include_extension && !stem.is_empty() && !path.is_dir() => {
+            (stem.to_string(), Some(extension))
+        }
+        _ => (name.to_string(), None),
+    };
+
+    for rule in rules {
+        stem = match rule {
+            CompiledRenameRule::Replace(regex, replacement) => regex.replace_all(&stem, *replacement).into_owned(),
+            CompiledRenameRule::Other(RenameRule::Number { start, step, width, position, separator }) => {
+                let number = start.saturating_add(step.saturating_mul(index as u64));
+                insert_text(&stem, &format!("{:0width$}", number, width = *width), *position, separator)
+            }
+            CompiledRenameRule::Other(RenameRule::Case { case }) => change_case(&stem, *case),
+            CompiledRenameRule::Other(RenameRule::Date { source, format, position, separator }) => {
+                match file_date(path, *source) {
+                    Some(date) => insert_text(&stem, &date.format(format).to_string(), *position, separator),
+                    None => stem,
+                }
+            }
+            CompiledRenameRule::Other(RenameRule::Replace { .. }) => stem,
+        };
+    }
+
+    match extension {
+        Some(extension) => format!("{}.{}", stem, extension),
+        None => stem,
+    }
+}
+
+fn insert_text(stem: &str, text: &str, position: InsertPosition, separator: &str) -> String {
+    match position {
+        InsertPosition::Prefix => format!("{}{}{}", text, separator, stem),
+        InsertPosition::Suffix => format!("{}{}{}", stem, separator, text),
+        InsertPosition::Replace => text.to_string(),
+    }
+}
+
+fn change_case(text: &str, case: LetterCase) -> String {
+    match case {
+        LetterCase::Lower => text.to_lowercase(),
+        LetterCase::Upper => text.to_uppercase(),
+        LetterCase::Title => {
+            let mut previous_alphanumeric = false;
+            text.chars()
+                .flat_map(|c| {
+                    let converted: Vec<char> = if previous_alphanumeric {
+                        c.to_lowercase().collect()
+                    } else {
+                        c.to_uppercase().collect()
+                    };
+                    previous_alphanumeric = c.is_alphanumeric();
+                    converted
+                })
+                .collect()
+        }
+    }
+}
+
+/// 名前に使う日付（EXIF の撮影日時、または更新日時）
+fn file_date(path: &Path, source: DateSource) -> Option<NaiveDateTime> {
+    if source == DateSource::Exif {
+        let taken = ImageService::read_exif(path)
+            .and_then(|exif| exif.date_taken)
+            .and_then(|date| NaiveDateTime::parse_from_str(date.trim(), "%Y:%m:%d %H:%M:%S").ok());
+        if taken.is_some() {
+            return taken;
+        }
+    }
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok()?;
+    Some(DateTime::<Local>::from(modified).naive_local())
+}
+
+fn is_valid_file_name(name: &str) -> bool {
+    let forbidden: &[char] = if cfg!(windows) { &['/', '\\', '\0'] } else { &['/', '\0'] };
+    !name.is_empty() && name != "." && name != ".." && !name.contains(forbidden)
+}
+
+/// 大文字・小文字だけの変更など、別のパスが同じファイルを指しているか
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// 入れ替えや連鎖にも対応するため、一度すべてを一時的な名前にしてから新しい名前にする
+///
+/// 失敗した場合は済んだ変更を逆順に戻す。
+fn rename_all(moves: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+    let mut done: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let result = (|| {
+        let mut staged = Vec::with_capacity(moves.len());
+        for (index, (from, to)) in moves.iter().enumerate() {
+            let temporary = temporary_rename_path(from, index);
+            fs::rename(from, &temporary).map_err(|e| format!("名前の変更に失敗しました: {}: {}", from.display(), e))?;
+            done.push((from.clone(), temporary.clone()));
+            staged.push((temporary, to));
+        }
+        for (temporary, to) in staged {
+            // rename は既存のファイルを上書きするため、直前にもう一度確認する
+            if fs::symlink_metadata(to).is_ok() {
+                return Err(format!("同じ名前のファイルが既にあります: {}", to.display()));
+            }
+            fs::rename(&temporary, to).map_err(|e| format!("名前の変更に失敗しました: {}: {}", to.display(), e))?;
+            done.push((temporary, to.clone()));
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        let failed = done.iter().rev().filter(|(from, to)| fs::rename(to, from).is_err()).count();
+        if failed > 0 {
+            return Err(format!("{}（{} 件を元に戻せませんでした）", e, failed));
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// 同じディレクトリ内の使われていない一時的な名前
+fn temporary_rename_path(path: &Path, index: usize) -> PathBuf {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    (0..)
+        .map(|attempt| path.with_file_name(format!(".{}.renaming-{}-{}-{}", name, std::process::id(), index, attempt)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("一時的な名前が見つかりません")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
-    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn content_options(query: &str) -> ContentSearchOptions {
+        ContentSearchOptions { query: query.to_string(), ..Default::default() }
+    }
+
+    fn grep(root: &Path, options: &ContentSearchOptions) -> (Vec<ContentMatch>, SearchSummary) {
+        let mut found = Vec::new();
+        let summary = FileService::search_contents(root, options, &AtomicBool::new(false), |m| found.push(m)).unwrap();
+        found.sort_by(|a, b| (&a.path, a.line_number).cmp(&(&b.path, b.line_number)));
+        (found, summary)
+    }
+
+    fn setup_logs() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("app.log"), "start\nINFO ready\nERROR disk full\nretry\nerror again\n").unwrap();
+        fs::write(root.join("notes.txt"), "no errors here? ERROR\r\n").unwrap();
+        fs::write(root.join("data.bin"), b"ERROR\0\x01\x02").unwrap();
+        temp_dir
+    }
 
     #[test]
     fn test_get_file_info_nonexistent() {
@@ -184,8 +1452,456 @@ mod tests {
 
     #[test]
     fn test_list_directory_nonexistent() {
-        let result = FileService::list_directory("/nonexistent/directory");
+        let result = FileService::list_directory("/nonexistent/directory", &ListOptions::default());
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), "ディレクトリが見つかりません");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_search_contents_literal_with_context() {
+        let temp_dir = setup_logs();
+        let options = ContentSearchOptions {
+            context_lines: 1,
+            file_pattern: Some("*.log".to_string()),
+            ..content_options("error")
+        };
+        let (found, summary) = grep(temp_dir.path(), &options);
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].line_number, 3);
+        assert_eq!(found[0].line, "ERROR disk full");
+        assert_eq!(found[0].ranges, vec![MatchRange { start: 0, end: 5 }]);
+        assert_eq!(found[0].context_before, vec!["INFO ready"]);
+        assert_eq!(found[0].context_after, vec!["retry"]);
+        assert_eq!(found[1].line_number, 5);
+        assert!(found[1].context_after.is_empty());
+        assert_eq!(summary.scanned, 1);
+    }
+
+    #[test]
+    fn test_search_contents_skips_binary_and_large_files() {
+        let temp_dir = setup_logs();
+        let options = ContentSearchOptions { case_sensitive: true, ..content_options("ERROR") };
+        let (found, summary) = grep(temp_dir.path(), &options);
+
+        let paths: Vec<_> = found.iter().map(|m| Path::new(&m.path).file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(paths, vec!["app.log", "notes.txt"]);
+        assert_eq!(found[1].line, "no errors here? ERROR");
+        assert_eq!(found[1].ranges, vec![MatchRange { start: 16, end: 21 }]);
+        assert_eq!(summary.skipped, 1);
+
+        let (found, summary) = grep(temp_dir.path(), &ContentSearchOptions { max_file_size: Some(30), ..options });
+        assert_eq!(found.len(), 1);
+        assert_eq!(summary.skipped, 2);
+    }
+
+    #[test]
+    fn test_search_contents_regex_and_limits() {
+        let temp_dir = setup_logs();
+        let options = ContentSearchOptions { is_regex: true, ..content_options(r"^(start|retry)$") };
+        let (found, _) = grep(temp_dir.path(), &options);
+        assert_eq!(found.iter().map(|m| m.line_number).collect::<Vec<_>>(), vec![1, 4]);
+
+        let (found, summary) = grep(temp_dir.path(), &ContentSearchOptions { max_results: Some(1), ..content_options("error") });
+        assert_eq!(found.len(), 1);
+        assert!(summary.truncated);
+
+        // 文字列検索では正規表現の記号をそのまま探す
+        let (found, _) = grep(temp_dir.path(), &content_options("here?"));
+        assert_eq!(found.len(), 1);
+    }
+
+    #[test]
+    fn test_search_contents_cancel_and_invalid_query() {
+        let temp_dir = setup_logs();
+        let cancel = AtomicBool::new(true);
+        let summary = FileService::search_contents(temp_dir.path(), &content_options("error"), &cancel, |_| {}).unwrap();
+        assert!(summary.cancelled);
+        assert_eq!(summary.matched, 0);
+
+        assert_eq!(FileService::validate_content_search(&content_options("")).unwrap_err(), "検索文字列を入力してください");
+        let invalid = ContentSearchOptions { is_regex: true, ..content_options("(") };
+        assert!(FileService::validate_content_search(&invalid).is_err());
+    }
+
+    fn entry<'a>(entries: &'a [DirectoryEntry], name: &str) -> &'a DirectoryEntry {
+        entries.iter().find(|entry| entry.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_list_directory_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(root.join("photo.png"), b"png").unwrap();
+        fs::write(root.join(".env"), b"SECRET=1").unwrap();
+
+        let entries = FileService::list_directory(root.to_str().unwrap(), &ListOptions::default()).unwrap();
+        assert_eq!(entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), vec!["docs", ".env", "photo.png"]);
+
+        let photo = entry(&entries, "photo.png");
+        assert_eq!(photo.mime_type.as_deref(), Some("image/png"));
+        assert_eq!(photo.size, Some(3));
+        assert!(!photo.is_hidden && !photo.is_symlink);
+        assert!(photo.accessed.is_some());
+        assert_eq!(entry(&entries, "docs").mime_type.as_deref(), Some("inode/directory"));
+        assert!(entry(&entries, ".env").is_hidden);
+
+        let visible = FileService::list_directory(
+            root.to_str().unwrap(),
+            &ListOptions { show_hidden: false, ..Default::default() },
+        ).unwrap();
+        assert!(visible.iter().all(|e| !e.is_hidden));
+        assert_eq!(visible.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_directory_symlinks_and_permissions() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("target")).unwrap();
+        fs::write(root.join("script.sh"), b"#!/bin/sh").unwrap();
+        fs::set_permissions(root.join("script.sh"), fs::Permissions::from_mode(0o750)).unwrap();
+        symlink(root.join("target"), root.join("link")).unwrap();
+        symlink(root.join("missing"), root.join("broken")).unwrap();
+
+        let followed = FileService::list_directory(root.to_str().unwrap(), &ListOptions::default()).unwrap();
+        let link = entry(&followed, "link");
+        assert!(link.is_symlink && link.is_dir && !link.is_broken_link);
+        assert_eq!(link.link_target.as_deref(), root.join("target").to_str());
+        let broken = entry(&followed, "broken");
+        assert!(broken.is_broken_link && !broken.is_dir);
+        assert_eq!(broken.mime_type.as_deref(), Some("inode/symlink"));
+
+        let script = entry(&followed, "script.sh");
+        assert_eq!(script.permissions, Some(0o750));
+        assert_eq!(script.permissions_text.as_deref(), Some("rwxr-x---"));
+        assert!(script.owner.is_some() && script.group.is_some());
+
+        let not_followed = FileService::list_directory(
+            root.to_str().unwrap(),
+            &ListOptions { follow_symlinks: false, ..Default::default() },
+        ).unwrap();
+        let link = entry(&not_followed, "link");
+        assert!(link.is_symlink && !link.is_dir);
+        assert_eq!(link.mime_type.as_deref(), Some("inode/symlink"));
+    }
+
+    #[test]
+    fn test_permissions_text_special_bits() {
+        assert_eq!(permissions_text(0o644), "rw-r--r--");
+        assert_eq!(permissions_text(0o4755), "rwsr-xr-x");
+        assert_eq!(permissions_text(0o1777), "rwxrwxrwt");
+        assert_eq!(permissions_text(0o2640), "rw-r-S---");
+    }
+
+    fn names(entries: &[DirectoryEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_list_directory_sort_options() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::write(root.join("file10.txt"), b"1234567890").unwrap();
+        fs::write(root.join("file2.txt"), b"12").unwrap();
+        fs::write(root.join("File3.md"), b"12345").unwrap();
+        let dir = root.to_str().unwrap();
+
+        let by_name = FileService::list_directory(dir, &ListOptions::default()).unwrap();
+        assert_eq!(names(&by_name), vec!["sub", "file2.txt", "File3.md", "file10.txt"]);
+
+        let case_sensitive = ListOptions { case_sensitive: true, ..Default::default() };
+        let entries = FileService::list_directory(dir, &case_sensitive).unwrap();
+        assert_eq!(names(&entries), vec!["sub", "File3.md", "file2.txt", "file10.txt"]);
+
+        let by_size_desc = ListOptions {
+            sort_by: SortKey::Size,
+            sort_direction: SortDirection::Descending,
+            ..Default::default()
+        };
+        let entries = FileService::list_directory(dir, &by_size_desc).unwrap();
+        assert_eq!(names(&entries), vec!["sub", "file10.txt", "File3.md", "file2.txt"]);
+
+        let by_type = ListOptions { sort_by: SortKey::Type, directories_first: false, ..Default::default() };
+        let entries = FileService::list_directory(dir, &by_type).unwrap();
+        assert_eq!(names(&entries), vec!["sub", "File3.md", "file2.txt", "file10.txt"]);
+    }
+
+    #[test]
+    fn test_list_directory_locale_collation() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["zebra", "Éclair", "edge", "ösel"] {
+            fs::write(temp_dir.path().join(name), b"").unwrap();
+        }
+        let dir = temp_dir.path().to_str().unwrap();
+
+        let english = ListOptions { locale: Some("en_US".to_string()), ..Default::default() };
+        let entries = FileService::list_directory(dir, &english).unwrap();
+        assert_eq!(names(&entries), vec!["Éclair", "edge", "ösel", "zebra"]);
+
+        let swedish = ListOptions { locale: Some("sv_SE".to_string()), ..Default::default() };
+        let entries = FileService::list_directory(dir, &swedish).unwrap();
+        assert_eq!(names(&entries), vec!["Éclair", "edge", "zebra", "ösel"]);
+    }
+
+    #[test]
+    fn test_list_directory_page_reuses_sorted_listing() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), b"").unwrap();
+        let dir = temp_dir.path().to_str().unwrap();
+        let set_modified = |modified: SystemTime| fs::File::open(temp_dir.path()).unwrap().set_modified(modified).unwrap();
+        let old = SystemTime::now() - Duration::from_secs(3600);
+        set_modified(old);
+
+        let options = ListOptions::default();
+        assert_eq!(FileService::list_directory_page(dir, &options, 0, None).unwrap().total, 1);
+
+        // 更新日時が同じ間は前回の一覧を使う
+        fs::write(temp_dir.path().join("b.txt"), b"").unwrap();
+        set_modified(old);
+        assert_eq!(FileService::list_directory_page(dir, &options, 0, None).unwrap().total, 1);
+        let hidden_excluded = ListOptions { show_hidden: false, ..Default::default() };
+        assert_eq!(FileService::list_directory_page(dir, &hidden_excluded, 0, None).unwrap().total, 2);
+
+        set_modified(old + Duration::from_secs(1));
+        assert_eq!(FileService::list_directory_page(dir, &options, 0, None).unwrap().total, 2);
+    }
+
+    #[test]
+    fn test_list_directory_page() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 1..=25 {
+            fs::write(temp_dir.path().join(format!("item{}", i)), b"").unwrap();
+        }
+        let dir = temp_dir.path().to_str().unwrap();
+
+        let first = FileService::list_directory_page(dir, &ListOptions::default(), 0, Some(10)).unwrap();
+        assert_eq!(first.total, 25);
+        assert!(first.has_more);
+        assert_eq!(names(&first.entries)[..3], ["item1", "item2", "item3"]);
+
+        let last = FileService::list_directory_page(dir, &ListOptions::default(), 20, Some(10)).unwrap();
+        assert_eq!(names(&last.entries), vec!["item21", "item22", "item23", "item24", "item25"]);
+        assert!(!last.has_more);
+
+        let beyond = FileService::list_directory_page(dir, &ListOptions::default(), 100, Some(10)).unwrap();
+        assert!(beyond.entries.is_empty());
+        assert_eq!(beyond.offset, 100);
+    }
+
+    #[test]
+    fn test_hash_file_known_digests() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("abc.txt");
+        fs::write(&file, b"abc").unwrap();
+        let path = file.to_str().unwrap();
+
+        let expected = [
+            (HashAlgorithm::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+            (HashAlgorithm::Sha1, "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (HashAlgorithm::Sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            (HashAlgorithm::Blake3, "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"),
+        ];
+        for (algorithm, digest) in expected {
+            let hash = FileService::hash_file(path, algorithm, |_| {}).unwrap();
+            assert_eq!(hash.hash, digest, "{:?}", algorithm);
+            assert_eq!(hash.size, 3);
+        }
+    }
+
+    #[test]
+    fn test_hash_file_reports_progress() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("large.bin");
+        fs::write(&file, vec![7u8; (HASH_PROGRESS_INTERVAL * 2 + 10) as usize]).unwrap();
+
+        let mut progress = Vec::new();
+        FileService::hash_file(file.to_str().unwrap(), HashAlgorithm::Blake3, |p| progress.push(p.processed_bytes)).unwrap();
+        assert_eq!(progress.len(), 3);
+        assert!(progress.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(*progress.last().unwrap(), HASH_PROGRESS_INTERVAL * 2 + 10);
+
+        let missing = FileService::hash_file("/nonexistent/file", HashAlgorithm::Md5, |_| {});
+        assert_eq!(missing.unwrap_err(), "ファイルが見つかりません");
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("backup")).unwrap();
+        let photo = vec![1u8; 1000];
+        fs::write(root.join("photo.jpg"), &photo).unwrap();
+        fs::write(root.join("backup/photo.jpg"), &photo).unwrap();
+        fs::write(root.join("backup/photo (2).jpg"), &photo).unwrap();
+        // 同じサイズ・同じ先頭部分で末尾だけ異なる
+        let mut large = vec![0u8; (PARTIAL_HASH_LENGTH + 100) as usize];
+        fs::write(root.join("a.bin"), &large).unwrap();
+        *large.last_mut().unwrap() = 1;
+        fs::write(root.join("b.bin"), &large).unwrap();
+        fs::write(root.join("empty1"), b"").unwrap();
+        fs::write(root.join("empty2"), b"").unwrap();
+
+        let report = FileService::find_duplicates(root, &DuplicateOptions::default()).unwrap();
+        assert_eq!(report.scanned_files, 7);
+        assert_eq!(report.groups.len(), 1);
+        let group = &report.groups[0];
+        assert_eq!(group.size, 1000);
+        assert_eq!(group.paths.len(), 3);
+        assert_eq!(group.reclaimable_bytes, 2000);
+        assert_eq!(report.reclaimable_bytes, 2000);
+
+        let with_empty = FileService::find_duplicates(root, &DuplicateOptions { min_size: 0, ..Default::default() }).unwrap();
+        assert_eq!(with_empty.groups.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_duplicates_skips_hard_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("original.txt"), vec![1u8; 1000]).unwrap();
+        fs::hard_link(root.join("original.txt"), root.join("link.txt")).unwrap();
+
+        // ハードリンクだけでは容量を空けられない
+        let report = FileService::find_duplicates(root, &DuplicateOptions::default()).unwrap();
+        assert_eq!(report.scanned_files, 2);
+        assert!(report.groups.is_empty());
+
+        fs::write(root.join("copy.txt"), vec![1u8; 1000]).unwrap();
+        let report = FileService::find_duplicates(root, &DuplicateOptions::default()).unwrap();
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(report.groups[0].paths.len(), 2);
+        assert_eq!(report.reclaimable_bytes, 1000);
+    }
+
+    fn rename_options(rules: Vec<RenameRule>) -> BatchRenameOptions {
+        BatchRenameOptions { rules, include_extension: false }
+    }
+
+    fn number_rule(position: InsertPosition, width: usize) -> RenameRule {
+        RenameRule::Number { start: 1, step: 1, width, position, separator: "_".to_string() }
+    }
+
+    #[test]
+    fn test_preview_batch_rename_rules() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let paths: Vec<PathBuf> = ["IMG_0012.JPG", "img_0007.jpg", "my photo.tar.gz"].iter().map(|name| root.join(name)).collect();
+        for path in &paths {
+            fs::write(path, b"x").unwrap();
+        }
+
+        let options = rename_options(vec![
+            RenameRule::Replace { pattern: r"^img_(\d+)$".to_string(), replacement: "photo-$1".to_string(), case_insensitive: true },
+            RenameRule::Case { case: LetterCase::Title },
+            number_rule(InsertPosition::Prefix, 3),
+        ]);
+        let preview = FileService::preview_batch_rename(&paths, &options).unwrap();
+        let names: Vec<&str> = preview.items.iter().map(|item| item.new_name.as_str()).collect();
+        assert_eq!(names, vec!["001_Photo-0012.JPG", "002_Photo-0007.jpg", "003_My Photo.Tar.gz"]);
+        assert_eq!(preview.changed_count, 3);
+        assert_eq!(preview.conflict_count, 0);
+        // プレビューでは変更しない
+        assert!(paths[0].exists());
+
+        let with_extension = BatchRenameOptions {
+            rules: vec![RenameRule::Case { case: LetterCase::Upper }],
+            include_extension: true,
+        };
+        let preview = FileService::preview_batch_rename(&paths[2..], &with_extension).unwrap();
+        assert_eq!(preview.items[0].new_name, "MY PHOTO.TAR.GZ");
+
+        let year = DateTime::<Local>::from(fs::metadata(&paths[1]).unwrap().modified().unwrap()).format("%Y").to_string();
+        let date = rename_options(vec![RenameRule::Date {
+            source: DateSource::Exif,
+            format: "%Y".to_string(),
+            position: InsertPosition::Suffix,
+            separator: "-".to_string(),
+        }]);
+        let preview = FileService::preview_batch_rename(&paths[1..2], &date).unwrap();
+        assert_eq!(preview.items[0].new_name, format!("img_0007-{}.jpg", year));
+
+        let invalid = rename_options(vec![RenameRule::Replace { pattern: "(".to_string(), replacement: String::new(), case_insensitive: false }]);
+        assert!(FileService::preview_batch_rename(&paths, &invalid).is_err());
+        assert!(FileService::preview_batch_rename(&paths, &rename_options(vec![])).is_err());
+    }
+
+    #[test]
+    fn test_preview_batch_rename_conflicts() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for name in ["a1.txt", "a2.txt", "b.txt", "keep.txt"] {
+            fs::write(root.join(name), name).unwrap();
+        }
+        let paths = vec![root.join("a1.txt"), root.join("a2.txt"), root.join("b.txt")];
+        let replace = |pattern: &str, replacement: &str| {
+            rename_options(vec![RenameRule::Replace { pattern: pattern.to_string(), replacement: replacement.to_string(), case_insensitive: false }])
+        };
+
+        // a1, a2 は同じ名前になり、b は既存の keep.txt と衝突する
+        let mut colliding = replace(r"^a\d$", "same");
+        colliding.rules.extend(replace("^b$", "keep").rules);
+        let preview = FileService::preview_batch_rename(&paths, &colliding).unwrap();
+        let conflicts: Vec<Option<RenameConflict>> = preview.items.iter().map(|item| item.conflict).collect();
+        assert_eq!(conflicts, vec![Some(RenameConflict::Duplicate), Some(RenameConflict::Duplicate), Some(RenameConflict::Exists)]);
+        assert_eq!(preview.conflict_count, 3);
+
+        let preview = FileService::preview_batch_rename(&paths[2..], &replace("b", "x/y")).unwrap();
+        assert_eq!(preview.items[0].conflict, Some(RenameConflict::InvalidName));
+
+        let result = FileService::batch_rename(&paths, &colliding);
+        assert!(result.unwrap_err().contains("3 件"));
+        assert!(root.join("a1.txt").exists() && root.join("b.txt").exists());
+        assert_eq!(fs::read_to_string(root.join("keep.txt")).unwrap(), "keep.txt");
+
+        assert!(FileService::preview_batch_rename(&[paths[0].clone(), paths[0].clone()], &replace("a", "c")).is_err());
+    }
+
+    #[test]
+    fn test_batch_rename_swaps_names() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("1.txt"), "one").unwrap();
+        fs::write(root.join("2.txt"), "two").unwrap();
+        fs::write(root.join("3.txt"), "three").unwrap();
+
+        // 2→1, 1→2 の入れ替えと、変更しない 3
+        let paths = vec![root.join("2.txt"), root.join("1.txt"), root.join("3.txt")];
+        let preview = FileService::preview_batch_rename(&paths, &rename_options(vec![number_rule(InsertPosition::Replace, 0)])).unwrap();
+        assert_eq!(preview.conflict_count, 0);
+        assert_eq!(preview.changed_count, 2);
+
+        let renamed = FileService::batch_rename(&paths, &rename_options(vec![number_rule(InsertPosition::Replace, 0)])).unwrap();
+        assert_eq!(renamed.len(), 2);
+        assert_eq!(fs::read_to_string(root.join("1.txt")).unwrap(), "two");
+        assert_eq!(fs::read_to_string(root.join("2.txt")).unwrap(), "one");
+        assert_eq!(fs::read_to_string(root.join("3.txt")).unwrap(), "three");
+        assert_eq!(fs::read_dir(root).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_rename_all_rolls_back_on_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+        fs::write(root.join("taken.txt"), "taken").unwrap();
+
+        let moves = vec![
+            (root.join("a.txt"), root.join("c.txt")),
+            (root.join("b.txt"), root.join("taken.txt")),
+        ];
+        assert!(rename_all(&moves).is_err());
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "b");
+        assert_eq!(fs::read_to_string(root.join("taken.txt")).unwrap(), "taken");
+        assert!(!root.join("c.txt").exists());
+        assert_eq!(fs::read_dir(root).unwrap().count(), 3);
+    }
+}
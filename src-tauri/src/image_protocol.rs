@@ -0,0 +1,362 @@
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use chrono::{DateTime, Utc};
+use percent_encoding::percent_decode_str;
+use tauri::http::{header, response, Method, Request, Response, StatusCode};
+use tauri::Manager;
+use crate::scope_service::{ScopeState, FILE_NOT_FOUND};
+
+/// 画像配信用のURIスキーム名
+///
+/// フロントエンドでは `convertFileSrc(path, "localimg")` でURLを組み立てる。
+pub const SCHEME: &str = "localimg";
+
+/// すべてのレスポンスに付ける Content-Security-Policy
+///
+/// SVG が文書として開かれてもスクリプトや外部リソースを読み込ませない。
+const CONTENT_SECURITY_POLICY: &str = "default-src 'none'; style-src 'unsafe-inline'";
+
+/// Range ヘッダーの解釈結果
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    /// Range 指定なし（または解釈できないため全体を返す）
+    Full,
+    /// 開始・終了位置（終了位置を含む）
+    Partial(u64, u64),
+    /// ファイルサイズの範囲外
+    Unsatisfiable,
+}
+
+/// 画像ファイルをURIスキーム経由で配信するハンドラ
+pub struct ImageProtocol;
+
+impl ImageProtocol {
+
+    /// Tauri から呼ばれる非同期ハンドラ。ファイル読み込みはブロッキングスレッドで行う
+    pub fn handle_async(
+        ctx: tauri::UriSchemeContext<'_, tauri::Wry>,
+        request: Request<Vec<u8>>,
+        responder: tauri::UriSchemeResponder,
+    ) {
+        let app = ctx.app_handle().clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let scope = app.state::<ScopeState>();
+            responder.respond(Self::handle(&scope, &request));
+        });
+    }
+
+    /// リクエストを処理してレスポンスを作る
+    pub fn handle(scope: &ScopeState, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+        if request.method() != Method::GET && request.method() != Method::HEAD {
+            return error_response(StatusCode::METHOD_NOT_ALLOWED, "GET/HEAD のみ対応しています");
+        }
+
+        // convertFileSrc はパス全体を encodeURIComponent して1セグメントにする
+        let encoded = request.uri().path().trim_start_matches('/');
+        let requested = match percent_decode_str(encoded).decode_utf8() {
+            Ok(path) => path.to_string(),
+            Err(_) => return error_response(StatusCode::BAD_REQUEST, "パスのデコードに失敗しました"),
+        };
+
+        // 範囲外のパスは存在の有無にかかわらず 403 を返す
+        let path = match scope.resolve_str(&requested) {
+            Ok(path) => path,
+            Err(e) if e == FILE_NOT_FOUND => return error_response(StatusCode::NOT_FOUND, &e),
+            Err(e) => return error_response(StatusCode::FORBIDDEN, &e),
+        };
+
+        let Some(content_type) = image_mime_type(Path::new(&path)) else {
+            return error_response(StatusCode::UNSUPPORTED_MEDIA_TYPE, "サポートされていないファイル形式です");
+        };
+
+        match serve_file(Path::new(&path), content_type, request) {
+            Ok(response) => response,
+            Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, &e),
+        }
+    }
+}
+
+/// 拡張子から画像のContent-Typeを求める
+pub fn image_mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "bmp" => Some("image/bmp"),
+        "svg" => Some("image/svg+xml"),
+        "ico" => Some("image/x-icon"),
+        "avif" => Some("image/avif"),
+        _ => None,
+    }
+}
+
+/// ファイルを読み込み、キャッシュ・Range 指定に応じたレスポンスを作る
+fn serve_file(path: &Path, content_type: &str, request: &Request<Vec<u8>>) -> Result<Response<Vec<u8>>, String> {
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("ファイル情報の取得に失敗しました: {}", e))?;
+    let size = metadata.len();
+    let modified = metadata.modified().ok();
+    let etag = entity_tag(size, modified);
+
+    let builder = secure_builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ETAG, &etag)
+        // 内容はファイル更新で変わるため、毎回 ETag で再検証させる
+        .header(header::CACHE_CONTROL, "no-cache");
+    let builder = match modified {
+        Some(time) => builder.header(header::LAST_MODIFIED, http_date(time.into())),
+        None => builder,
+    };
+
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok());
+    if if_none_match.is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*")) {
+        return builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Vec::new())
+            .map_err(|e| e.to_string());
+    }
+
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(|value| parse_range(value, size))
+        .unwrap_or(ByteRange::Full);
+
+    let (status, start, length, builder) = match range {
+        ByteRange::Full => (StatusCode::OK, 0, size, builder),
+        ByteRange::Partial(start, end) => (
+            StatusCode::PARTIAL_CONTENT,
+            start,
+            end - start + 1,
+            builder.header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, size)),
+        ),
+        ByteRange::Unsatisfiable => {
+            return builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", size))
+                .body(Vec::new())
+                .map_err(|e| e.to_string());
+        }
+    };
+
+    let body = if request.method() == Method::HEAD {
+        Vec::new()
+    } else {
+        read_range(path, start, length)?
+    };
+
+    builder
+        .status(status)
+        .header(header::CONTENT_LENGTH, length)
+        .body(body)
+        .map_err(|e| e.to_string())
+}
+
+/// ファイルの指定範囲だけを読み込む
+fn read_range(path: &Path, start: u64, length: u64) -> Result<Vec<u8>, String> {
+    let mut file = File::open(path)
+        .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+    file.seek(SeekFrom::Start(start))
+        .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+
+    let mut buffer = Vec::with_capacity(length as usize);
+    file.take(length)
+        .read_to_end(&mut buffer)
+        .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+    Ok(buffer)
+}
+
+/// Range ヘッダー（"bytes=0-99" / "bytes=100-" / "bytes=-100"）を解釈する
+///
+/// 複数範囲の指定には対応せず、全体を返す。
+fn parse_range(value: &str, size: u64) -> ByteRange {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", "") => return ByteRange::Full,
+        // 末尾から指定バイト数
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return ByteRange::Unsatisfiable,
+            Ok(suffix) => (size.saturating_sub(suffix), size.saturating_sub(1)),
+            Err(_) => return ByteRange::Full,
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, size.saturating_sub(1)),
+            Err(_) => return ByteRange::Full,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(size.saturating_sub(1))),
+            _ => return ByteRange::Full,
+        },
+    };
+
+    if size == 0 || start >= size {
+        ByteRange::Unsatisfiable
+    } else {
+        ByteRange::Partial(start, end)
+    }
+}
+
+/// サイズと更新日時から ETag を作る
+fn entity_tag(size: u64, modified: Option<std::time::SystemTime>) -> String {
+    let nanos = modified
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+    format!("\"{:x}-{:x}\"", nanos, size)
+}
+
+/// HTTP の日付形式（RFC 7231）に変換
+fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// セキュリティ用ヘッダーを設定したレスポンスビルダーを作る
+fn secure_builder() -> response::Builder {
+    Response::builder()
+        .header(header::CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY)
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+}
+
+/// エラー用のテキストレスポンスを作る
+fn error_response(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    secure_builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(message.as_bytes().to_vec())
+        .unwrap_or_else(|_| Response::new(Vec::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scope_service::ScopeService;
+    use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+    use tempfile::TempDir;
+
+    fn setup() -> (ScopeState, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let allowed = temp_dir.path().join("allowed");
+        fs::create_dir_all(&allowed).unwrap();
+        fs::write(allowed.join("画像.png"), b"0123456789").unwrap();
+        fs::write(allowed.join("notes.txt"), b"text").unwrap();
+        fs::write(temp_dir.path().join("secret.png"), b"secret").unwrap();
+        (ScopeState::new(ScopeService::new(vec![allowed])), temp_dir)
+    }
+
+    fn request(path: &Path, headers: &[(&str, &str)]) -> Request<Vec<u8>> {
+        let encoded = utf8_percent_encode(&path.to_string_lossy(), NON_ALPHANUMERIC).to_string();
+        let mut builder = Request::builder().uri(format!("localimg://localhost/{}", encoded));
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Vec::new()).unwrap()
+    }
+
+    #[test]
+    fn test_serves_whole_file_with_headers() {
+        let (scope, temp_dir) = setup();
+        let response = ImageProtocol::handle(&scope, &request(&temp_dir.path().join("allowed/画像.png"), &[]));
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "10");
+        assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+        assert!(response.headers().contains_key(header::ETAG));
+        assert!(response.headers().contains_key(header::LAST_MODIFIED));
+        assert_eq!(response.body(), b"0123456789");
+    }
+
+    #[test]
+    fn test_every_response_has_security_headers() {
+        let (scope, temp_dir) = setup();
+        let ok = ImageProtocol::handle(&scope, &request(&temp_dir.path().join("allowed/画像.png"), &[]));
+        let denied = ImageProtocol::handle(&scope, &request(&temp_dir.path().join("secret.png"), &[]));
+
+        for response in [ok, denied] {
+            assert_eq!(response.headers()[header::CONTENT_SECURITY_POLICY], CONTENT_SECURITY_POLICY);
+            assert_eq!(response.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+        }
+    }
+
+    #[test]
+    fn test_serves_partial_content() {
+        let (scope, temp_dir) = setup();
+        let path = temp_dir.path().join("allowed/画像.png");
+        let response = ImageProtocol::handle(&scope, &request(&path, &[("Range", "bytes=2-5")]));
+
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 2-5/10");
+        assert_eq!(response.body(), b"2345");
+
+        let response = ImageProtocol::handle(&scope, &request(&path, &[("Range", "bytes=20-")]));
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes */10");
+    }
+
+    #[test]
+    fn test_not_modified_when_etag_matches() {
+        let (scope, temp_dir) = setup();
+        let path = temp_dir.path().join("allowed/画像.png");
+        let first = ImageProtocol::handle(&scope, &request(&path, &[]));
+        let etag = first.headers()[header::ETAG].to_str().unwrap().to_string();
+
+        let second = ImageProtocol::handle(&scope, &request(&path, &[("If-None-Match", &etag)]));
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+        assert!(second.body().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_out_of_scope_missing_and_non_images() {
+        let (scope, temp_dir) = setup();
+        let denied = ImageProtocol::handle(&scope, &request(&temp_dir.path().join("secret.png"), &[]));
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+
+        let missing = ImageProtocol::handle(&scope, &request(&temp_dir.path().join("allowed/none.png"), &[]));
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+        let missing_outside = ImageProtocol::handle(&scope, &request(&temp_dir.path().join("none.png"), &[]));
+        assert_eq!(missing_outside.status(), StatusCode::FORBIDDEN);
+
+        let text = ImageProtocol::handle(&scope, &request(&temp_dir.path().join("allowed/notes.txt"), &[]));
+        assert_eq!(text.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[test]
+    fn test_head_request_has_no_body() {
+        let (scope, temp_dir) = setup();
+        let mut head = request(&temp_dir.path().join("allowed/画像.png"), &[]);
+        *head.method_mut() = Method::HEAD;
+
+        let response = ImageProtocol::handle(&scope, &head);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "10");
+        assert!(response.body().is_empty());
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-99", 1000), ByteRange::Partial(0, 99));
+        assert_eq!(parse_range("bytes=900-", 1000), ByteRange::Partial(900, 999));
+        assert_eq!(parse_range("bytes=-100", 1000), ByteRange::Partial(900, 999));
+        assert_eq!(parse_range("bytes=990-2000", 1000), ByteRange::Partial(990, 999));
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), ByteRange::Full);
+        assert_eq!(parse_range("items=0-1", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=5-1", 1000), ByteRange::Full);
+    }
+}
@@ -0,0 +1,1323 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Seek};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use base64::{Engine as _, engine::general_purpose};
+use exif::{In, Tag, Value};
+use image::codecs::gif::GifDecoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{PngDecoder, PngEncoder};
+use image::codecs::webp::{WebPDecoder, WebPEncoder};
+use image::imageops::FilterType;
+use image::metadata::{LoopCount, Orientation};
+use image::{AnimationDecoder, DynamicImage, Frames, ImageDecoder, ImageFormat, ImageReader, RgbaImage};
+use resvg::{tiny_skia, usvg};
+use serde::{Deserialize, Serialize};
+use crate::dialog_service::{DialogFilter, DialogService, SaveDialogOptions};
+use crate::temp_path::temp_path_for;
+
+/// 形式判定のために先頭から読むバイト数
+const SNIFF_LENGTH: usize = 512;
+
+/// 保存ダイアログで最後に使ったディレクトリを覚えておく用途名
+const DIALOG_PURPOSE: &str = "image-export";
+
+/// JPEG 保存時の既定品質
+const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+/// リサイズ後の一辺の上限（誤入力で巨大な画像を確保しないため）
+const MAX_DIMENSION: u32 = 16384;
+
+/// アニメーションのフレームを1回に返す既定の数
+const DEFAULT_FRAME_PAGE_SIZE: usize = 20;
+
+/// アニメーションのフレームを1回に返す数の上限
+const MAX_FRAME_PAGE_SIZE: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GpsInfo {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ExifInfo {
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens_model: Option<String>,
+    pub orientation: Option<u16>,
+    pub date_taken: Option<String>,
+    pub exposure_time: Option<String>,
+    pub f_number: Option<f64>,
+    pub iso: Option<u32>,
+    pub focal_length: Option<f64>,
+    pub gps: Option<GpsInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ImageInfo {
+    pub name: String,
+    pub size: u64,
+    /// マジックバイトから判定した形式（png, jpeg, gif, webp, bmp, svg など）
+    pub format: Option<String>,
+    pub mime_type: Option<String>,
+    /// 拡張子が実際の形式と一致しているか
+    pub extension_matches: bool,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub color_type: Option<String>,
+    pub frame_count: Option<u32>,
+    pub animated: bool,
+    pub exif: Option<ExifInfo>,
+}
+
+/// リサイズ時の補間フィルター
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResizeFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    #[default]
+    Lanczos3,
+}
+
+impl From<ResizeFilter> for FilterType {
+    fn from(filter: ResizeFilter) -> Self {
+        match filter {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Triangle => FilterType::Triangle,
+            ResizeFilter::CatmullRom => FilterType::CatmullRom,
+            ResizeFilter::Gaussian => FilterType::Gaussian,
+            ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+/// 画像に適用する編集操作（指定順に適用される）
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImageEditOperation {
+    /// 時計回りに回転（90 / 180 / 270 度）
+    Rotate { degrees: u32 },
+    FlipHorizontal,
+    FlipVertical,
+    /// EXIF の Orientation に従って正立させる
+    AutoOrient,
+    Crop { x: u32, y: u32, width: u32, height: u32 },
+    /// `keep_aspect` が true の場合は縦横比を保って指定サイズに収める
+    Resize {
+        width: u32,
+        height: u32,
+        #[serde(default)]
+        filter: ResizeFilter,
+        #[serde(default)]
+        keep_aspect: bool,
+    },
+}
+
+/// 編集結果の保存形式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Png,
+    Jpeg,
+    /// WebP は可逆圧縮のみ対応（品質指定は無視される）
+    WebP,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::WebP => "webp",
+        }
+    }
+
+    fn filter_name(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "PNG",
+            OutputFormat::Jpeg => "JPEG",
+            OutputFormat::WebP => "WebP",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ImageEditRequest {
+    pub operations: Vec<ImageEditOperation>,
+    pub format: OutputFormat,
+    /// JPEG の品質（1〜100）。省略時は 90
+    pub quality: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EditedImage {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    pub size: u64,
+}
+
+/// PNG に変換した画像
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RenderedImage {
+    /// 元の形式（svg, bmp, tiff, ico など）
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    /// PNG の Base64
+    pub data: String,
+}
+
+/// アニメーションの1フレーム（前のフレームと合成済みの画像全体）
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AnimationFrame {
+    pub index: usize,
+    /// 次のフレームまでの表示時間
+    pub delay_ms: u32,
+    /// PNG の Base64
+    pub data: String,
+}
+
+/// アニメーション画像のフレーム一覧（1ページ分）
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AnimationFrames {
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    /// 繰り返し回数（None は無限に繰り返す）
+    pub loop_count: Option<u32>,
+    pub frames: Vec<AnimationFrame>,
+    /// frames の表示時間の合計
+    pub total_duration_ms: u64,
+    /// 最初のフレームの位置
+    pub offset: usize,
+    /// 続きのフレームがある場合 true
+    pub has_more: bool,
+}
+
+/// 先頭バイトから判定した画像形式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DetectedFormat {
+    Raster(ImageFormat),
+    Svg,
+}
+
+impl DetectedFormat {
+    /// 形式名（小文字）
+    pub fn name(&self) -> String {
+        match self {
+            DetectedFormat::Raster(format) => format!("{:?}", format).to_lowercase(),
+            DetectedFormat::Svg => "svg".to_string(),
+        }
+    }
+
+    pub fn mime_type(&self) -> String {
+        match self {
+            DetectedFormat::Raster(format) => format.to_mime_type().to_string(),
+            DetectedFormat::Svg => "image/svg+xml".to_string(),
+        }
+    }
+
+    /// 拡張子がこの形式のものか
+    pub fn matches_extension(&self, extension: &str) -> bool {
+        let extension = extension.to_lowercase();
+        match self {
+            DetectedFormat::Raster(format) => format.extensions_str().contains(&extension.as_str()),
+            DetectedFormat::Svg => extension == "svg" || extension == "svgz",
+        }
+    }
+}
+
+/// 画像のメタデータ取得と編集を担当するサービスクラス
+pub struct ImageService;
+
+impl ImageService {
+
+    /// 先頭バイトから画像形式を判定（拡張子は見ない）
+    pub fn sniff_format(header: &[u8]) -> Option<DetectedFormat> {
+        if let Ok(format) = image::guess_format(header) {
+            return Some(DetectedFormat::Raster(format));
+        }
+
+        // SVG はテキストなので、先頭付近に <svg があるかで判定する
+        let text = String::from_utf8_lossy(header);
+        let trimmed = text.trim_start_matches('\u{feff}').trim_start();
+        if trimmed.starts_with("<svg") || ((trimmed.starts_with("<?xml") || trimmed.starts_with("<!DOCTYPE")) && text.contains("<svg")) {
+            return Some(DetectedFormat::Svg);
+        }
+        None
+    }
+
+    /// ファイルの先頭を読んで画像形式を判定
+    pub fn detect_format(path: &Path) -> Result<Option<DetectedFormat>, String> {
+        let mut header = Vec::with_capacity(SNIFF_LENGTH);
+        File::open(path)
+            .and_then(|file| file.take(SNIFF_LENGTH as u64).read_to_end(&mut header))
+            .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+        Ok(Self::sniff_format(&header))
+    }
+
+    /// 画像の形式・サイズ・色・フレーム数・EXIFを取得
+    ///
+    /// `strip_gps` が true の場合は位置情報を結果から除く。
+    pub fn get_image_info(file_path: &str, strip_gps: bool) -> Result<ImageInfo, String> {
+        let path = Path::new(file_path);
+        if !path.exists() {
+            return Err("ファイルが見つかりません".to_string());
+        }
+
+        let metadata = fs::metadata(path)
+            .map_err(|e| format!("ファイル情報の取得に失敗しました: {}", e))?;
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("不明")
+            .to_string();
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+
+        let detected = Self::detect_format(path)?;
+        let mut info = ImageInfo {
+            name,
+            size: metadata.len(),
+            format: detected.map(|format| format.name()),
+            mime_type: detected.map(|format| format.mime_type()),
+            extension_matches: detected.map(|format| format.matches_extension(extension)).unwrap_or(false),
+            width: None,
+            height: None,
+            color_type: None,
+            frame_count: None,
+            animated: false,
+            exif: None,
+        };
+
+        let Some(DetectedFormat::Raster(format)) = detected else {
+            return Ok(info);
+        };
+
+        // デコーダーが有効な形式のみ寸法と色を取得できる
+        if let Ok((width, height, color_type)) = Self::read_header(path, format) {
+            info.width = Some(width);
+            info.height = Some(height);
+            info.color_type = Some(color_type);
+        }
+
+        if matches!(format, ImageFormat::Gif | ImageFormat::WebP | ImageFormat::Png) {
+            let file = File::open(path)
+                .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+            info.frame_count = frame_count(format, &mut BufReader::new(file));
+            info.animated = info.frame_count.is_some_and(|count| count > 1);
+        }
+
+        info.exif = Self::read_exif(path).map(|mut exif| {
+            if strip_gps {
+                exif.gps = None;
+            }
+            exif
+        });
+
+        Ok(info)
+    }
+
+    /// 画素データをデコードせずに寸法と色の種類を読む
+    fn read_header(path: &Path, format: ImageFormat) -> Result<(u32, u32, String), String> {
+        let file = File::open(path)
+            .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+        let mut reader = ImageReader::new(BufReader::new(file));
+        reader.set_format(format);
+        let decoder = reader
+            .into_decoder()
+            .map_err(|e| format!("画像ヘッダーの読み込みに失敗しました: {}", e))?;
+        let (width, height) = decoder.dimensions();
+        Ok((width, height, format!("{:?}", decoder.color_type())))
+    }
+
+    /// EXIF を読み取る。EXIF を持たない画像は None
+    pub fn read_exif(path: &Path) -> Option<ExifInfo> {
+        let file = File::open(path).ok()?;
+        let exif = exif::Reader::new()
+            .read_from_container(&mut BufReader::new(file))
+            .ok()?;
+
+        let ascii = |tag: Tag| exif.get_field(tag, In::PRIMARY).and_then(|field| ascii_value(&field.value));
+        let rational = |tag: Tag| exif.get_field(tag, In::PRIMARY).and_then(|field| rational_value(&field.value, 0));
+        let uint = |tag: Tag| exif.get_field(tag, In::PRIMARY).and_then(|field| field.value.get_uint(0));
+
+        let gps = match (
+            exif.get_field(Tag::GPSLatitude, In::PRIMARY),
+            exif.get_field(Tag::GPSLongitude, In::PRIMARY),
+        ) {
+            (Some(latitude), Some(longitude)) => {
+                let latitude = dms_to_degrees(&latitude.value)
+                    .map(|value| if ascii(Tag::GPSLatitudeRef).as_deref() == Some("S") { -value } else { value });
+                let longitude = dms_to_degrees(&longitude.value)
+                    .map(|value| if ascii(Tag::GPSLongitudeRef).as_deref() == Some("W") { -value } else { value });
+                match (latitude, longitude) {
+                    (Some(latitude), Some(longitude)) => Some(GpsInfo {
+                        latitude,
+                        longitude,
+                        altitude: rational(Tag::GPSAltitude),
+                    }),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        Some(ExifInfo {
+            camera_make: ascii(Tag::Make),
+            camera_model: ascii(Tag::Model),
+            lens_model: ascii(Tag::LensModel),
+            orientation: uint(Tag::Orientation).map(|value| value as u16),
+            date_taken: ascii(Tag::DateTimeOriginal).or_else(|| ascii(Tag::DateTime)),
+            exposure_time: exif
+                .get_field(Tag::ExposureTime, In::PRIMARY)
+                .map(|field| field.display_value().to_string()),
+            f_number: rational(Tag::FNumber),
+            iso: uint(Tag::PhotographicSensitivity),
+            focal_length: rational(Tag::FocalLength),
+            gps,
+        })
+    }
+
+    /// 画像を PNG に変換する（SVG はラスタライズし、BMP・TIFF・ICO などもブラウザで表示できるようにする）
+    ///
+    /// `width` / `height` を指定した場合は縦横比を保ってその範囲に収める。省略時は元のサイズ。
+    pub fn render_image(file_path: &str, width: Option<u32>, height: Option<u32>) -> Result<RenderedImage, String> {
+        let path = Path::new(file_path);
+        if !path.is_file() {
+            return Err("ファイルが見つかりません".to_string());
+        }
+        // gzip 圧縮された SVG は先頭では判定できないため拡張子で判断する
+        let is_svgz = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("svgz"));
+        let detected = Self::detect_format(path)?
+            .or(is_svgz.then_some(DetectedFormat::Svg))
+            .ok_or_else(|| "画像形式を判定できません".to_string())?;
+
+        let image = match detected {
+            DetectedFormat::Svg => {
+                let data = fs::read(path).map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+                rasterize_svg(&data, width, height)?
+            }
+            DetectedFormat::Raster(format) => {
+                let file = File::open(path).map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+                let mut reader = ImageReader::new(BufReader::new(file));
+                reader.set_format(format);
+                let image = reader
+                    .decode()
+                    .map_err(|e| format!("画像のデコードに失敗しました: {}", e))?;
+                let (target_width, target_height) =
+                    fit_size(image.width() as f32, image.height() as f32, width, height)?;
+                if (target_width, target_height) == (image.width(), image.height()) {
+                    image
+                } else {
+                    image.resize_exact(target_width, target_height, FilterType::Lanczos3)
+                }
+            }
+        };
+
+        Ok(RenderedImage {
+            format: detected.name(),
+            width: image.width(),
+            height: image.height(),
+            data: general_purpose::STANDARD.encode(encode_png(&image)?),
+        })
+    }
+
+    /// アニメーション GIF・WebP・PNG（APNG）の `offset` 番目から最大 `limit` 枚のフレームを表示時間とともに取り出す
+    ///
+    /// 各フレームは前のフレームと合成した画像全体を PNG にしたもの。合成のため `offset` より前の
+    /// フレームもデコードするが、PNG には変換しない。
+    pub fn extract_frames(file_path: &str, offset: usize, limit: Option<usize>) -> Result<AnimationFrames, String> {
+        let path = Path::new(file_path);
+        if !path.is_file() {
+            return Err("ファイルが見つかりません".to_string());
+        }
+        let format = match Self::detect_format(path)? {
+            Some(DetectedFormat::Raster(format @ (ImageFormat::Gif | ImageFormat::WebP | ImageFormat::Png))) => format,
+            _ => return Err("アニメーションに対応していない形式です".to_string()),
+        };
+        let limit = limit.unwrap_or(DEFAULT_FRAME_PAGE_SIZE).clamp(1, MAX_FRAME_PAGE_SIZE);
+        let animation = open_animation(path, format)?;
+
+        let mut frames = Vec::new();
+        let mut total_duration_ms = 0u64;
+        let mut has_more = false;
+        for (index, frame) in animation.frames.enumerate() {
+            if index >= offset.saturating_add(limit) {
+                has_more = true;
+                break;
+            }
+            let frame = frame.map_err(|e| format!("フレームのデコードに失敗しました: {}", e))?;
+            if index < offset {
+                continue;
+            }
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+            let delay_ms = numerator.checked_div(denominator).unwrap_or(0);
+            total_duration_ms += u64::from(delay_ms);
+            let image = DynamicImage::ImageRgba8(frame.into_buffer());
+            frames.push(AnimationFrame {
+                index,
+                delay_ms,
+                data: general_purpose::STANDARD.encode(encode_png(&image)?),
+            });
+        }
+
+        Ok(AnimationFrames {
+            format: DetectedFormat::Raster(format).name(),
+            width: animation.width,
+            height: animation.height,
+            loop_count: match animation.loop_count {
+                LoopCount::Infinite => None,
+                LoopCount::Finite(count) => Some(count.get()),
+            },
+            frames,
+            total_duration_ms,
+            offset,
+            has_more,
+        })
+    }
+}
+
+impl ImageService {
+
+    /// 編集した画像を保存ダイアログで選ばれた場所に書き出す
+    ///
+    /// キャンセルされた場合は `None` を返す。保存先が元画像と同じ場合は
+    /// `overwrite_source` が true のときだけ上書きする。初期表示は前回保存した
+    /// ディレクトリで、まだない場合は元画像のディレクトリ。
+    pub async fn save_edited_image(
+        app: &tauri::AppHandle,
+        dialogs: &DialogService,
+        source: &str,
+        request: &ImageEditRequest,
+        overwrite_source: bool,
+    ) -> Result<Option<EditedImage>, String> {
+        let source = Path::new(source);
+        let stem = source
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("image");
+        let (remembered, _) = dialogs.initial_location(Some(DIALOG_PURPOSE), None)?;
+        let options = SaveDialogOptions {
+            purpose: Some(DIALOG_PURPOSE.to_string()),
+            filters: vec![DialogFilter {
+                name: request.format.filter_name().to_string(),
+                extensions: vec![request.format.extension().to_string()],
+            }],
+            default_path: match remembered {
+                Some(_) => None,
+                None => source.parent().map(|parent| parent.to_string_lossy().to_string()),
+            },
+            file_name: Some(format!("{}_edited.{}", stem, request.format.extension())),
+            ..Default::default()
+        };
+        let Some(destination) = dialogs.save(app, &options).await? else {
+            return Ok(None);
+        };
+
+        // デコード・編集・エンコードは重いので、別スレッドで実行する
+        let source = source.to_path_buf();
+        let request = request.clone();
+        tauri::async_runtime::spawn_blocking(move || Self::edit_image(&source, &request, &destination, overwrite_source))
+            .await
+            .map_err(|e| format!("画像の保存に失敗しました: {}", e))?
+            .map(Some)
+    }
+
+    /// 元画像に編集操作を適用して `destination` に保存
+    pub fn edit_image(
+        source: &Path,
+        request: &ImageEditRequest,
+        destination: &Path,
+        overwrite_source: bool,
+    ) -> Result<EditedImage, String> {
+        if !source.exists() {
+            return Err("ファイルが見つかりません".to_string());
+        }
+        if !overwrite_source && is_same_file(source, destination) {
+            return Err("元の画像を上書きするには確認が必要です".to_string());
+        }
+
+        let image = ImageReader::open(source)
+            .and_then(|reader| reader.with_guessed_format())
+            .map_err(|e| format!("画像の読み込みに失敗しました: {}", e))?
+            .decode()
+            .map_err(|e| format!("画像のデコードに失敗しました: {}", e))?;
+
+        // 自動回転が要求されたときだけ EXIF を読む
+        let orientation = if request.operations.contains(&ImageEditOperation::AutoOrient) {
+            Self::read_exif(source).and_then(|exif| exif.orientation)
+        } else {
+            None
+        };
+
+        let edited = Self::apply_operations(image, &request.operations, orientation)?;
+        Self::write_image(&edited, request.format, request.quality, destination)?;
+
+        let size = fs::metadata(destination)
+            .map_err(|e| format!("ファイル情報の取得に失敗しました: {}", e))?
+            .len();
+        Ok(EditedImage {
+            path: destination.to_string_lossy().to_string(),
+            width: edited.width(),
+            height: edited.height(),
+            size,
+        })
+    }
+
+    /// 編集操作を順番に適用
+    ///
+    /// `orientation` は `AutoOrient` で使う EXIF の Orientation 値。
+    pub fn apply_operations(
+        mut image: DynamicImage,
+        operations: &[ImageEditOperation],
+        orientation: Option<u16>,
+    ) -> Result<DynamicImage, String> {
+        for operation in operations {
+            image = match *operation {
+                ImageEditOperation::Rotate { degrees } => match degrees % 360 {
+                    0 => image,
+                    90 => image.rotate90(),
+                    180 => image.rotate180(),
+                    270 => image.rotate270(),
+                    _ => return Err(format!("回転角度は90度単位で指定してください: {}", degrees)),
+                },
+                ImageEditOperation::FlipHorizontal => image.fliph(),
+                ImageEditOperation::FlipVertical => image.flipv(),
+                ImageEditOperation::AutoOrient => {
+                    if let Some(orientation) = orientation
+                        .and_then(|value| u8::try_from(value).ok())
+                        .and_then(Orientation::from_exif)
+                    {
+                        image.apply_orientation(orientation);
+                    }
+                    image
+                }
+                ImageEditOperation::Crop { x, y, width, height } => {
+                    let fits = width > 0
+                        && height > 0
+                        && x.checked_add(width).is_some_and(|right| right <= image.width())
+                        && y.checked_add(height).is_some_and(|bottom| bottom <= image.height());
+                    if !fits {
+                        return Err(format!(
+                            "切り抜き範囲が画像の外にはみ出しています: {}x{}+{}+{}（画像 {}x{}）",
+                            width, height, x, y, image.width(), image.height()
+                        ));
+                    }
+                    image.crop_imm(x, y, width, height)
+                }
+                ImageEditOperation::Resize { width, height, filter, keep_aspect } => {
+                    if width == 0 || height == 0 || width > MAX_DIMENSION || height > MAX_DIMENSION {
+                        return Err(format!(
+                            "リサイズ後のサイズは1〜{}ピクセルで指定してください: {}x{}",
+                            MAX_DIMENSION, width, height
+                        ));
+                    }
+                    if keep_aspect {
+                        image.resize(width, height, filter.into())
+                    } else {
+                        image.resize_exact(width, height, filter.into())
+                    }
+                }
+            };
+        }
+        Ok(image)
+    }
+
+    /// 指定形式でエンコードし、一時ファイル経由で書き出す
+    fn write_image(
+        image: &DynamicImage,
+        format: OutputFormat,
+        quality: Option<u8>,
+        destination: &Path,
+    ) -> Result<(), String> {
+        let quality = quality.unwrap_or(DEFAULT_JPEG_QUALITY);
+        if !(1..=100).contains(&quality) {
+            return Err(format!("品質は1〜100で指定してください: {}", quality));
+        }
+
+        let temp_path = temp_path_for(destination);
+        let result = File::create(&temp_path)
+            .map_err(|e| format!("ファイルの作成に失敗しました: {}", e))
+            .and_then(|file| {
+                let writer = BufWriter::new(file);
+                match format {
+                    OutputFormat::Png => image.write_with_encoder(PngEncoder::new(writer)),
+                    // JPEG はアルファを持てないため RGB に落とす
+                    OutputFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+                        .write_with_encoder(JpegEncoder::new_with_quality(writer, quality)),
+                    OutputFormat::WebP => DynamicImage::ImageRgba8(image.to_rgba8())
+                        .write_with_encoder(WebPEncoder::new_lossless(writer)),
+                }
+                .map_err(|e| format!("画像のエンコードに失敗しました: {}", e))
+            })
+            .and_then(|_| {
+                fs::rename(&temp_path, destination)
+                    .map_err(|e| format!("ファイルの保存に失敗しました: {}", e))
+            });
+
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+}
+
+/// 2つのパスが同じファイルを指しているか（保存先が未作成なら false）
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// ASCII 型のEXIF値を文字列に変換（空文字は None）
+fn ascii_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Ascii(values) => values
+            .first()
+            .map(|bytes| String::from_utf8_lossy(bytes).trim_end_matches('\0').trim().to_string())
+            .filter(|s| !s.is_empty()),
+        _ => None,
+    }
+}
+
+/// 有理数型のEXIF値を f64 に変換
+fn rational_value(value: &Value, index: usize) -> Option<f64> {
+    match value {
+        Value::Rational(values) => values
+            .get(index)
+            .filter(|r| r.denom != 0)
+            .map(|r| r.to_f64()),
+        _ => None,
+    }
+}
+
+/// 度・分・秒の3要素の有理数を度に変換
+fn dms_to_degrees(value: &Value) -> Option<f64> {
+    let degrees = rational_value(value, 0)?;
+    let minutes = rational_value(value, 1).unwrap_or(0.0);
+    let seconds = rational_value(value, 2).unwrap_or(0.0);
+    Some(degrees + minutes / 60.0 + seconds / 3600.0)
+}
+
+/// アニメーション形式のフレーム数を数える（画素はデコードしない）
+///
+/// チャンク・ブロックの見出しだけを読み、中身は読み飛ばすためファイル全体は読み込まない。
+pub fn frame_count<R: Read + Seek>(format: ImageFormat, reader: &mut BufReader<R>) -> Option<u32> {
+    match format {
+        ImageFormat::Gif => gif_frame_count(reader),
+        ImageFormat::WebP => webp_frame_count(reader),
+        ImageFormat::Png => png_frame_count(reader),
+        _ => Some(1),
+    }
+}
+
+/// 固定長のバイト列を読む。途中でファイルが終わった場合は `None`
+fn read_bytes<const N: usize, R: Read>(reader: &mut R) -> Option<[u8; N]> {
+    let mut buffer = [0u8; N];
+    reader.read_exact(&mut buffer).ok()?;
+    Some(buffer)
+}
+
+/// 指定バイト数を読み飛ばす（バッファ内で済む場合はシステムコールを発行しない）
+fn skip_bytes<R: Read + Seek>(reader: &mut BufReader<R>, length: u64) -> Option<()> {
+    reader.seek_relative(i64::try_from(length).ok()?).ok()
+}
+
+/// GIF のブロックを辿って画像記述子の数を数える
+fn gif_frame_count<R: Read + Seek>(reader: &mut BufReader<R>) -> Option<u32> {
+    fn color_table_size(flags: u8) -> u64 {
+        if flags & 0x80 != 0 {
+            3 * (1 << ((flags & 0x07) + 1))
+        } else {
+            0
+        }
+    }
+
+    fn skip_sub_blocks<R: Read + Seek>(reader: &mut BufReader<R>) -> Option<()> {
+        loop {
+            let [length] = read_bytes::<1, _>(reader)?;
+            if length == 0 {
+                return Some(());
+            }
+            skip_bytes(reader, length as u64)?;
+        }
+    }
+
+    let header = read_bytes::<13, _>(reader)?;
+    if !header.starts_with(b"GIF") {
+        return None;
+    }
+    skip_bytes(reader, color_table_size(header[10]))?;
+
+    let mut frames = 0;
+    loop {
+        let next = match read_bytes::<1, _>(reader) {
+            // 拡張ブロック: 導入子・ラベルの後にサブブロックが続く
+            Some([0x21]) => read_bytes::<1, _>(reader).and_then(|_| skip_sub_blocks(reader)),
+            // 画像記述子: 10バイト + ローカルカラーテーブル + LZW最小符号長 + サブブロック
+            Some([0x2C]) => {
+                frames += 1;
+                read_bytes::<9, _>(reader)
+                    .and_then(|descriptor| skip_bytes(reader, color_table_size(descriptor[8]) + 1))
+                    .and_then(|_| skip_sub_blocks(reader))
+            }
+            _ => None,
+        };
+        if next.is_none() {
+            // トレーラーか、途中で切れたファイル
+            break;
+        }
+    }
+
+    (frames > 0).then_some(frames)
+}
+
+/// WebP の RIFF チャンクから ANMF（アニメーションフレーム）を数える
+fn webp_frame_count<R: Read + Seek>(reader: &mut BufReader<R>) -> Option<u32> {
+    let header = read_bytes::<12, _>(reader)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WEBP" {
+        return None;
+    }
+
+    let mut frames = 0;
+    while let Some(chunk) = read_bytes::<8, _>(reader) {
+        let size = u32::from_le_bytes(chunk[4..8].try_into().ok()?) as u64;
+        if &chunk[0..4] == b"ANMF" {
+            frames += 1;
+        }
+        // チャンクは偶数バイトに揃えられる
+        if skip_bytes(reader, size + (size & 1)).is_none() {
+            break;
+        }
+    }
+
+    Some(frames.max(1))
+}
+
+/// PNG の acTL チャンク（APNG）からフレーム数を読む
+fn png_frame_count<R: Read + Seek>(reader: &mut BufReader<R>) -> Option<u32> {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    if read_bytes::<8, _>(reader)? != SIGNATURE {
+        return None;
+    }
+
+    while let Some(chunk) = read_bytes::<8, _>(reader) {
+        let length = u32::from_be_bytes(chunk[0..4].try_into().ok()?) as u64;
+        match &chunk[4..8] {
+            b"acTL" => return read_bytes::<4, _>(reader).map(u32::from_be_bytes),
+            // acTL は IDAT より前に置かれるため、ここまでに無ければ静止画
+            b"IDAT" | b"IEND" => break,
+            // データと CRC を読み飛ばす
+            _ => skip_bytes(reader, length + 4)?,
+        }
+    }
+
+    Some(1)
+}
+
+/// フレームを取り出すために開いたアニメーション
+struct Animation<'a> {
+    width: u32,
+    height: u32,
+    loop_count: LoopCount,
+    frames: Frames<'a>,
+}
+
+fn open_animation<'a>(path: &Path, format: ImageFormat) -> Result<Animation<'a>, String> {
+    let file = File::open(path).map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+    let reader = BufReader::new(file);
+    let decode_error = |e: image::ImageError| format!("画像のデコードに失敗しました: {}", e);
+
+    match format {
+        ImageFormat::Gif => {
+            let decoder = GifDecoder::new(reader).map_err(decode_error)?;
+            let (width, height) = decoder.dimensions();
+            Ok(Animation { width, height, loop_count: decoder.loop_count(), frames: decoder.into_frames() })
+        }
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(reader).map_err(decode_error)?;
+            let (width, height) = decoder.dimensions();
+            Ok(Animation { width, height, loop_count: decoder.loop_count(), frames: decoder.into_frames() })
+        }
+        _ => {
+            let decoder = PngDecoder::new(reader).map_err(decode_error)?;
+            // 静止画の PNG を APNG として読むとフレームが無いため、先に確かめる
+            if !decoder.is_apng().map_err(decode_error)? {
+                return Err("アニメーション画像ではありません".to_string());
+            }
+            let (width, height) = decoder.dimensions();
+            let decoder = decoder.apng().map_err(decode_error)?;
+            Ok(Animation { width, height, loop_count: decoder.loop_count(), frames: decoder.into_frames() })
+        }
+    }
+}
+
+/// 縦横比を保って `width` × `height` に収まるサイズ（省略時は元のサイズ、上限を超える場合は縮小する）
+fn fit_size(source_width: f32, source_height: f32, width: Option<u32>, height: Option<u32>) -> Result<(u32, u32), String> {
+    if let Some(value) = [width, height].into_iter().flatten().find(|value| *value == 0 || *value > MAX_DIMENSION) {
+        return Err(format!("サイズは1〜{}ピクセルで指定してください: {}", MAX_DIMENSION, value));
+    }
+    let scale = match (width, height) {
+        (None, None) => 1.0,
+        (Some(width), None) => width as f32 / source_width,
+        (None, Some(height)) => height as f32 / source_height,
+        (Some(width), Some(height)) => (width as f32 / source_width).min(height as f32 / source_height),
+    };
+    let scale = scale.min(MAX_DIMENSION as f32 / source_width.max(source_height));
+    Ok((
+        ((source_width * scale).round() as u32).max(1),
+        ((source_height * scale).round() as u32).max(1),
+    ))
+}
+
+/// SVG の描画に使うシステムフォント（読み込みに時間がかかるため一度だけ読む）
+fn svg_font_database() -> Arc<usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut database = usvg::fontdb::Database::new();
+            database.load_system_fonts();
+            Arc::new(database)
+        })
+        .clone()
+}
+
+/// SVG を指定サイズの画像に描画する
+fn rasterize_svg(data: &[u8], width: Option<u32>, height: Option<u32>) -> Result<DynamicImage, String> {
+    let options = usvg::Options {
+        fontdb: svg_font_database(),
+        // アクセス範囲外のファイルを読まないよう、埋め込み（data URL）の画像だけを使う
+        image_href_resolver: usvg::ImageHrefResolver {
+            resolve_data: usvg::ImageHrefResolver::default_data_resolver(),
+            resolve_string: Box::new(|_, _| None),
+        },
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_data(data, &options).map_err(|e| format!("SVG の解析に失敗しました: {}", e))?;
+    let size = tree.size();
+    let (target_width, target_height) = fit_size(size.width(), size.height(), width, height)?;
+
+    let mut pixmap = tiny_skia::Pixmap::new(target_width, target_height)
+        .ok_or_else(|| "画像の確保に失敗しました".to_string())?;
+    let transform = tiny_skia::Transform::from_scale(
+        target_width as f32 / size.width(),
+        target_height as f32 / size.height(),
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    // tiny-skia はアルファ乗算済みなので、通常の RGBA に戻す
+    let pixels: Vec<u8> = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    RgbaImage::from_raw(target_width, target_height, pixels)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| "画像の確保に失敗しました".to_string())
+}
+
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    image
+        .write_with_encoder(PngEncoder::new(&mut bytes))
+        .map_err(|e| format!("画像のエンコードに失敗しました: {}", e))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exif::experimental::Writer;
+    use exif::{Field, Rational};
+    use image::codecs::gif::GifEncoder;
+    use image::{Frame, Rgb, RgbImage, Rgba, RgbaImage};
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    fn ascii_field(tag: Tag, value: &str) -> Field {
+        Field { tag, ifd_num: In::PRIMARY, value: Value::Ascii(vec![value.as_bytes().to_vec()]) }
+    }
+
+    fn dms(degrees: u32, minutes: u32, seconds: u32) -> Value {
+        Value::Rational(vec![
+            Rational { num: degrees, denom: 1 },
+            Rational { num: minutes, denom: 1 },
+            Rational { num: seconds, denom: 1 },
+        ])
+    }
+
+    /// EXIF（APP1）付きのJPEGを作る
+    fn jpeg_with_exif(path: &Path) {
+        let fields = vec![
+            ascii_field(Tag::Make, "Canon"),
+            ascii_field(Tag::Model, "EOS R5"),
+            ascii_field(Tag::DateTimeOriginal, "2024:05:01 10:20:30"),
+            Field { tag: Tag::Orientation, ifd_num: In::PRIMARY, value: Value::Short(vec![6]) },
+            Field { tag: Tag::PhotographicSensitivity, ifd_num: In::PRIMARY, value: Value::Short(vec![400]) },
+            Field {
+                tag: Tag::FNumber,
+                ifd_num: In::PRIMARY,
+                value: Value::Rational(vec![Rational { num: 28, denom: 10 }]),
+            },
+            ascii_field(Tag::GPSLatitudeRef, "N"),
+            Field { tag: Tag::GPSLatitude, ifd_num: In::PRIMARY, value: dms(35, 30, 0) },
+            ascii_field(Tag::GPSLongitudeRef, "W"),
+            Field { tag: Tag::GPSLongitude, ifd_num: In::PRIMARY, value: dms(139, 45, 0) },
+        ];
+        let mut writer = Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        let tiff = tiff.into_inner();
+
+        let mut jpeg = Cursor::new(Vec::new());
+        RgbImage::from_pixel(8, 4, Rgb([10, 20, 30]))
+            .write_to(&mut jpeg, ImageFormat::Jpeg)
+            .unwrap();
+        let jpeg = jpeg.into_inner();
+
+        // SOI の直後に APP1 セグメントを差し込む
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        out.extend_from_slice(b"Exif\0\0");
+        out.extend_from_slice(&tiff);
+        out.extend_from_slice(&jpeg[2..]);
+        fs::write(path, out).unwrap();
+    }
+
+    fn path_str(path: &Path) -> &str {
+        path.to_str().unwrap()
+    }
+
+    #[test]
+    fn test_sniff_format_ignores_extension() {
+        let temp_dir = TempDir::new().unwrap();
+        let disguised = temp_dir.path().join("photo.jpg");
+        RgbImage::from_pixel(3, 2, Rgb([0, 0, 0])).save_with_format(&disguised, ImageFormat::Png).unwrap();
+
+        let info = ImageService::get_image_info(path_str(&disguised), false).unwrap();
+        assert_eq!(info.format.as_deref(), Some("png"));
+        assert_eq!(info.mime_type.as_deref(), Some("image/png"));
+        assert!(!info.extension_matches);
+        assert_eq!((info.width, info.height), (Some(3), Some(2)));
+        assert_eq!(info.color_type.as_deref(), Some("Rgb8"));
+        assert_eq!(info.frame_count, Some(1));
+        assert!(!info.animated);
+    }
+
+    #[test]
+    fn test_sniff_svg_and_unknown() {
+        assert_eq!(
+            ImageService::sniff_format(b"<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>"),
+            Some(DetectedFormat::Svg)
+        );
+        assert_eq!(ImageService::sniff_format(b"hello world"), None);
+    }
+
+    #[test]
+    fn test_animated_gif_frame_count() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("anim.gif");
+        {
+            let file = File::create(&path).unwrap();
+            let mut encoder = GifEncoder::new(file);
+            for shade in [0u8, 128, 255] {
+                encoder.encode_frame(Frame::new(RgbaImage::from_pixel(4, 4, Rgba([shade, 0, 0, 255])))).unwrap();
+            }
+        }
+
+        let info = ImageService::get_image_info(path_str(&path), false).unwrap();
+        assert_eq!(info.format.as_deref(), Some("gif"));
+        assert_eq!(info.frame_count, Some(3));
+        assert!(info.animated);
+    }
+
+    #[test]
+    fn test_webp_and_png_frame_count_parsing() {
+        let mut webp = b"RIFF\0\0\0\0WEBP".to_vec();
+        for fourcc in [b"VP8X", b"ANIM", b"ANMF", b"ANMF"] {
+            webp.extend_from_slice(fourcc);
+            webp.extend_from_slice(&3u32.to_le_bytes());
+            webp.extend_from_slice(&[0, 0, 0, 0]);
+        }
+        assert_eq!(webp_frame_count(&mut BufReader::new(Cursor::new(webp))), Some(2));
+
+        let mut apng = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        apng.extend_from_slice(&8u32.to_be_bytes());
+        apng.extend_from_slice(b"acTL");
+        apng.extend_from_slice(&5u32.to_be_bytes());
+        apng.extend_from_slice(&0u32.to_be_bytes());
+        apng.extend_from_slice(&[0, 0, 0, 0]);
+        assert_eq!(png_frame_count(&mut BufReader::new(Cursor::new(apng))), Some(5));
+    }
+
+    #[test]
+    fn test_exif_fields_and_gps_stripping() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("camera.jpg");
+        jpeg_with_exif(&path);
+
+        let info = ImageService::get_image_info(path_str(&path), false).unwrap();
+        assert_eq!(info.format.as_deref(), Some("jpeg"));
+        assert!(info.extension_matches);
+        let exif = info.exif.unwrap();
+        assert_eq!(exif.camera_make.as_deref(), Some("Canon"));
+        assert_eq!(exif.camera_model.as_deref(), Some("EOS R5"));
+        assert_eq!(exif.orientation, Some(6));
+        assert_eq!(exif.date_taken.as_deref(), Some("2024:05:01 10:20:30"));
+        assert_eq!(exif.iso, Some(400));
+        assert_eq!(exif.f_number, Some(2.8));
+        let gps = exif.gps.unwrap();
+        assert_eq!(gps.latitude, 35.5);
+        assert_eq!(gps.longitude, -139.75);
+
+        let stripped = ImageService::get_image_info(path_str(&path), true).unwrap();
+        let exif = stripped.exif.unwrap();
+        assert!(exif.gps.is_none());
+        assert_eq!(exif.camera_make.as_deref(), Some("Canon"));
+    }
+
+    fn edit_request(operations: Vec<ImageEditOperation>, format: OutputFormat) -> ImageEditRequest {
+        ImageEditRequest { operations, format, quality: None }
+    }
+
+    #[test]
+    fn test_rotate_flip_and_crop() {
+        let mut source = RgbImage::from_pixel(4, 2, Rgb([0, 0, 0]));
+        source.put_pixel(0, 0, Rgb([255, 0, 0]));
+        let image = DynamicImage::ImageRgb8(source);
+
+        let rotated = ImageService::apply_operations(
+            image.clone(),
+            &[ImageEditOperation::Rotate { degrees: 90 }],
+            None,
+        ).unwrap();
+        assert_eq!((rotated.width(), rotated.height()), (2, 4));
+        // 左上の画素は時計回り90度で右上に移る
+        assert_eq!(rotated.to_rgb8().get_pixel(1, 0), &Rgb([255, 0, 0]));
+
+        let flipped = ImageService::apply_operations(image.clone(), &[ImageEditOperation::FlipHorizontal], None).unwrap();
+        assert_eq!(flipped.to_rgb8().get_pixel(3, 0), &Rgb([255, 0, 0]));
+
+        let cropped = ImageService::apply_operations(
+            image.clone(),
+            &[ImageEditOperation::Crop { x: 1, y: 0, width: 3, height: 2 }],
+            None,
+        ).unwrap();
+        assert_eq!((cropped.width(), cropped.height()), (3, 2));
+
+        let outside = ImageService::apply_operations(
+            image.clone(),
+            &[ImageEditOperation::Crop { x: 2, y: 0, width: 3, height: 2 }],
+            None,
+        );
+        assert!(outside.unwrap_err().contains("はみ出しています"));
+
+        let bad_angle = ImageService::apply_operations(image, &[ImageEditOperation::Rotate { degrees: 45 }], None);
+        assert!(bad_angle.is_err());
+    }
+
+    #[test]
+    fn test_auto_orient_and_resize() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(40, 20, Rgb([1, 2, 3])));
+
+        // Orientation 6 は時計回り90度回転で正立する
+        let oriented = ImageService::apply_operations(image.clone(), &[ImageEditOperation::AutoOrient], Some(6)).unwrap();
+        assert_eq!((oriented.width(), oriented.height()), (20, 40));
+        let untouched = ImageService::apply_operations(image.clone(), &[ImageEditOperation::AutoOrient], None).unwrap();
+        assert_eq!((untouched.width(), untouched.height()), (40, 20));
+
+        let fit = ImageService::apply_operations(
+            image.clone(),
+            &[ImageEditOperation::Resize { width: 10, height: 10, filter: ResizeFilter::Triangle, keep_aspect: true }],
+            None,
+        ).unwrap();
+        assert_eq!((fit.width(), fit.height()), (10, 5));
+
+        let exact = ImageService::apply_operations(
+            image.clone(),
+            &[ImageEditOperation::Resize { width: 10, height: 10, filter: ResizeFilter::Nearest, keep_aspect: false }],
+            None,
+        ).unwrap();
+        assert_eq!((exact.width(), exact.height()), (10, 10));
+
+        let zero = ImageService::apply_operations(
+            image,
+            &[ImageEditOperation::Resize { width: 0, height: 10, filter: ResizeFilter::Lanczos3, keep_aspect: false }],
+            None,
+        );
+        assert!(zero.is_err());
+    }
+
+    #[test]
+    fn test_operation_deserialization() {
+        let json = r#"[{"type":"rotate","degrees":180},{"type":"flip_vertical"},{"type":"resize","width":5,"height":6}]"#;
+        let operations: Vec<ImageEditOperation> = serde_json::from_str(json).unwrap();
+        assert_eq!(operations[2], ImageEditOperation::Resize {
+            width: 5,
+            height: 6,
+            filter: ResizeFilter::Lanczos3,
+            keep_aspect: false,
+        });
+    }
+
+    #[test]
+    fn test_edit_image_converts_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source.png");
+        RgbaImage::from_pixel(6, 4, Rgba([10, 20, 30, 128])).save(&source).unwrap();
+
+        for (format, expected) in [(OutputFormat::Jpeg, "jpeg"), (OutputFormat::WebP, "webp"), (OutputFormat::Png, "png")] {
+            let destination = temp_dir.path().join(format!("out.{}", format.extension()));
+            let request = ImageEditRequest {
+                operations: vec![ImageEditOperation::Rotate { degrees: 270 }],
+                format,
+                quality: Some(80),
+            };
+            let edited = ImageService::edit_image(&source, &request, &destination, false).unwrap();
+            assert_eq!((edited.width, edited.height), (4, 6));
+            assert!(edited.size > 0);
+
+            let info = ImageService::get_image_info(path_str(&destination), false).unwrap();
+            assert_eq!(info.format.as_deref(), Some(expected));
+            assert_eq!((info.width, info.height), (Some(4), Some(6)));
+        }
+
+        let bad_quality = ImageEditRequest { operations: vec![], format: OutputFormat::Jpeg, quality: Some(0) };
+        assert!(ImageService::edit_image(&source, &bad_quality, &temp_dir.path().join("q.jpg"), false).is_err());
+        assert!(!temp_dir.path().join("q.jpg").exists());
+    }
+
+    #[test]
+    fn test_edit_image_requires_confirmation_to_overwrite_source() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source.png");
+        RgbImage::from_pixel(6, 4, Rgb([0, 0, 0])).save(&source).unwrap();
+        let original = fs::read(&source).unwrap();
+        let request = edit_request(vec![ImageEditOperation::Rotate { degrees: 90 }], OutputFormat::Png);
+
+        let refused = ImageService::edit_image(&source, &request, &source, false);
+        assert_eq!(refused.unwrap_err(), "元の画像を上書きするには確認が必要です");
+        assert_eq!(fs::read(&source).unwrap(), original);
+
+        let edited = ImageService::edit_image(&source, &request, &source, true).unwrap();
+        assert_eq!((edited.width, edited.height), (4, 6));
+    }
+
+    #[test]
+    fn test_nonexistent_file() {
+        let result = ImageService::get_image_info("/nonexistent/image.png", false);
+        assert_eq!(result.unwrap_err(), "ファイルが見つかりません");
+    }
+
+    fn decode_png(data: &str) -> RgbaImage {
+        let bytes = general_purpose::STANDARD.decode(data).unwrap();
+        image::load_from_memory_with_format(&bytes, ImageFormat::Png).unwrap().to_rgba8()
+    }
+
+    #[test]
+    fn test_render_svg_at_requested_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("shape.svg");
+        fs::write(
+            &path,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50"><rect width="100" height="50" fill="red"/><image href="/etc/hosts" width="10" height="10"/></svg>"#,
+        )
+        .unwrap();
+
+        let rendered = ImageService::render_image(path_str(&path), Some(40), Some(40)).unwrap();
+        assert_eq!(rendered.format, "svg");
+        assert_eq!((rendered.width, rendered.height), (40, 20));
+        let image = decode_png(&rendered.data);
+        assert_eq!(image.dimensions(), (40, 20));
+        assert_eq!(*image.get_pixel(20, 10), Rgba([255, 0, 0, 255]));
+
+        let original = ImageService::render_image(path_str(&path), None, None).unwrap();
+        assert_eq!((original.width, original.height), (100, 50));
+        assert!(ImageService::render_image(path_str(&path), Some(0), None).is_err());
+
+        fs::write(&path, "<svg").unwrap();
+        assert!(ImageService::render_image(path_str(&path), None, None).is_err());
+    }
+
+    #[test]
+    fn test_render_bmp_tiff_ico_to_png() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = RgbaImage::from_pixel(8, 6, Rgba([0, 128, 255, 255]));
+        for (name, format) in [("a.bmp", ImageFormat::Bmp), ("a.tiff", ImageFormat::Tiff), ("a.ico", ImageFormat::Ico)] {
+            let path = temp_dir.path().join(name);
+            DynamicImage::ImageRgba8(source.clone()).save_with_format(&path, format).unwrap();
+
+            let rendered = ImageService::render_image(path_str(&path), None, None).unwrap();
+            assert_eq!(rendered.format, format!("{:?}", format).to_lowercase());
+            let image = decode_png(&rendered.data);
+            assert_eq!(image.dimensions(), (8, 6));
+            assert_eq!(*image.get_pixel(3, 3), Rgba([0, 128, 255, 255]));
+        }
+
+        let resized = ImageService::render_image(path_str(&temp_dir.path().join("a.bmp")), None, Some(3)).unwrap();
+        assert_eq!((resized.width, resized.height), (4, 3));
+    }
+
+    #[test]
+    fn test_extract_gif_frames_with_delays() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("anim.gif");
+        {
+            let file = File::create(&path).unwrap();
+            let mut encoder = GifEncoder::new(file);
+            encoder.set_repeat(image::codecs::gif::Repeat::Infinite).unwrap();
+            for (shade, delay) in [(0u8, 100u32), (128, 200), (255, 300)] {
+                let buffer = RgbaImage::from_pixel(4, 4, Rgba([shade, 0, 0, 255]));
+                encoder
+                    .encode_frame(Frame::from_parts(buffer, 0, 0, image::Delay::from_numer_denom_ms(delay, 1)))
+                    .unwrap();
+            }
+        }
+
+        let animation = ImageService::extract_frames(path_str(&path), 0, None).unwrap();
+        assert_eq!(animation.format, "gif");
+        assert_eq!((animation.width, animation.height), (4, 4));
+        assert_eq!(animation.loop_count, None);
+        let delays: Vec<u32> = animation.frames.iter().map(|frame| frame.delay_ms).collect();
+        assert_eq!(delays, vec![100, 200, 300]);
+        assert_eq!(animation.total_duration_ms, 600);
+        assert!(!animation.has_more);
+        assert_eq!(decode_png(&animation.frames[1].data).get_pixel(0, 0)[0], 128);
+
+        let first = ImageService::extract_frames(path_str(&path), 0, Some(2)).unwrap();
+        assert_eq!(first.frames.len(), 2);
+        assert!(first.has_more);
+        let rest = ImageService::extract_frames(path_str(&path), 2, Some(2)).unwrap();
+        assert_eq!((rest.offset, rest.frames.len(), rest.has_more), (2, 1, false));
+        assert_eq!(rest.frames[0].index, 2);
+        assert_eq!(rest.total_duration_ms, 300);
+        assert_eq!(decode_png(&rest.frames[0].data).get_pixel(0, 0)[0], 255);
+
+        let still = temp_dir.path().join("still.png");
+        RgbImage::from_pixel(2, 2, Rgb([1, 2, 3])).save(&still).unwrap();
+        assert!(ImageService::extract_frames(path_str(&still), 0, None).is_err());
+    }
+
+    #[test]
+    fn test_fit_size() {
+        assert_eq!(fit_size(200.0, 100.0, None, None).unwrap(), (200, 100));
+        assert_eq!(fit_size(200.0, 100.0, Some(50), None).unwrap(), (50, 25));
+        assert_eq!(fit_size(200.0, 100.0, Some(50), Some(10)).unwrap(), (20, 10));
+        assert_eq!(fit_size(40000.0, 100.0, None, None).unwrap(), (MAX_DIMENSION, 41));
+        assert!(fit_size(10.0, 10.0, Some(MAX_DIMENSION + 1), None).is_err());
+    }
+}
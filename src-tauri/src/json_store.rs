@@ -0,0 +1,134 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::temp_path::temp_path_for;
+
+/// アプリデータに置く JSON 設定ファイルの読み書きを担当する
+///
+/// 壊れたファイルは `*.corrupt` に退避して既定値で起動し、書き込みは一時ファイルを
+/// 置き換える形で行うため、途中で終了しても読みかけのファイルが残らない。
+/// 読み込み時の問題は起動を止めずに `log` の警告として記録する。
+pub struct JsonStore {
+    path: Option<PathBuf>,
+    /// エラーメッセージに使う設定の名前（例: "ブックマーク"）
+    label: &'static str,
+}
+
+impl JsonStore {
+    /// `path` が `None` の場合は保存しない（テスト用）
+    pub fn new(path: Option<PathBuf>, label: &'static str) -> Self {
+        Self { path, label }
+    }
+
+    /// 保存済みの内容を読み込む。ファイルがない・壊れている場合は既定値
+    pub fn load_or_default<T: DeserializeOwned + Default>(&self) -> T {
+        let Some(path) = &self.path else {
+            return T::default();
+        };
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return T::default(),
+            Err(e) => {
+                log::warn!("{}の読み込みに失敗しました: {}", self.label, e);
+                return T::default();
+            }
+        };
+
+        match serde_json::from_str(&content) {
+            Ok(value) => value,
+            Err(e) => {
+                log::warn!("{}の解析に失敗しました。既定値で起動します: {}", self.label, e);
+                let corrupt = corrupt_path(path);
+                if let Err(e) = fs::rename(path, &corrupt) {
+                    log::warn!("{}の退避に失敗しました: {}", self.label, e);
+                }
+                T::default()
+            }
+        }
+    }
+
+    /// 内容を一時ファイルに書いてから置き換える
+    pub fn save<T: Serialize>(&self, value: &T) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("ディレクトリの作成に失敗しました: {}", e))?;
+        }
+        let content = serde_json::to_vec_pretty(value)
+            .map_err(|e| format!("{}のシリアライズに失敗しました: {}", self.label, e))?;
+
+        let temporary = temp_path_for(path);
+        let result = write_synced(&temporary, &content).and_then(|_| fs::rename(&temporary, path));
+        if result.is_err() {
+            let _ = fs::remove_file(&temporary);
+        }
+        result.map_err(|e| format!("{}の保存に失敗しました: {}", self.label, e))
+    }
+}
+
+/// ファイルに書き込み、ディスクへの反映を待つ
+fn write_synced(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+/// 壊れたファイルの退避先（"places.json" → "places.json.corrupt"）
+fn corrupt_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".corrupt");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use tempfile::TempDir;
+
+    #[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+    struct Settings {
+        names: Vec<String>,
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JsonStore::new(Some(temp_dir.path().join("nested/settings.json")), "設定");
+        let settings = Settings { names: vec!["a".to_string()] };
+
+        store.save(&settings).unwrap();
+        assert_eq!(store.load_or_default::<Settings>(), settings);
+
+        // 一時ファイルが残っていないこと
+        let files: Vec<_> = fs::read_dir(temp_dir.path().join("nested")).unwrap().collect();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn test_missing_file_gives_default() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JsonStore::new(Some(temp_dir.path().join("settings.json")), "設定");
+        assert_eq!(store.load_or_default::<Settings>(), Settings::default());
+        assert_eq!(JsonStore::new(None, "設定").load_or_default::<Settings>(), Settings::default());
+    }
+
+    #[test]
+    fn test_corrupt_file_is_moved_aside() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("settings.json");
+        fs::write(&path, b"{\"names\": [\"a\"").unwrap();
+
+        let store = JsonStore::new(Some(path.clone()), "設定");
+        assert_eq!(store.load_or_default::<Settings>(), Settings::default());
+        assert!(!path.exists());
+        assert_eq!(fs::read(temp_dir.path().join("settings.json.corrupt")).unwrap(), b"{\"names\": [\"a\"");
+
+        // 退避後は通常どおり保存できる
+        store.save(&Settings { names: vec!["b".to_string()] }).unwrap();
+        assert_eq!(store.load_or_default::<Settings>().names, vec!["b"]);
+    }
+}
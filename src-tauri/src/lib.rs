@@ -3,25 +3,138 @@ mod file_service;
 mod system_service;
 mod demo_service;
 mod database_service;
+mod report_service;
+mod environment_service;
+mod socket_service;
+mod scope_service;
+mod temp_path;
+mod json_store;
+mod image_protocol;
+mod thumbnail_service;
+mod image_service;
+mod search_service;
+mod walker;
+mod collation;
+mod watch_service;
+mod progress;
+mod file_operation_service;
+mod disk_usage_service;
+mod preview_service;
+mod archive_service;
+mod dialog_service;
+mod places_service;
+mod application_service;
+mod data_service;
 
 use file_service::FileService;
 use system_service::SystemService;
 use demo_service::DemoService;
 use database_service::DatabaseService;
+use report_service::ReportService;
+use scope_service::{ScopeService, ScopeState};
+use image_protocol::ImageProtocol;
+use thumbnail_service::ThumbnailService;
+use image_service::ImageService;
+use search_service::SearchRegistry;
+use watch_service::WatchService;
+use file_operation_service::FileOperationService;
+use disk_usage_service::DiskUsageService;
+use preview_service::PreviewService;
+use archive_service::ArchiveService;
+use dialog_service::DialogService;
+use places_service::PlacesService;
+use application_service::ApplicationService;
+use data_service::DataService;
+use tauri::{Emitter, Manager};
 
 // 型定義を各サービスモジュールから再エクスポート
-pub use file_service::{FileInfo, DirectoryEntry};
-pub use system_service::{SystemInfo, DiskInfo, RealTimeMetrics};
+pub use file_service::{FileInfo, DirectoryEntry, ListOptions, SortKey, SortDirection, DirectoryPage, ContentSearchOptions, ContentMatch, MatchRange};
+pub use file_service::{HashAlgorithm, FileHash, HashProgress, DuplicateOptions, DuplicateGroup, DuplicateReport};
+pub use file_service::{RenameRule, InsertPosition, LetterCase, DateSource, BatchRenameOptions, RenameConflict, RenamePreviewItem, BatchRenamePreview, RenamedPath};
+pub use system_service::{SystemInfo, DiskInfo, RealTimeMetrics, NetworkInfo, ProcessInfo};
 pub use database_service::{Memo, CreateMemoRequest, UpdateMemoRequest};
 pub use demo_service::DemoInfo;
+pub use report_service::{ReportFormat, SystemReport, AppInfo, AppSettings};
+pub use environment_service::EnvironmentInfo;
+pub use socket_service::{SocketInfo, SocketFilter, SocketProtocol};
+pub use scope_service::AllowedRoots;
+pub use thumbnail_service::{ThumbnailReady, CacheEvictionResult};
+pub use image_service::{ImageInfo, ExifInfo, GpsInfo, ImageEditOperation, ImageEditRequest, EditedImage, OutputFormat, ResizeFilter};
+pub use image_service::{RenderedImage, AnimationFrame, AnimationFrames};
+pub use search_service::{SearchOptions, PatternKind, SearchMatch, SearchMatchBatch, SearchFinished};
+pub use walker::SearchSummary;
+pub use watch_service::{ChangeKind, FileChange, DirectoryChanged};
+pub use file_operation_service::{ConflictPolicy, OperationKind, OperationProgress, OperationOutcome, OperationSummary};
+pub use disk_usage_service::{DiskUsageOptions, DiskUsageNode, DiskUsageReport, DiskUsageFinished};
+pub use preview_service::{PreviewMode, PreviewOptions, TextPreview, HexLine, LineEnding};
+pub use archive_service::{ArchiveFormat, ArchiveEntry, ArchiveEntryPreview, ArchiveProgress, ExtractOutcome, CreatedArchive};
+pub use dialog_service::{DialogFilter, OpenDialogOptions, SaveDialogOptions};
+pub use places_service::{Bookmark, RecentKind, RecentItem, Place, Places};
+pub use application_service::DesktopApplication;
+pub use data_service::{DataFormat, DataOptions, ColumnType, DataColumn, DataPage, DataCursor, ColumnStats, DataStats};
 
 // ========== Tauri コマンド層 ==========
 // この層は薄いラッパーとして機能し、サービス層に処理を委譲する
 
 /// 画像ファイル選択コマンド - UIからファイル選択ダイアログを開く
+///
+/// 選ばれたファイルはアクセス範囲に個別に追加される。
 #[tauri::command]
-async fn select_image_file(app: tauri::AppHandle) -> Result<Option<String>, String> {
-    FileService::select_image_file(&app).await
+async fn select_image_file(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    dialogs: tauri::State<'_, DialogService>,
+) -> Result<Option<String>, String> {
+    let options = OpenDialogOptions {
+        purpose: Some("image".to_string()),
+        filters: vec![DialogFilter {
+            name: "Images".to_string(),
+            extensions: ["png", "jpg", "jpeg", "gif", "bmp", "webp", "svg", "tif", "tiff", "ico"].map(String::from).to_vec(),
+        }],
+        ..Default::default()
+    };
+    let Some(selected) = dialogs.open(&app, &options).await?.and_then(|paths| paths.into_iter().next()) else {
+        return Ok(None);
+    };
+    scope.lock()?.allow_file(&selected)?;
+    Ok(Some(selected.to_string_lossy().to_string()))
+}
+
+/// ファイル選択ダイアログコマンド - フィルター・初期位置・複数選択・フォルダ選択を指定して開く
+///
+/// 選ばれたファイルは個別に、フォルダは許可ディレクトリとしてアクセス範囲に追加される。
+#[tauri::command]
+async fn open_dialog(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    dialogs: tauri::State<'_, DialogService>,
+    options: OpenDialogOptions,
+) -> Result<Option<Vec<String>>, String> {
+    let Some(selected) = dialogs.open(&app, &options).await? else {
+        return Ok(None);
+    };
+    let mut scope = scope.lock()?;
+    let allowed = selected
+        .iter()
+        .map(|path| if options.directory { scope.allow_directory(path) } else { scope.allow_file(path) })
+        .map(|allowed| allowed.map(|path| path.to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some(allowed))
+}
+
+/// 保存ダイアログコマンド - 保存先を選ばせ、そのパスへの書き込みを許可する
+#[tauri::command]
+async fn save_dialog(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    dialogs: tauri::State<'_, DialogService>,
+    options: SaveDialogOptions,
+) -> Result<Option<String>, String> {
+    let Some(selected) = dialogs.save(&app, &options).await? else {
+        return Ok(None);
+    };
+    let allowed = scope.lock()?.allow_new_file(&selected)?;
+    Ok(Some(allowed.to_string_lossy().to_string()))
 }
 
 /// 挨拶メッセージ生成コマンド - デモ用の基本機能
@@ -44,8 +157,118 @@ fn get_demo_info(demo_id: &str) -> Option<DemoInfo> {
 
 /// 画像ファイル読み込みコマンド - ファイルをBase64エンコードして返す
 #[tauri::command]
-fn read_image_file(file_path: &str) -> Result<String, String> {
-    FileService::read_image_file(file_path)
+fn read_image_file(scope: tauri::State<'_, ScopeState>, file_path: &str) -> Result<String, String> {
+    FileService::read_image_file(&scope.resolve_str(file_path)?)
+}
+
+/// ファイルプレビューコマンド - 先頭を文字コード判定してテキストで、バイナリなら16進ダンプで返す
+#[tauri::command]
+async fn preview_file(
+    scope: tauri::State<'_, ScopeState>,
+    file_path: String,
+    options: Option<PreviewOptions>,
+) -> Result<TextPreview, String> {
+    let file_path = scope.resolve_str(&file_path)?;
+    // 行数を数えるためにファイル全体を読むので、別スレッドで実行する
+    tauri::async_runtime::spawn_blocking(move || PreviewService::preview_file(&file_path, &options.unwrap_or_default()))
+        .await
+        .map_err(|e| format!("プレビューの作成に失敗しました: {}", e))?
+}
+
+/// データページ取得コマンド - CSV・JSON・JSON Lines を表として1ページ分読み込み、列の型を推定する
+///
+/// `cursor` に前のページの `next_cursor` を渡すと、先頭から読み直さずに続きを読む。
+#[tauri::command]
+async fn read_data_page(
+    scope: tauri::State<'_, ScopeState>,
+    file_path: String,
+    options: Option<DataOptions>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    cursor: Option<DataCursor>,
+) -> Result<DataPage, String> {
+    let file_path = scope.lock()?.resolve(&file_path)?;
+    tauri::async_runtime::spawn_blocking(move || match cursor {
+        Some(cursor) => DataService::read_next_page(&file_path, &cursor, limit),
+        None => DataService::read_page(&file_path, &options.unwrap_or_default(), offset.unwrap_or(0), limit),
+    })
+    .await
+    .map_err(|e| format!("データの読み込みに失敗しました: {}", e))?
+}
+
+/// データ統計取得コマンド - ファイル全体を読み、列ごとの件数・異なる値の数・最小値・最大値を求める
+#[tauri::command]
+async fn get_data_stats(
+    scope: tauri::State<'_, ScopeState>,
+    file_path: String,
+    options: Option<DataOptions>,
+) -> Result<DataStats, String> {
+    let file_path = scope.lock()?.resolve(&file_path)?;
+    tauri::async_runtime::spawn_blocking(move || DataService::column_stats(&file_path, &options.unwrap_or_default()))
+        .await
+        .map_err(|e| format!("データの読み込みに失敗しました: {}", e))?
+}
+
+/// 画像情報取得コマンド - 形式・寸法・色・フレーム数・EXIFを取得（strip_gps で位置情報を除外）
+#[tauri::command]
+async fn get_image_info(
+    scope: tauri::State<'_, ScopeState>,
+    file_path: String,
+    strip_gps: Option<bool>,
+) -> Result<ImageInfo, String> {
+    let file_path = scope.resolve_str(&file_path)?;
+    // EXIF とフレーム数の読み取りでファイル全体を読むことがあるので、別スレッドで実行する
+    tauri::async_runtime::spawn_blocking(move || ImageService::get_image_info(&file_path, strip_gps.unwrap_or(false)))
+        .await
+        .map_err(|e| format!("画像情報の取得に失敗しました: {}", e))?
+}
+
+/// 画像変換コマンド - SVG を指定サイズでラスタライズし、BMP・TIFF・ICO なども PNG（Base64）に変換する
+#[tauri::command]
+async fn render_image(
+    scope: tauri::State<'_, ScopeState>,
+    file_path: String,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> Result<RenderedImage, String> {
+    let file_path = scope.resolve_str(&file_path)?;
+    tauri::async_runtime::spawn_blocking(move || ImageService::render_image(&file_path, width, height))
+        .await
+        .map_err(|e| format!("画像の変換に失敗しました: {}", e))?
+}
+
+/// アニメーションフレーム取得コマンド - GIF・WebP・APNG の offset 番目から limit 枚のフレームを表示時間とともに PNG で返す
+#[tauri::command]
+async fn get_animation_frames(
+    scope: tauri::State<'_, ScopeState>,
+    file_path: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<AnimationFrames, String> {
+    let file_path = scope.resolve_str(&file_path)?;
+    tauri::async_runtime::spawn_blocking(move || ImageService::extract_frames(&file_path, offset.unwrap_or(0), limit))
+        .await
+        .map_err(|e| format!("フレームの取り出しに失敗しました: {}", e))?
+}
+
+/// 画像編集保存コマンド - 回転・反転・切り抜き・リサイズ・形式変換を適用し、保存ダイアログで選んだ場所に書き出す
+///
+/// 元画像への上書きは overwrite_source が true のときだけ行う。保存先はアクセス範囲に追加される。
+#[tauri::command]
+async fn save_edited_image(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    dialogs: tauri::State<'_, DialogService>,
+    file_path: String,
+    request: ImageEditRequest,
+    overwrite_source: Option<bool>,
+) -> Result<Option<EditedImage>, String> {
+    let source = scope.resolve_str(&file_path)?;
+    let edited = ImageService::save_edited_image(&app, &dialogs, &source, &request, overwrite_source.unwrap_or(false)).await?;
+    if let Some(edited) = &edited {
+        scope.lock()?.allow_file(std::path::Path::new(&edited.path))?;
+    }
+    Ok(edited)
 }
 
 /// システム情報取得コマンド - OS、CPU、メモリ、ディスク情報を取得
@@ -60,16 +283,55 @@ fn get_realtime_metrics() -> Result<RealTimeMetrics, String> {
     SystemService::get_realtime_metrics()
 }
 
+/// ソケット一覧取得コマンド - 待ち受けポートや確立済み接続を条件で絞り込んで取得
+#[tauri::command]
+fn list_sockets(filter: Option<SocketFilter>) -> Result<Vec<SocketInfo>, String> {
+    SystemService::list_sockets(&filter.unwrap_or_default())
+}
+
+/// システムレポート出力コマンド - 保存ダイアログで選んだ場所にJSON/Markdown/HTMLで書き出す
+#[tauri::command]
+async fn export_system_report(
+    app: tauri::AppHandle,
+    dialogs: tauri::State<'_, DialogService>,
+    format: ReportFormat,
+    redact: bool,
+) -> Result<Option<String>, String> {
+    ReportService::export_system_report(&app, &dialogs, format, redact).await
+}
+
 /// ファイル情報取得コマンド - 指定されたファイルの詳細情報を取得
 #[tauri::command]
-fn get_file_info(file_path: &str) -> Result<FileInfo, String> {
-    FileService::get_file_info(file_path)  
+fn get_file_info(scope: tauri::State<'_, ScopeState>, file_path: &str) -> Result<FileInfo, String> {
+    FileService::get_file_info(&scope.resolve_str(file_path)?)
+}
+
+/// ディレクトリ一覧取得コマンド - 指定されたディレクトリ内のファイル・フォルダ一覧を取得（隠しファイル・シンボリックリンクの扱いを指定可能）
+#[tauri::command]
+fn list_directory(
+    scope: tauri::State<'_, ScopeState>,
+    dir_path: &str,
+    options: Option<ListOptions>,
+) -> Result<Vec<DirectoryEntry>, String> {
+    FileService::list_directory(&scope.resolve_str(dir_path)?, &options.unwrap_or_default())
 }
 
-/// ディレクトリ一覧取得コマンド - 指定されたディレクトリ内のファイル・フォルダ一覧を取得
+/// ディレクトリページ取得コマンド - 並べ替えたディレクトリ内容を offset から limit 件ずつ取得
 #[tauri::command]
-fn list_directory(dir_path: &str) -> Result<Vec<DirectoryEntry>, String> {
-    FileService::list_directory(dir_path)
+async fn list_directory_page(
+    scope: tauri::State<'_, ScopeState>,
+    dir_path: String,
+    options: Option<ListOptions>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<DirectoryPage, String> {
+    let dir_path = scope.resolve_str(&dir_path)?;
+    // 大きなディレクトリでは全件の読み込みと並べ替えに時間がかかるので、別スレッドで実行する
+    tauri::async_runtime::spawn_blocking(move || {
+        FileService::list_directory_page(&dir_path, &options.unwrap_or_default(), offset.unwrap_or(0), limit)
+    })
+    .await
+    .map_err(|e| format!("ディレクトリの読み込みに失敗しました: {}", e))?
 }
 
 /// ホームディレクトリ取得コマンド - ユーザーのホームディレクトリパスを取得
@@ -80,8 +342,479 @@ fn get_home_directory() -> Result<String, String> {
 
 /// ディレクトリ読み込みコマンド - React用のエイリアス
 #[tauri::command]
-fn read_directory(path: &str) -> Result<Vec<DirectoryEntry>, String> {
-    FileService::read_directory(path)
+fn read_directory(
+    scope: tauri::State<'_, ScopeState>,
+    path: &str,
+    options: Option<ListOptions>,
+) -> Result<Vec<DirectoryEntry>, String> {
+    FileService::read_directory(&scope.resolve_str(path)?, &options.unwrap_or_default())
+}
+
+/// サムネイル生成要求コマンド - バックグラウンドで並列生成し、完了ごとに thumbnail-ready イベントを送る
+#[tauri::command]
+fn request_thumbnails(app: tauri::AppHandle, scope: tauri::State<'_, ScopeState>, paths: Vec<String>) -> Result<usize, String> {
+    let resolved = paths
+        .iter()
+        .map(|path| scope.resolve_str(path).map(std::path::PathBuf::from))
+        .collect::<Result<Vec<_>, String>>()?;
+    let count = resolved.len();
+    ThumbnailService::spawn_generation(app, resolved);
+    Ok(count)
+}
+
+/// サムネイルキャッシュ削除コマンド - キャッシュ済みのサムネイルをすべて削除
+#[tauri::command]
+fn clear_thumbnail_cache(thumbnails: tauri::State<'_, ThumbnailService>) -> Result<CacheEvictionResult, String> {
+    thumbnails.clear()
+}
+
+/// ファイル検索開始コマンド - ルート以下を再帰的に検索し、結果を search-match イベントで順次送る
+///
+/// 戻り値の検索IDで cancel_search から中断できる。
+#[tauri::command]
+fn start_search(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    searches: tauri::State<'_, SearchRegistry>,
+    root: &str,
+    options: SearchOptions,
+) -> Result<u64, String> {
+    let root = scope.resolve_str(root)?;
+    searches.start(app, std::path::PathBuf::from(root), options)
+}
+
+/// 内容検索開始コマンド - ルート以下のファイル内容を文字列・正規表現で検索し、結果を content-match イベントで順次送る
+#[tauri::command]
+fn start_content_search(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    searches: tauri::State<'_, SearchRegistry>,
+    root: &str,
+    options: ContentSearchOptions,
+) -> Result<u64, String> {
+    let root = scope.resolve_str(root)?;
+    searches.start_content_search(app, std::path::PathBuf::from(root), options)
+}
+
+/// 検索中断コマンド - 実行中のファイル名検索・内容検索を止める
+#[tauri::command]
+fn cancel_search(searches: tauri::State<'_, SearchRegistry>, search_id: u64) -> Result<bool, String> {
+    searches.cancel(search_id)
+}
+
+/// ディレクトリ監視開始コマンド - 変更を directory-changed イベントで通知する（戻り値は購読数）
+#[tauri::command]
+fn watch_directory(
+    window: tauri::Window,
+    scope: tauri::State<'_, ScopeState>,
+    watches: tauri::State<'_, WatchService>,
+    dir_path: &str,
+) -> Result<usize, String> {
+    let dir = scope.lock()?.resolve(dir_path)?;
+    watches.subscribe(window.label(), &dir)
+}
+
+/// ディレクトリ監視解除コマンド - watch_directory で開始した購読を1つ解除する
+#[tauri::command]
+fn unwatch_directory(
+    window: tauri::Window,
+    scope: tauri::State<'_, ScopeState>,
+    watches: tauri::State<'_, WatchService>,
+    dir_path: &str,
+) -> Result<bool, String> {
+    // 監視中に削除されたディレクトリも解除できるよう、解決できなければ渡されたパスのまま探す
+    let dir = scope
+        .lock()?
+        .resolve(dir_path)
+        .unwrap_or_else(|_| std::path::PathBuf::from(dir_path));
+    watches.unsubscribe(window.label(), &dir)
+}
+
+/// 監視中ディレクトリ一覧取得コマンド - 現在ウォッチャーが動いているディレクトリを取得
+#[tauri::command]
+fn get_watched_directories(watches: tauri::State<'_, WatchService>) -> Result<Vec<String>, String> {
+    watches.watched_directories()
+}
+
+/// ハッシュ計算コマンド - MD5/SHA-1/SHA-256/BLAKE3 を計算し、大きいファイルの進捗を hash-progress イベントで送る
+#[tauri::command]
+async fn hash_file(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    file_path: String,
+    algorithm: Option<HashAlgorithm>,
+) -> Result<FileHash, String> {
+    let file_path = scope.resolve_str(&file_path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        FileService::hash_file(&file_path, algorithm.unwrap_or_default(), |progress| {
+            let _ = app.emit(file_service::HASH_PROGRESS_EVENT, progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("ハッシュの計算に失敗しました: {}", e))?
+}
+
+/// 重複ファイル検出コマンド - サイズ・先頭部分・全体のハッシュの順に絞り込み、空けられる容量の大きい順に返す
+#[tauri::command]
+async fn find_duplicates(
+    scope: tauri::State<'_, ScopeState>,
+    root: String,
+    options: Option<DuplicateOptions>,
+) -> Result<DuplicateReport, String> {
+    let root = scope.lock()?.resolve(&root)?;
+    tauri::async_runtime::spawn_blocking(move || FileService::find_duplicates(&root, &options.unwrap_or_default()))
+        .await
+        .map_err(|e| format!("重複ファイルの検出に失敗しました: {}", e))?
+}
+
+/// ディスク使用量集計開始コマンド - 配下のサイズを並列に集計し、結果を disk-usage-finished イベントで送る
+///
+/// 戻り値の集計IDで cancel_disk_usage から中断できる。
+#[tauri::command]
+fn start_disk_usage(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    disk_usage: tauri::State<'_, DiskUsageService>,
+    dir_path: &str,
+    options: Option<DiskUsageOptions>,
+) -> Result<u64, String> {
+    let root = scope.resolve_str(dir_path)?;
+    disk_usage.start(app, std::path::PathBuf::from(root), options.unwrap_or_default())
+}
+
+/// ディスク使用量集計中断コマンド
+#[tauri::command]
+fn cancel_disk_usage(disk_usage: tauri::State<'_, DiskUsageService>, analysis_id: u64) -> Result<bool, String> {
+    disk_usage.cancel(analysis_id)
+}
+
+/// ディスク使用量キャッシュ削除コマンド - パス指定時はその配下だけを破棄する
+#[tauri::command]
+fn clear_disk_usage_cache(
+    scope: tauri::State<'_, ScopeState>,
+    disk_usage: tauri::State<'_, DiskUsageService>,
+    dir_path: Option<String>,
+) -> Result<(), String> {
+    let path = dir_path.map(|path| scope.resolve_str(&path)).transpose()?;
+    disk_usage.clear_cache(path.as_deref().map(std::path::Path::new))
+}
+
+// ========== ファイル管理コマンド ==========
+
+/// 操作対象のパスをまとめて検証する（シンボリックリンクはリンク自体を対象にする）
+fn resolve_entries(scope: &ScopeState, paths: &[String]) -> Result<Vec<std::path::PathBuf>, String> {
+    let scope = scope.lock()?;
+    paths.iter().map(|path| scope.resolve_entry(path)).collect()
+}
+
+/// ディレクトリ作成コマンド - 親ディレクトリの中に新しいフォルダを作る
+#[tauri::command]
+fn create_directory(
+    scope: tauri::State<'_, ScopeState>,
+    operations: tauri::State<'_, FileOperationService>,
+    parent_path: &str,
+    name: &str,
+) -> Result<String, String> {
+    let parent = scope.lock()?.resolve(parent_path)?;
+    operations.create_directory(&parent, name)
+}
+
+/// 名前変更コマンド - 同じディレクトリ内でファイル・フォルダの名前を変える
+#[tauri::command]
+fn rename_path(
+    scope: tauri::State<'_, ScopeState>,
+    operations: tauri::State<'_, FileOperationService>,
+    path: &str,
+    new_name: &str,
+) -> Result<String, String> {
+    let entry = scope.lock()?.resolve_entry(path)?;
+    operations.rename(&entry, new_name)
+}
+
+/// 一括名前変更プレビューコマンド - 変更後の名前と、重複・既存ファイルとの衝突を返す（変更はしない）
+#[tauri::command]
+async fn preview_batch_rename(
+    scope: tauri::State<'_, ScopeState>,
+    paths: Vec<String>,
+    options: BatchRenameOptions,
+) -> Result<BatchRenamePreview, String> {
+    let paths = resolve_entries(&scope, &paths)?;
+    tauri::async_runtime::spawn_blocking(move || FileService::preview_batch_rename(&paths, &options))
+        .await
+        .map_err(|e| format!("名前の変更に失敗しました: {}", e))?
+}
+
+/// 一括名前変更コマンド - 衝突が無ければまとめて変更し、途中で失敗した場合はすべて元に戻す
+#[tauri::command]
+async fn batch_rename(
+    scope: tauri::State<'_, ScopeState>,
+    paths: Vec<String>,
+    options: BatchRenameOptions,
+) -> Result<Vec<RenamedPath>, String> {
+    let paths = resolve_entries(&scope, &paths)?;
+    tauri::async_runtime::spawn_blocking(move || FileService::batch_rename(&paths, &options))
+        .await
+        .map_err(|e| format!("名前の変更に失敗しました: {}", e))?
+}
+
+/// コピーコマンド - フォルダごと再帰的にコピーし、進捗を file-operation-progress イベントで送る
+#[tauri::command]
+async fn copy_paths(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    sources: Vec<String>,
+    dest_dir: String,
+    policy: Option<ConflictPolicy>,
+) -> Result<OperationOutcome, String> {
+    let sources = resolve_entries(&scope, &sources)?;
+    let dest_dir = scope.lock()?.resolve(&dest_dir)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<FileOperationService>().copy(&sources, &dest_dir, policy.unwrap_or_default(), |progress| {
+            let _ = app.emit(file_operation_service::FILE_OPERATION_PROGRESS_EVENT, progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("ファイル操作の実行に失敗しました: {}", e))?
+}
+
+/// 移動コマンド - 別デバイスへの移動はコピー後に削除し、進捗を file-operation-progress イベントで送る
+#[tauri::command]
+async fn move_paths(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    sources: Vec<String>,
+    dest_dir: String,
+    policy: Option<ConflictPolicy>,
+) -> Result<OperationOutcome, String> {
+    let sources = resolve_entries(&scope, &sources)?;
+    let dest_dir = scope.lock()?.resolve(&dest_dir)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<FileOperationService>().move_items(&sources, &dest_dir, policy.unwrap_or_default(), |progress| {
+            let _ = app.emit(file_operation_service::FILE_OPERATION_PROGRESS_EVENT, progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("ファイル操作の実行に失敗しました: {}", e))?
+}
+
+/// ゴミ箱へ移動コマンド - freedesktop 仕様のゴミ箱に移す（完全には削除しない）
+#[tauri::command]
+async fn trash_paths(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    paths: Vec<String>,
+) -> Result<OperationOutcome, String> {
+    let paths = resolve_entries(&scope, &paths)?;
+    tauri::async_runtime::spawn_blocking(move || app.state::<FileOperationService>().trash(&paths))
+        .await
+        .map_err(|e| format!("ファイル操作の実行に失敗しました: {}", e))?
+}
+
+/// 直前の操作取得コマンド - 取り消しできる操作の概要を取得
+#[tauri::command]
+fn get_last_operation(operations: tauri::State<'_, FileOperationService>) -> Result<Option<OperationSummary>, String> {
+    operations.last_operation()
+}
+
+/// 取り消しコマンド - 直前のファイル操作を元に戻す
+#[tauri::command]
+async fn undo_last_operation(app: tauri::AppHandle) -> Result<Option<OperationSummary>, String> {
+    tauri::async_runtime::spawn_blocking(move || app.state::<FileOperationService>().undo_last())
+        .await
+        .map_err(|e| format!("ファイル操作の実行に失敗しました: {}", e))?
+}
+
+/// 既定のアプリケーションで開くコマンド - ファイル・フォルダを OS の既定のアプリケーションで開く
+#[tauri::command]
+fn open_with_default_application(scope: tauri::State<'_, ScopeState>, path: &str) -> Result<(), String> {
+    ApplicationService::open_default(&scope.lock()?.resolve(path)?)
+}
+
+/// アプリケーション一覧取得コマンド - ファイルを開けるアプリケーションをデスクトップエントリから取得
+#[tauri::command]
+async fn get_applications_for(
+    scope: tauri::State<'_, ScopeState>,
+    path: String,
+) -> Result<Vec<DesktopApplication>, String> {
+    let path = scope.lock()?.resolve(&path)?;
+    tauri::async_runtime::spawn_blocking(move || ApplicationService::from_environment().applications_for(&path))
+        .await
+        .map_err(|e| format!("アプリケーション一覧の取得に失敗しました: {}", e))
+}
+
+/// アプリケーション指定で開くコマンド - デスクトップファイルIDで指定したアプリケーションで開く
+#[tauri::command]
+async fn open_with_application(
+    scope: tauri::State<'_, ScopeState>,
+    path: String,
+    application_id: String,
+) -> Result<(), String> {
+    let path = scope.lock()?.resolve(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        ApplicationService::from_environment().open_with(&path, &application_id)
+    })
+    .await
+    .map_err(|e| format!("アプリケーションの起動に失敗しました: {}", e))?
+}
+
+/// ファイルマネージャー表示コマンド - 親フォルダを開いて項目を選択状態にする
+#[tauri::command]
+fn reveal_in_file_manager(scope: tauri::State<'_, ScopeState>, path: &str) -> Result<(), String> {
+    ApplicationService::reveal(&scope.lock()?.resolve(path)?)
+}
+
+// ========== 書庫コマンド ==========
+
+/// 書庫一覧コマンド - zip/tar/tar.gz 内のエントリ一覧を取得
+#[tauri::command]
+async fn list_archive(scope: tauri::State<'_, ScopeState>, archive_path: String) -> Result<Vec<ArchiveEntry>, String> {
+    let archive = scope.lock()?.resolve(&archive_path)?;
+    tauri::async_runtime::spawn_blocking(move || ArchiveService::list_entries(&archive))
+        .await
+        .map_err(|e| format!("書庫の読み込みに失敗しました: {}", e))?
+}
+
+/// 書庫エントリプレビューコマンド - 展開せずに1つのエントリの先頭を Base64 で返す
+#[tauri::command]
+async fn preview_archive_entry(
+    scope: tauri::State<'_, ScopeState>,
+    archive_path: String,
+    entry_path: String,
+    max_bytes: Option<usize>,
+) -> Result<ArchiveEntryPreview, String> {
+    let archive = scope.lock()?.resolve(&archive_path)?;
+    tauri::async_runtime::spawn_blocking(move || ArchiveService::preview_entry(&archive, &entry_path, max_bytes))
+        .await
+        .map_err(|e| format!("書庫の読み込みに失敗しました: {}", e))?
+}
+
+/// 書庫展開コマンド - 全体または選択したエントリを展開し、進捗を archive-progress イベントで送る
+#[tauri::command]
+async fn extract_archive(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    archive_path: String,
+    dest_dir: String,
+    entries: Option<Vec<String>>,
+    overwrite: Option<bool>,
+) -> Result<ExtractOutcome, String> {
+    let (archive, dest_dir) = {
+        let scope = scope.lock()?;
+        (scope.resolve(&archive_path)?, scope.resolve(&dest_dir)?)
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        ArchiveService::extract(&archive, &dest_dir, entries.as_deref(), overwrite.unwrap_or(false), |progress| {
+            let _ = app.emit(archive_service::ARCHIVE_PROGRESS_EVENT, progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("書庫の展開に失敗しました: {}", e))?
+}
+
+/// zip 作成コマンド - 選択したファイル・フォルダを zip にまとめ、進捗を archive-progress イベントで送る
+#[tauri::command]
+async fn create_zip(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    sources: Vec<String>,
+    destination: String,
+) -> Result<CreatedArchive, String> {
+    let sources = resolve_entries(&scope, &sources)?;
+    let destination = scope.lock()?.resolve_new_entry(&destination)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        ArchiveService::create_zip(&sources, &destination, |progress| {
+            let _ = app.emit(archive_service::ARCHIVE_PROGRESS_EVENT, progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("書庫の作成に失敗しました: {}", e))?
+}
+
+// ========== 場所（ブックマーク・最近使った場所）コマンド ==========
+
+/// 場所一覧取得コマンド - 標準のユーザーディレクトリ・ブックマーク・最近使った場所を取得
+#[tauri::command]
+fn get_places(app: tauri::AppHandle, places: tauri::State<'_, PlacesService>) -> Result<Places, String> {
+    places.places(&app)
+}
+
+/// ブックマーク追加コマンド - ディレクトリをブックマークの末尾に追加
+#[tauri::command]
+fn add_bookmark(
+    scope: tauri::State<'_, ScopeState>,
+    places: tauri::State<'_, PlacesService>,
+    path: &str,
+    name: Option<String>,
+) -> Result<Bookmark, String> {
+    places.add_bookmark(&scope.lock()?.resolve(path)?, name.as_deref())
+}
+
+/// ブックマーク削除コマンド - 指定したブックマークを削除
+#[tauri::command]
+fn remove_bookmark(places: tauri::State<'_, PlacesService>, id: u64) -> Result<bool, String> {
+    places.remove_bookmark(id)
+}
+
+/// ブックマーク名変更コマンド - ブックマークの表示名を変更
+#[tauri::command]
+fn rename_bookmark(places: tauri::State<'_, PlacesService>, id: u64, name: &str) -> Result<Bookmark, String> {
+    places.rename_bookmark(id, name)
+}
+
+/// ブックマーク並べ替えコマンド - 指定したIDの順にブックマークを並べ替える
+#[tauri::command]
+fn reorder_bookmarks(places: tauri::State<'_, PlacesService>, ids: Vec<u64>) -> Result<Vec<Bookmark>, String> {
+    places.reorder_bookmarks(&ids)
+}
+
+/// 最近使った場所の記録コマンド - 開いたディレクトリ・ファイルを履歴の先頭に記録
+#[tauri::command]
+fn record_recent(
+    scope: tauri::State<'_, ScopeState>,
+    places: tauri::State<'_, PlacesService>,
+    path: &str,
+    kind: RecentKind,
+) -> Result<(), String> {
+    places.record_recent(&scope.lock()?.resolve(path)?, kind)
+}
+
+/// 最近使った場所の消去コマンド - 種類を省略した場合はすべて消去
+#[tauri::command]
+fn clear_recent(places: tauri::State<'_, PlacesService>, kind: Option<RecentKind>) -> Result<(), String> {
+    places.clear_recent(kind)
+}
+
+// ========== アクセス範囲（スコープ）コマンド ==========
+
+/// 許可範囲一覧取得コマンド - ファイル操作が許可されているディレクトリを取得
+#[tauri::command]
+fn get_allowed_roots(scope: tauri::State<'_, ScopeState>) -> Result<AllowedRoots, String> {
+    Ok(scope.lock()?.allowed_roots())
+}
+
+/// 許可ディレクトリ追加コマンド - ダイアログで選んだディレクトリを許可範囲に追加して保存
+#[tauri::command]
+async fn add_allowed_directory(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeState>,
+    dialogs: tauri::State<'_, DialogService>,
+) -> Result<Option<String>, String> {
+    let options = OpenDialogOptions {
+        purpose: Some("allowed-directory".to_string()),
+        directory: true,
+        ..Default::default()
+    };
+    let Some(selected) = dialogs.open(&app, &options).await?.and_then(|paths| paths.into_iter().next()) else {
+        return Ok(None);
+    };
+    let allowed = scope.lock()?.allow_directory(&selected)?;
+    Ok(Some(allowed.to_string_lossy().to_string()))
+}
+
+/// 許可ディレクトリ削除コマンド - ユーザーが追加したディレクトリの許可を取り消す
+#[tauri::command]
+fn remove_allowed_directory(scope: tauri::State<'_, ScopeState>, dir_path: &str) -> Result<bool, String> {
+    scope.lock()?.remove_directory(dir_path)
 }
 
 // ========== データベース操作コマンド ==========
@@ -142,6 +875,39 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            // ファイル操作のアクセス範囲を初期化
+            let mut scope = ScopeService::load(app.handle())?;
+
+            // サムネイルはキャッシュディレクトリから localimg:// で配信する
+            let thumbnails = ThumbnailService::new(app.handle())?;
+            scope.add_base_root(thumbnails.cache_dir())?;
+
+            app.manage(ScopeState::new(scope));
+            app.manage(thumbnails);
+            app.manage(SearchRegistry::default());
+            app.manage(FileOperationService::new()?);
+            app.manage(DiskUsageService::default());
+            app.manage(DialogService::load(app.handle())?);
+            app.manage(PlacesService::load(app.handle())?);
+
+            let handle = app.handle().clone();
+            app.manage(WatchService::new(move |windows, changed| {
+                // 購読しているウィンドウにだけ送る
+                for window in windows {
+                    let _ = handle.emit_to(window.as_str(), watch_service::DIRECTORY_CHANGED_EVENT, &changed);
+                }
+            }));
+            Ok(())
+        })
+        // 閉じられたウィンドウのディレクトリ監視を解除する
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                let _ = window.state::<WatchService>().unsubscribe_window(window.label());
+            }
+        })
+        // 画像は Base64 IPC ではなく localimg:// で直接配信する
+        .register_asynchronous_uri_scheme_protocol(image_protocol::SCHEME, ImageProtocol::handle_async)
         .invoke_handler(tauri::generate_handler![
             // デモ機能
             greet,
@@ -149,14 +915,70 @@ pub fn run() {
             get_demo_info,
             // ファイル操作
             select_image_file,
+            open_dialog,
+            save_dialog,
             read_image_file,
+            preview_file,
+            read_data_page,
+            get_data_stats,
+            get_image_info,
+            render_image,
+            get_animation_frames,
+            save_edited_image,
             get_file_info,
             list_directory,
+            list_directory_page,
             get_home_directory,
             read_directory,
+            request_thumbnails,
+            clear_thumbnail_cache,
+            start_search,
+            start_content_search,
+            cancel_search,
+            watch_directory,
+            unwatch_directory,
+            get_watched_directories,
+            hash_file,
+            find_duplicates,
+            start_disk_usage,
+            cancel_disk_usage,
+            clear_disk_usage_cache,
+            // ファイル管理
+            create_directory,
+            rename_path,
+            preview_batch_rename,
+            batch_rename,
+            copy_paths,
+            move_paths,
+            trash_paths,
+            get_last_operation,
+            undo_last_operation,
+            open_with_default_application,
+            get_applications_for,
+            open_with_application,
+            reveal_in_file_manager,
+            // 書庫
+            list_archive,
+            preview_archive_entry,
+            extract_archive,
+            create_zip,
+            // 場所
+            get_places,
+            add_bookmark,
+            remove_bookmark,
+            rename_bookmark,
+            reorder_bookmarks,
+            record_recent,
+            clear_recent,
+            // アクセス範囲
+            get_allowed_roots,
+            add_allowed_directory,
+            remove_allowed_directory,
             // システム情報
             get_system_info,
             get_realtime_metrics,
+            list_sockets,
+            export_system_report,
             // データベース操作
             create_memo,
             get_all_memos,
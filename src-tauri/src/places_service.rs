@@ -0,0 +1,374 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use crate::json_store::JsonStore;
+
+/// ブックマークと最近使った場所を保存するファイル名
+const PLACES_FILE_NAME: &str = "places.json";
+
+/// 保存ファイルの表示名（エラーメッセージ用）
+const PLACES_STORE_LABEL: &str = "ブックマーク";
+
+/// 最近使った場所を種類ごとに残す件数
+const MAX_RECENT_ITEMS: usize = 30;
+
+/// ブックマーク
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Bookmark {
+    pub id: u64,
+    pub name: String,
+    pub path: String,
+    /// 作成日時（UNIX秒）
+    pub created_at: u64,
+}
+
+/// 最近使った場所の種類
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecentKind {
+    Directory,
+    File,
+}
+
+/// 最近使った場所
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RecentItem {
+    pub path: String,
+    pub kind: RecentKind,
+    /// 最後に使った日時（UNIX秒）
+    pub last_used: u64,
+}
+
+/// OS 標準のユーザーディレクトリ
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Place {
+    /// "home", "desktop", "documents", "downloads", "pictures", "music", "videos"
+    pub kind: String,
+    pub name: String,
+    pub path: String,
+}
+
+/// サイドバーに表示する場所の一覧
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Places {
+    pub standard: Vec<Place>,
+    pub bookmarks: Vec<Bookmark>,
+    pub recent_directories: Vec<RecentItem>,
+    pub recent_files: Vec<RecentItem>,
+}
+
+/// 保存ファイルの形式
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+struct PersistedPlaces {
+    bookmarks: Vec<Bookmark>,
+    /// 新しい順
+    recent: Vec<RecentItem>,
+}
+
+/// ブックマーク・最近使った場所・標準のユーザーディレクトリを扱うサービスクラス
+pub struct PlacesService {
+    state: Mutex<PersistedPlaces>,
+    store: JsonStore,
+}
+
+impl PlacesService {
+    /// ブックマークと最近使った場所を `store_path` に保存するインスタンスを作成
+    pub fn new(store_path: Option<PathBuf>) -> Self {
+        let store = JsonStore::new(store_path, PLACES_STORE_LABEL);
+        Self {
+            state: Mutex::new(store.load_or_default()),
+            store,
+        }
+    }
+
+    /// アプリデータディレクトリに保存するインスタンスを作成
+    pub fn load(app_handle: &tauri::AppHandle) -> Result<Self, String> {
+        let app_dir = app_handle
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("アプリデータディレクトリの取得に失敗しました: {}", e))?;
+        Ok(Self::new(Some(app_dir.join(PLACES_FILE_NAME))))
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, PersistedPlaces>, String> {
+        self.state
+            .lock()
+            .map_err(|e| format!("ブックマーク状態のロックに失敗しました: {}", e))
+    }
+
+    /// 状態を保存ファイルに書き出す
+    fn save(&self, state: &PersistedPlaces) -> Result<(), String> {
+        self.store.save(state)
+    }
+
+    /// 標準のユーザーディレクトリ・ブックマーク・最近使った場所をまとめて取得
+    pub fn places(&self, app_handle: &tauri::AppHandle) -> Result<Places, String> {
+        Ok(Places {
+            standard: standard_places(app_handle),
+            bookmarks: self.bookmarks()?,
+            recent_directories: self.recent(RecentKind::Directory)?,
+            recent_files: self.recent(RecentKind::File)?,
+        })
+    }
+
+    /// ブックマーク一覧を取得（表示順）
+    pub fn bookmarks(&self) -> Result<Vec<Bookmark>, String> {
+        Ok(self.lock()?.bookmarks.clone())
+    }
+
+    /// ブックマークを末尾に追加する。名前を省略した場合はフォルダ名を使う
+    pub fn add_bookmark(&self, path: &Path, name: Option<&str>) -> Result<Bookmark, String> {
+        if !path.is_dir() {
+            return Err("指定されたパスはディレクトリではありません".to_string());
+        }
+        let path_text = path.to_string_lossy().to_string();
+
+        let mut state = self.lock()?;
+        if state.bookmarks.iter().any(|bookmark| bookmark.path == path_text) {
+            return Err("既にブックマークされています".to_string());
+        }
+        let name = match name.map(str::trim) {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path_text.clone()),
+        };
+        let bookmark = Bookmark {
+            id: state.bookmarks.iter().map(|bookmark| bookmark.id).max().unwrap_or(0) + 1,
+            name,
+            path: path_text,
+            created_at: now(),
+        };
+        state.bookmarks.push(bookmark.clone());
+        self.save(&state)?;
+        Ok(bookmark)
+    }
+
+    /// ブックマークを削除する。見つからなければ false
+    pub fn remove_bookmark(&self, id: u64) -> Result<bool, String> {
+        let mut state = self.lock()?;
+        let before = state.bookmarks.len();
+        state.bookmarks.retain(|bookmark| bookmark.id != id);
+        if state.bookmarks.len() == before {
+            return Ok(false);
+        }
+        self.save(&state)?;
+        Ok(true)
+    }
+
+    /// ブックマークの表示名を変更する
+    pub fn rename_bookmark(&self, id: u64, name: &str) -> Result<Bookmark, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("名前を入力してください".to_string());
+        }
+        let mut state = self.lock()?;
+        let bookmark = state
+            .bookmarks
+            .iter_mut()
+            .find(|bookmark| bookmark.id == id)
+            .ok_or_else(|| "ブックマークが見つかりません".to_string())?;
+        bookmark.name = name.to_string();
+        let renamed = bookmark.clone();
+        self.save(&state)?;
+        Ok(renamed)
+    }
+
+    /// ブックマークを `ids` の順に並べ替える（すべてのIDを1回ずつ指定する）
+    pub fn reorder_bookmarks(&self, ids: &[u64]) -> Result<Vec<Bookmark>, String> {
+        let mut state = self.lock()?;
+        let mut sorted_ids = ids.to_vec();
+        sorted_ids.sort_unstable();
+        let mut current: Vec<u64> = state.bookmarks.iter().map(|bookmark| bookmark.id).collect();
+        current.sort_unstable();
+        if sorted_ids != current {
+            return Err("並べ替えにはすべてのブックマークを1回ずつ指定してください".to_string());
+        }
+
+        state
+            .bookmarks
+            .sort_by_key(|bookmark| ids.iter().position(|id| *id == bookmark.id));
+        self.save(&state)?;
+        Ok(state.bookmarks.clone())
+    }
+
+    /// 開いたディレクトリ・ファイルを最近使った場所の先頭に記録する
+    pub fn record_recent(&self, path: &Path, kind: RecentKind) -> Result<(), String> {
+        let path = path.to_string_lossy().to_string();
+        let mut state = self.lock()?;
+        state.recent.retain(|item| !(item.kind == kind && item.path == path));
+        state.recent.insert(0, RecentItem { path, kind, last_used: now() });
+
+        // 種類ごとに上限を超えた古いものを落とす
+        let mut kept = 0;
+        state.recent.retain(|item| {
+            if item.kind != kind {
+                return true;
+            }
+            kept += 1;
+            kept <= MAX_RECENT_ITEMS
+        });
+        self.save(&state)
+    }
+
+    /// 最近使った場所（新しい順、存在しなくなったものは除く）
+    pub fn recent(&self, kind: RecentKind) -> Result<Vec<RecentItem>, String> {
+        Ok(self
+            .lock()?
+            .recent
+            .iter()
+            .filter(|item| item.kind == kind && Path::new(&item.path).exists())
+            .cloned()
+            .collect())
+    }
+
+    /// 最近使った場所を消去する。種類を省略した場合はすべて消す
+    pub fn clear_recent(&self, kind: Option<RecentKind>) -> Result<(), String> {
+        let mut state = self.lock()?;
+        state.recent.retain(|item| kind.is_some_and(|kind| item.kind != kind));
+        self.save(&state)
+    }
+}
+
+/// Tauri のパスAPIで XDG ユーザーディレクトリなどを解決する（存在するものだけ）
+fn standard_places(app_handle: &tauri::AppHandle) -> Vec<Place> {
+    let path = app_handle.path();
+    let candidates = [
+        ("home", "ホーム", path.home_dir()),
+        ("desktop", "デスクトップ", path.desktop_dir()),
+        ("documents", "ドキュメント", path.document_dir()),
+        ("downloads", "ダウンロード", path.download_dir()),
+        ("pictures", "ピクチャ", path.picture_dir()),
+        ("music", "ミュージック", path.audio_dir()),
+        ("videos", "ビデオ", path.video_dir()),
+    ];
+
+    candidates
+        .into_iter()
+        .filter_map(|(kind, name, dir)| {
+            let dir = dir.ok().filter(|dir| dir.is_dir())?;
+            Some(Place {
+                kind: kind.to_string(),
+                name: name.to_string(),
+                path: dir.to_string_lossy().to_string(),
+            })
+        })
+        .collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup() -> (PlacesService, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["photos", "work", "music"] {
+            fs::create_dir(temp_dir.path().join(name)).unwrap();
+        }
+        let service = PlacesService::new(Some(temp_dir.path().join("store/places.json")));
+        (service, temp_dir)
+    }
+
+    #[test]
+    fn test_bookmark_add_rename_remove() {
+        let (service, temp_dir) = setup();
+        let photos = temp_dir.path().join("photos");
+
+        let bookmark = service.add_bookmark(&photos, None).unwrap();
+        assert_eq!(bookmark.name, "photos");
+        assert_eq!(service.add_bookmark(&photos, Some("別名")).unwrap_err(), "既にブックマークされています");
+        assert!(service.add_bookmark(&temp_dir.path().join("missing"), None).is_err());
+
+        let renamed = service.rename_bookmark(bookmark.id, "  写真  ").unwrap();
+        assert_eq!(renamed.name, "写真");
+        assert!(service.rename_bookmark(bookmark.id, " ").is_err());
+
+        assert!(service.remove_bookmark(bookmark.id).unwrap());
+        assert!(!service.remove_bookmark(bookmark.id).unwrap());
+        assert!(service.bookmarks().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reorder_bookmarks() {
+        let (service, temp_dir) = setup();
+        let ids: Vec<u64> = ["photos", "work", "music"]
+            .iter()
+            .map(|name| service.add_bookmark(&temp_dir.path().join(name), None).unwrap().id)
+            .collect();
+
+        let reordered = service.reorder_bookmarks(&[ids[2], ids[0], ids[1]]).unwrap();
+        assert_eq!(reordered.iter().map(|b| b.name.as_str()).collect::<Vec<_>>(), vec!["music", "photos", "work"]);
+        assert!(service.reorder_bookmarks(&[ids[0], ids[1]]).is_err());
+        assert!(service.reorder_bookmarks(&[ids[0], ids[0], ids[1]]).is_err());
+    }
+
+    #[test]
+    fn test_recent_items_are_most_recent_first_and_capped() {
+        let (service, temp_dir) = setup();
+        let file = temp_dir.path().join("notes.txt");
+        fs::write(&file, "memo").unwrap();
+
+        service.record_recent(&temp_dir.path().join("photos"), RecentKind::Directory).unwrap();
+        service.record_recent(&temp_dir.path().join("work"), RecentKind::Directory).unwrap();
+        service.record_recent(&temp_dir.path().join("photos"), RecentKind::Directory).unwrap();
+        service.record_recent(&file, RecentKind::File).unwrap();
+
+        let directories = service.recent(RecentKind::Directory).unwrap();
+        assert_eq!(directories.len(), 2);
+        assert!(directories[0].path.ends_with("photos"));
+        assert_eq!(service.recent(RecentKind::File).unwrap().len(), 1);
+
+        for i in 0..MAX_RECENT_ITEMS + 5 {
+            let dir = temp_dir.path().join(format!("dir{}", i));
+            fs::create_dir(&dir).unwrap();
+            service.record_recent(&dir, RecentKind::Directory).unwrap();
+        }
+        assert_eq!(service.recent(RecentKind::Directory).unwrap().len(), MAX_RECENT_ITEMS);
+        assert_eq!(service.recent(RecentKind::File).unwrap().len(), 1);
+
+        // 削除された場所は表示しない
+        fs::remove_file(&file).unwrap();
+        assert!(service.recent(RecentKind::File).unwrap().is_empty());
+
+        service.clear_recent(Some(RecentKind::Directory)).unwrap();
+        assert!(service.recent(RecentKind::Directory).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_persists_between_instances() {
+        let (service, temp_dir) = setup();
+        service.add_bookmark(&temp_dir.path().join("work"), Some("仕事")).unwrap();
+        service.record_recent(&temp_dir.path().join("music"), RecentKind::Directory).unwrap();
+
+        let reloaded = PlacesService::new(Some(temp_dir.path().join("store/places.json")));
+        assert_eq!(reloaded.bookmarks().unwrap()[0].name, "仕事");
+        assert_eq!(reloaded.recent(RecentKind::Directory).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_corrupt_store_starts_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        let store_path = temp_dir.path().join("places.json");
+        fs::write(&store_path, b"{\"bookmarks\": [{").unwrap();
+
+        let service = PlacesService::new(Some(store_path));
+        assert!(service.bookmarks().unwrap().is_empty());
+        assert!(temp_dir.path().join("places.json.corrupt").exists());
+
+        service.add_bookmark(temp_dir.path(), Some("tmp")).unwrap();
+        assert_eq!(PlacesService::new(Some(temp_dir.path().join("places.json"))).bookmarks().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,412 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use encoding_rs::{Encoding, EUC_JP, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8};
+use serde::{Deserialize, Serialize};
+
+/// 既定で読み込む先頭のサイズ
+const DEFAULT_PREVIEW_BYTES: usize = 64 * 1024;
+
+/// 読み込むサイズの上限
+const MAX_PREVIEW_BYTES: usize = 1024 * 1024;
+
+/// バイナリ判定に使う先頭のサイズ
+const BINARY_CHECK_LENGTH: usize = 8192;
+
+/// 16進ダンプの1行あたりのバイト数
+const HEX_BYTES_PER_LINE: usize = 16;
+
+/// 表示方法
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewMode {
+    /// テキストならテキスト、バイナリなら16進ダンプ
+    #[default]
+    Auto,
+    Text,
+    Hex,
+}
+
+/// 改行コードの種類
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Cr,
+    /// 複数の種類が混在している
+    Mixed,
+    /// 改行が無い
+    None,
+}
+
+/// プレビューの取得条件
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct PreviewOptions {
+    /// 読み込むバイト数（省略時は 64KB、最大 1MB）
+    pub max_bytes: Option<usize>,
+    pub mode: PreviewMode,
+    /// 16進ダンプの開始位置（バイト）
+    pub offset: u64,
+    /// 文字コードを自動判定せずに指定する（例: "Shift_JIS"）
+    pub encoding: Option<String>,
+}
+
+/// 16進ダンプの1行
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HexLine {
+    pub offset: u64,
+    /// 例: "48 65 6c 6c 6f"
+    pub hex: String,
+    /// 表示できない文字は "." にする
+    pub ascii: String,
+}
+
+/// ファイルのプレビュー
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TextPreview {
+    pub path: String,
+    pub size: u64,
+    pub is_binary: bool,
+    /// テキストの場合の文字コード名（例: "UTF-8", "Shift_JIS"）
+    pub encoding: Option<String>,
+    pub has_bom: bool,
+    pub line_ending: Option<LineEnding>,
+    /// ファイル全体の行数（テキストの場合のみ）
+    pub line_count: Option<u64>,
+    /// テキスト表示の内容
+    pub content: Option<String>,
+    /// 16進ダンプ表示の内容
+    pub hex: Option<Vec<HexLine>>,
+    /// 読み込んだ範囲がファイルの末尾まで届いていない
+    pub truncated: bool,
+}
+
+/// テキスト・バイナリファイルのプレビューを作るサービスクラス
+pub struct PreviewService;
+
+impl PreviewService {
+    /// ファイルの先頭を読み込み、文字コードを判定してプレビューを返す
+    pub fn preview_file(file_path: &str, options: &PreviewOptions) -> Result<TextPreview, String> {
+        let path = Path::new(file_path);
+        if !path.is_file() {
+            return Err("ファイルが見つかりません".to_string());
+        }
+
+        let size = fs::metadata(path)
+            .map_err(|e| format!("ファイル情報の取得に失敗しました: {}", e))?
+            .len();
+        let max_bytes = options.max_bytes.unwrap_or(DEFAULT_PREVIEW_BYTES).clamp(1, MAX_PREVIEW_BYTES);
+        let forced = match &options.encoding {
+            Some(label) => Some(
+                Encoding::for_label(label.as_bytes()).ok_or_else(|| format!("未対応の文字コードです: {}", label))?,
+            ),
+            None => None,
+        };
+
+        let head = read_range(path, 0, max_bytes)?;
+        let bom = Encoding::for_bom(&head);
+        let is_binary = bom.is_none() && forced.is_none() && looks_binary(&head);
+        let use_hex = match options.mode {
+            PreviewMode::Auto => is_binary,
+            PreviewMode::Text => false,
+            PreviewMode::Hex => true,
+        };
+
+        let mut preview = TextPreview {
+            path: file_path.to_string(),
+            size,
+            is_binary,
+            encoding: None,
+            has_bom: bom.is_some(),
+            line_ending: None,
+            line_count: None,
+            content: None,
+            hex: None,
+            truncated: false,
+        };
+
+        if use_hex {
+            let bytes = if options.offset == 0 { head } else { read_range(path, options.offset, max_bytes)? };
+            preview.truncated = options.offset + (bytes.len() as u64) < size;
+            preview.hex = Some(hex_dump(&bytes, options.offset));
+            return Ok(preview);
+        }
+
+        let truncated = (head.len() as u64) < size;
+        let (encoding, bom_length) = match (forced, bom) {
+            (Some(encoding), Some((bom_encoding, length))) if encoding == bom_encoding => (encoding, length),
+            (Some(encoding), _) => (encoding, 0),
+            (None, Some((encoding, length))) => (encoding, length),
+            (None, None) => (detect_encoding(&head, !truncated), 0),
+        };
+
+        let content = decode(encoding, &head[bom_length..], !truncated);
+        preview.encoding = Some(encoding.name().to_string());
+        preview.line_ending = Some(line_ending(&content));
+        preview.line_count = Some(count_lines(path, encoding)?);
+        preview.content = Some(content);
+        preview.truncated = truncated;
+        Ok(preview)
+    }
+}
+
+/// ファイルの `offset` から最大 `length` バイトを読み込む
+fn read_range(path: &Path, offset: u64, length: usize) -> Result<Vec<u8>, String> {
+    use std::io::{Seek, SeekFrom};
+
+    let mut file = fs::File::open(path).map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+    let mut bytes = Vec::with_capacity(length);
+    file.take(length as u64)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+    Ok(bytes)
+}
+
+/// NUL や制御文字が多ければバイナリとみなす
+fn looks_binary(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(BINARY_CHECK_LENGTH)];
+    if sample.contains(&0) {
+        return true;
+    }
+    let control = sample
+        .iter()
+        .filter(|&&byte| byte < 0x20 && !matches!(byte, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b))
+        .count();
+    control * 10 > sample.len()
+}
+
+/// BOM の無いテキストの文字コードを判定する
+///
+/// UTF-8 として正しければ UTF-8、そうでなければ日本語を優先して推定する。
+pub fn detect_encoding(bytes: &[u8], is_complete: bool) -> &'static Encoding {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return UTF_8,
+        // 途中で切れた最後の文字だけが不完全な場合も UTF-8 とする
+        Err(e) if e.error_len().is_none() && !is_complete => return UTF_8,
+        Err(_) => {}
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, is_complete);
+    let guessed = detector.guess(Some(b"jp"), true);
+    if guessed == SHIFT_JIS || guessed == EUC_JP {
+        return guessed;
+    }
+    // 日本語以外と推定されても、どちらかで正しく読めるならそれを使う
+    [SHIFT_JIS, EUC_JP]
+        .into_iter()
+        .find(|encoding| decodes_cleanly(encoding, bytes, is_complete))
+        .unwrap_or(guessed)
+}
+
+fn decodes_cleanly(encoding: &'static Encoding, bytes: &[u8], is_complete: bool) -> bool {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut output = String::with_capacity(decoder.max_utf8_buffer_length(bytes.len()).unwrap_or(bytes.len() * 3));
+    let (result, _) = decoder.decode_to_string_without_replacement(bytes, &mut output, is_complete);
+    !matches!(result, encoding_rs::DecoderResult::Malformed(_, _))
+}
+
+/// 文字列に変換する。途中で切れている場合、最後の不完全な文字は捨てる
+fn decode(encoding: &'static Encoding, bytes: &[u8], is_complete: bool) -> String {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut output = String::with_capacity(decoder.max_utf8_buffer_length(bytes.len()).unwrap_or(bytes.len() * 3));
+    let _ = decoder.decode_to_string(bytes, &mut output, is_complete);
+    output
+}
+
+/// 改行コードの種類を判定する
+fn line_ending(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    let cr = text.matches('\r').count() - crlf;
+    match (crlf > 0, lf > 0, cr > 0) {
+        (false, false, false) => LineEnding::None,
+        (true, false, false) => LineEnding::Crlf,
+        (false, true, false) => LineEnding::Lf,
+        (false, false, true) => LineEnding::Cr,
+        _ => LineEnding::Mixed,
+    }
+}
+
+/// ファイル全体の行数を数える（CRLF・LF・CR のいずれも1つの改行として数える）
+fn count_lines(path: &Path, encoding: &'static Encoding) -> Result<u64, String> {
+    let unit_size = if encoding == UTF_16LE || encoding == UTF_16BE { 2 } else { 1 };
+    let mut file = fs::File::open(path).map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut pending: Vec<u8> = Vec::new();
+    let mut breaks = 0u64;
+    let mut previous_cr = false;
+    let mut last_unit = None;
+
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        pending.extend_from_slice(&buffer[..read]);
+        let usable = pending.len() - pending.len() % unit_size;
+        for chunk in pending[..usable].chunks_exact(unit_size) {
+            let unit = match (unit_size, encoding == UTF_16BE) {
+                (1, _) => chunk[0] as u16,
+                (_, true) => u16::from_be_bytes([chunk[0], chunk[1]]),
+                (_, false) => u16::from_le_bytes([chunk[0], chunk[1]]),
+            };
+            // LF と、LF が続かない CR をそれぞれ改行1つとして数える
+            if unit == u16::from(b'\n') || previous_cr {
+                breaks += 1;
+            }
+            previous_cr = unit == u16::from(b'\r');
+            last_unit = Some(unit);
+        }
+        pending.drain(..usable);
+    }
+
+    if previous_cr {
+        breaks += 1;
+    }
+    let unterminated = last_unit.is_some_and(|unit| unit != u16::from(b'\n') && unit != u16::from(b'\r'));
+    // 先頭の BOM だけのファイルは0行とする
+    let bom_only = last_unit == Some(0xfeff) && breaks == 0;
+    Ok(breaks + u64::from(unterminated && !bom_only))
+}
+
+/// 16進ダンプを作る
+fn hex_dump(bytes: &[u8], start_offset: u64) -> Vec<HexLine> {
+    bytes
+        .chunks(HEX_BYTES_PER_LINE)
+        .enumerate()
+        .map(|(index, chunk)| HexLine {
+            offset: start_offset + (index * HEX_BYTES_PER_LINE) as u64,
+            hex: chunk.iter().map(|byte| format!("{:02x}", byte)).collect::<Vec<_>>().join(" "),
+            ascii: chunk
+                .iter()
+                .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn preview(dir: &TempDir, name: &str, bytes: &[u8], options: &PreviewOptions) -> TextPreview {
+        let path = dir.path().join(name);
+        fs::write(&path, bytes).unwrap();
+        PreviewService::preview_file(path.to_str().unwrap(), options).unwrap()
+    }
+
+    #[test]
+    fn test_preview_utf8_text() {
+        let temp_dir = TempDir::new().unwrap();
+        let result = preview(&temp_dir, "a.txt", "こんにちは\r\n世界\r\n".as_bytes(), &PreviewOptions::default());
+
+        assert!(!result.is_binary);
+        assert_eq!(result.encoding.as_deref(), Some("UTF-8"));
+        assert_eq!(result.content.as_deref(), Some("こんにちは\r\n世界\r\n"));
+        assert_eq!(result.line_ending, Some(LineEnding::Crlf));
+        assert_eq!(result.line_count, Some(2));
+        assert!(!result.truncated && !result.has_bom);
+    }
+
+    #[test]
+    fn test_preview_japanese_legacy_encodings() {
+        let temp_dir = TempDir::new().unwrap();
+        let text = "日本語のテキストファイルです。\nひらがなとカタカナと漢字を含みます。\n";
+
+        let (sjis, _, _) = SHIFT_JIS.encode(text);
+        let result = preview(&temp_dir, "sjis.txt", &sjis, &PreviewOptions::default());
+        assert_eq!(result.encoding.as_deref(), Some("Shift_JIS"));
+        assert_eq!(result.content.as_deref(), Some(text));
+
+        let (euc, _, _) = EUC_JP.encode(text);
+        let result = preview(&temp_dir, "euc.txt", &euc, &PreviewOptions::default());
+        assert_eq!(result.encoding.as_deref(), Some("EUC-JP"));
+        assert_eq!(result.content.as_deref(), Some(text));
+        assert_eq!(result.line_count, Some(2));
+    }
+
+    #[test]
+    fn test_preview_utf16_bom() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut bytes = vec![0xff, 0xfe];
+        for unit in "first\nsecond".encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        let result = preview(&temp_dir, "utf16.txt", &bytes, &PreviewOptions::default());
+
+        assert!(!result.is_binary && result.has_bom);
+        assert_eq!(result.encoding.as_deref(), Some("UTF-16LE"));
+        assert_eq!(result.content.as_deref(), Some("first\nsecond"));
+        assert_eq!(result.line_ending, Some(LineEnding::Lf));
+        assert_eq!(result.line_count, Some(2));
+    }
+
+    #[test]
+    fn test_preview_truncates_without_broken_character() {
+        let temp_dir = TempDir::new().unwrap();
+        // 「あ」は UTF-8 で3バイトなので、4バイト目で切ると2文字目が不完全になる
+        let options = PreviewOptions { max_bytes: Some(4), ..Default::default() };
+        let result = preview(&temp_dir, "a.txt", "あい\nう".as_bytes(), &options);
+
+        assert!(result.truncated);
+        assert_eq!(result.encoding.as_deref(), Some("UTF-8"));
+        assert_eq!(result.content.as_deref(), Some("あ"));
+        assert_eq!(result.line_count, Some(2));
+    }
+
+    #[test]
+    fn test_preview_binary_as_hex() {
+        let temp_dir = TempDir::new().unwrap();
+        let bytes: Vec<u8> = (0u8..40).collect();
+        let result = preview(&temp_dir, "data.bin", &bytes, &PreviewOptions::default());
+
+        assert!(result.is_binary);
+        assert!(result.content.is_none() && result.line_count.is_none());
+        let hex = result.hex.unwrap();
+        assert_eq!(hex.len(), 3);
+        assert_eq!(hex[1].offset, 16);
+        assert!(hex[0].hex.starts_with("00 01 02"));
+        assert_eq!(hex[2].ascii, " !\"#$%&'");
+
+        let options = PreviewOptions { mode: PreviewMode::Hex, offset: 32, max_bytes: Some(4), ..Default::default() };
+        let result = preview(&temp_dir, "data.bin", &bytes, &options);
+        let hex = result.hex.unwrap();
+        assert_eq!(hex, vec![HexLine { offset: 32, hex: "20 21 22 23".to_string(), ascii: " !\"#".to_string() }]);
+        assert!(result.truncated);
+    }
+
+    #[test]
+    fn test_line_endings_and_counts() {
+        assert_eq!(line_ending("a\nb"), LineEnding::Lf);
+        assert_eq!(line_ending("a\rb\r"), LineEnding::Cr);
+        assert_eq!(line_ending("a\r\nb\n"), LineEnding::Mixed);
+        assert_eq!(line_ending("abc"), LineEnding::None);
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("mixed.txt");
+        fs::write(&path, b"a\r\nb\nc\rd").unwrap();
+        assert_eq!(count_lines(&path, UTF_8).unwrap(), 4);
+        fs::write(&path, b"").unwrap();
+        assert_eq!(count_lines(&path, UTF_8).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_preview_forced_encoding() {
+        let temp_dir = TempDir::new().unwrap();
+        let (sjis, _, _) = SHIFT_JIS.encode("表示");
+        let options = PreviewOptions { encoding: Some("shift_jis".to_string()), ..Default::default() };
+        let result = preview(&temp_dir, "a.txt", &sjis, &options);
+        assert_eq!(result.content.as_deref(), Some("表示"));
+
+        let path = temp_dir.path().join("a.txt");
+        let options = PreviewOptions { encoding: Some("unknown".to_string()), ..Default::default() };
+        let error = PreviewService::preview_file(path.to_str().unwrap(), &options).unwrap_err();
+        assert_eq!(error, "未対応の文字コードです: unknown");
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// 進捗を通知する最短間隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// ProgressTracker で集計する進捗イベントの内容
+pub trait Progress {
+    fn set_current_path(&mut self, path: String);
+    /// 処理済みのファイル数とバイト数を足す
+    fn add_processed(&mut self, files: usize, bytes: u64);
+}
+
+/// 進捗を集計し、一定間隔ごとに通知する（コピー・移動と書庫の展開・作成で共通）
+pub struct ProgressTracker<'a, P> {
+    progress: P,
+    last_sent: Option<Instant>,
+    callback: &'a mut dyn FnMut(&P),
+}
+
+impl<'a, P: Progress> ProgressTracker<'a, P> {
+    pub fn new(progress: P, callback: &'a mut dyn FnMut(&P)) -> Self {
+        Self { progress, last_sent: None, callback }
+    }
+
+    pub fn set_current(&mut self, path: impl AsRef<Path>) {
+        self.progress.set_current_path(path.as_ref().to_string_lossy().to_string());
+    }
+
+    pub fn add_bytes(&mut self, bytes: u64) {
+        self.add_files(0, bytes);
+    }
+
+    pub fn add_files(&mut self, files: usize, bytes: u64) {
+        self.progress.add_processed(files, bytes);
+        self.report(false);
+    }
+
+    /// 前回の通知から `PROGRESS_INTERVAL` 経っていれば通知する。`force` なら必ず通知する
+    pub fn report(&mut self, force: bool) {
+        if force || self.last_sent.is_none_or(|sent| sent.elapsed() >= PROGRESS_INTERVAL) {
+            (self.callback)(&self.progress);
+            self.last_sent = Some(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Counts {
+        current_path: String,
+        files: usize,
+        bytes: u64,
+    }
+
+    impl Progress for Counts {
+        fn set_current_path(&mut self, path: String) {
+            self.current_path = path;
+        }
+
+        fn add_processed(&mut self, files: usize, bytes: u64) {
+            self.files += files;
+            self.bytes += bytes;
+        }
+    }
+
+    #[test]
+    fn test_reports_are_throttled() {
+        let mut sent = Vec::new();
+        let mut callback = |progress: &Counts| sent.push(progress.clone());
+        let mut tracker = ProgressTracker::new(Counts::default(), &mut callback);
+
+        tracker.set_current("dir/a.txt");
+        tracker.add_bytes(10);
+        tracker.add_files(1, 5);
+        tracker.add_files(1, 0);
+        tracker.report(true);
+
+        // 最初の通知と強制した通知だけが送られる
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0], Counts { current_path: "dir/a.txt".to_string(), files: 0, bytes: 10 });
+        assert_eq!(sent[1], Counts { current_path: "dir/a.txt".to_string(), files: 2, bytes: 15 });
+    }
+}
@@ -0,0 +1,643 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use crate::dialog_service::{DialogFilter, DialogService, SaveDialogOptions};
+use crate::places_service::PlacesService;
+use crate::scope_service::ScopeState;
+use crate::system_service::{
+    MetricsProvider, NetworkInfo, ProcessInfo, SysinfoMetricsProvider, SystemInfo, SystemService,
+};
+use crate::thumbnail_service::ThumbnailService;
+
+/// レポートに含める上位プロセス数
+const TOP_PROCESS_COUNT: usize = 10;
+
+/// 伏せ字にした値の表示
+const REDACTED: &str = "[REDACTED]";
+
+/// 保存ダイアログで最後に使ったディレクトリを覚えておく用途名
+const DIALOG_PURPOSE: &str = "report";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Json,
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// 保存ダイアログで使う拡張子
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+        }
+    }
+
+    /// 保存ダイアログのフィルタ名
+    fn filter_name(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "JSON",
+            ReportFormat::Markdown => "Markdown",
+            ReportFormat::Html => "HTML",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AppInfo {
+    pub name: String,
+    pub version: String,
+    pub identifier: String,
+    pub tauri_version: String,
+    pub data_dir: Option<String>,
+}
+
+/// アプリの設定（ユーザーが変更した状態）
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AppSettings {
+    /// ユーザーがアクセスを許可したディレクトリ
+    pub allowed_directories: Vec<String>,
+    pub bookmark_count: usize,
+    pub thumbnail_cache_dir: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SystemReport {
+    pub generated_at: String,
+    pub redacted: bool,
+    pub app: AppInfo,
+    pub settings: AppSettings,
+    pub system: SystemInfo,
+    pub networks: Vec<NetworkInfo>,
+    pub top_processes: Vec<ProcessInfo>,
+}
+
+/// サポート問い合わせ用のシステムレポート作成を担当するサービスクラス
+pub struct ReportService;
+
+impl ReportService {
+
+    /// レポートを作成し、保存ダイアログで選ばれた場所に書き出す
+    ///
+    /// キャンセルされた場合は `None` を返す。
+    pub async fn export_system_report(
+        app: &tauri::AppHandle,
+        dialogs: &DialogService,
+        format: ReportFormat,
+        redact: bool,
+    ) -> Result<Option<String>, String> {
+        let app_info = Self::app_info(app);
+        let settings = Self::app_settings(app);
+        // CPU 使用率の計測待ちとプロセスの列挙があるので、別スレッドで実行する
+        let report = tauri::async_runtime::spawn_blocking(move || {
+            Self::build_report(&mut SysinfoMetricsProvider::new(), app_info, settings, redact)
+        })
+        .await
+        .map_err(|e| format!("レポートの作成に失敗しました: {}", e))??;
+
+        let file_name = format!(
+            "system-report-{}.{}",
+            Utc::now().format("%Y%m%d-%H%M%S"),
+            format.extension()
+        );
+        let options = SaveDialogOptions {
+            purpose: Some(DIALOG_PURPOSE.to_string()),
+            filters: vec![DialogFilter {
+                name: format.filter_name().to_string(),
+                extensions: vec![format.extension().to_string()],
+            }],
+            file_name: Some(file_name),
+            ..Default::default()
+        };
+        let Some(path) = dialogs.save(app, &options).await? else {
+            return Ok(None);
+        };
+
+        Self::write_report(&report, format, &path)?;
+        Ok(Some(path.to_string_lossy().to_string()))
+    }
+
+    /// 実行中のアプリケーション情報を取得
+    fn app_info(app: &tauri::AppHandle) -> AppInfo {
+        let package = app.package_info();
+        AppInfo {
+            name: package.name.clone(),
+            version: package.version.to_string(),
+            identifier: app.config().identifier.clone(),
+            tauri_version: tauri::VERSION.to_string(),
+            data_dir: app
+                .path()
+                .app_data_dir()
+                .ok()
+                .map(|dir| dir.to_string_lossy().to_string()),
+        }
+    }
+
+    /// 管理状態から現在の設定を集める（登録前の状態は空として扱う）
+    fn app_settings(app: &tauri::AppHandle) -> AppSettings {
+        let allowed_directories = app
+            .try_state::<ScopeState>()
+            .and_then(|scope| scope.lock().ok().map(|scope| scope.allowed_roots().user))
+            .unwrap_or_default();
+        let bookmark_count = app
+            .try_state::<PlacesService>()
+            .and_then(|places| places.bookmarks().ok())
+            .map(|bookmarks| bookmarks.len())
+            .unwrap_or(0);
+        let thumbnail_cache_dir = app
+            .try_state::<ThumbnailService>()
+            .map(|thumbnails| thumbnails.cache_dir().to_string_lossy().to_string());
+
+        AppSettings {
+            allowed_directories,
+            bookmark_count,
+            thumbnail_cache_dir,
+        }
+    }
+
+    /// プロバイダからレポートを組み立てる
+    pub fn build_report(
+        provider: &mut impl MetricsProvider,
+        app: AppInfo,
+        settings: AppSettings,
+        redact: bool,
+    ) -> Result<SystemReport, String> {
+        let mut report = SystemReport {
+            generated_at: Utc::now().to_rfc3339(),
+            redacted: false,
+            app,
+            settings,
+            system: SystemService::get_system_info_with(provider)?,
+            networks: SystemService::get_network_info_with(provider),
+            top_processes: SystemService::get_top_processes_with(provider, TOP_PROCESS_COUNT),
+        };
+
+        if redact {
+            Self::redact(&mut report, &Self::current_user_names());
+        }
+
+        Ok(report)
+    }
+
+    /// 実行ユーザー名を環境変数から取得
+    fn current_user_names() -> Vec<String> {
+        ["USER", "USERNAME", "LOGNAME"]
+            .iter()
+            .filter_map(|key| std::env::var(key).ok())
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+
+    /// ホスト名・ユーザー名・MAC アドレスを伏せ字に置き換える
+    ///
+    /// ユーザー名はプロセスの所有者に加え、パス中の同名のセグメントも置き換える。
+    /// MAC アドレスを含むインターフェース名（"enx0011..." など）も伏せる。
+    pub fn redact(report: &mut SystemReport, user_names: &[String]) {
+        let mut names: Vec<String> = user_names.to_vec();
+        names.extend(report.top_processes.iter().filter_map(|p| p.user.clone()));
+        names.push(report.system.hostname.clone());
+        names.retain(|name| !name.is_empty());
+
+        report.system.hostname = REDACTED.to_string();
+        for process in &mut report.top_processes {
+            if process.user.is_some() {
+                process.user = Some(REDACTED.to_string());
+            }
+        }
+        for network in &mut report.networks {
+            let digits: String = network
+                .mac_address
+                .chars()
+                .filter(char::is_ascii_hexdigit)
+                .collect::<String>()
+                .to_ascii_lowercase();
+            if !digits.is_empty() && network.name.to_ascii_lowercase().contains(&digits) {
+                network.name = REDACTED.to_string();
+            }
+            network.mac_address = REDACTED.to_string();
+        }
+        for disk in &mut report.system.disks {
+            disk.name = redact_path(&disk.name, &names);
+            disk.mount_point = redact_path(&disk.mount_point, &names);
+        }
+        report.app.data_dir = report.app.data_dir.as_deref().map(|dir| redact_path(dir, &names));
+        for dir in &mut report.settings.allowed_directories {
+            *dir = redact_path(dir, &names);
+        }
+        report.settings.thumbnail_cache_dir = report
+            .settings
+            .thumbnail_cache_dir
+            .as_deref()
+            .map(|dir| redact_path(dir, &names));
+        report.redacted = true;
+    }
+
+    /// 指定形式の文字列にレンダリング
+    pub fn render(report: &SystemReport, format: ReportFormat) -> Result<String, String> {
+        match format {
+            ReportFormat::Json => serde_json::to_string_pretty(report)
+                .map_err(|e| format!("レポートのシリアライズに失敗しました: {}", e)),
+            ReportFormat::Markdown => Ok(render_markdown(report)),
+            ReportFormat::Html => Ok(render_html(report)),
+        }
+    }
+
+    /// レンダリングしてファイルに書き出す
+    pub fn write_report(report: &SystemReport, format: ReportFormat, path: &Path) -> Result<PathBuf, String> {
+        let content = Self::render(report, format)?;
+        fs::write(path, content)
+            .map_err(|e| format!("レポートの書き込みに失敗しました: {}", e))?;
+        Ok(path.to_path_buf())
+    }
+}
+
+/// パスを区切り文字で分割し、名前と一致するセグメントを伏せ字にする
+fn redact_path(path: &str, names: &[String]) -> String {
+    let mut result = String::with_capacity(path.len());
+    let mut segment = String::new();
+
+    let flush = |segment: &mut String, result: &mut String| {
+        if names.iter().any(|name| name == segment) {
+            result.push_str(REDACTED);
+        } else {
+            result.push_str(segment);
+        }
+        segment.clear();
+    };
+
+    for ch in path.chars() {
+        if ch == '/' || ch == '\\' {
+            flush(&mut segment, &mut result);
+            result.push(ch);
+        } else {
+            segment.push(ch);
+        }
+    }
+    flush(&mut segment, &mut result);
+    result
+}
+
+/// バイト数を人が読みやすい単位に変換
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// 稼働秒数を「1日 2時間 3分」形式に変換
+fn format_uptime(seconds: u64) -> String {
+    let days = seconds / 86_400;
+    let hours = (seconds % 86_400) / 3_600;
+    let minutes = (seconds % 3_600) / 60;
+    format!("{}日 {}時間 {}分", days, hours, minutes)
+}
+
+/// Markdown/HTML出力で共通に使う表形式のセクション
+struct ReportTable {
+    title: &'static str,
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+/// レポートの各セクションを表として取り出す
+fn report_tables(report: &SystemReport) -> Vec<ReportTable> {
+    let app = &report.app;
+    let settings = &report.settings;
+    let system = &report.system;
+
+    vec![
+        ReportTable {
+            title: "アプリケーション",
+            headers: vec!["項目", "値"],
+            rows: vec![
+                vec!["名前".to_string(), app.name.clone()],
+                vec!["バージョン".to_string(), app.version.clone()],
+                vec!["識別子".to_string(), app.identifier.clone()],
+                vec!["Tauri".to_string(), app.tauri_version.clone()],
+                vec!["データディレクトリ".to_string(), app.data_dir.clone().unwrap_or_else(|| "-".to_string())],
+            ],
+        },
+        ReportTable {
+            title: "設定",
+            headers: vec!["項目", "値"],
+            rows: vec![
+                vec![
+                    "許可ディレクトリ".to_string(),
+                    if settings.allowed_directories.is_empty() {
+                        "-".to_string()
+                    } else {
+                        settings.allowed_directories.join(", ")
+                    },
+                ],
+                vec!["ブックマーク数".to_string(), settings.bookmark_count.to_string()],
+                vec![
+                    "サムネイルキャッシュ".to_string(),
+                    settings.thumbnail_cache_dir.clone().unwrap_or_else(|| "-".to_string()),
+                ],
+            ],
+        },
+        ReportTable {
+            title: "システム",
+            headers: vec!["項目", "値"],
+            rows: vec![
+                vec!["OS".to_string(), format!("{} {}", system.os_name, system.os_version)],
+                vec!["カーネル".to_string(), system.kernel_version.clone()],
+                vec!["ホスト名".to_string(), system.hostname.clone()],
+                vec!["稼働時間".to_string(), format_uptime(system.uptime)],
+                vec!["CPU".to_string(), format!("{} ({} コア)", system.cpu_brand, system.cpu_cores)],
+                vec![
+                    "コンテナ".to_string(),
+                    system.environment.container.clone().unwrap_or_else(|| "-".to_string()),
+                ],
+                vec![
+                    "仮想化".to_string(),
+                    match (&system.environment.hypervisor, system.environment.wsl) {
+                        (Some(hypervisor), true) => format!("{} (WSL)", hypervisor),
+                        (Some(hypervisor), false) => hypervisor.clone(),
+                        (None, true) => "WSL".to_string(),
+                        (None, false) => "-".to_string(),
+                    },
+                ],
+                vec![
+                    "cgroup CPU上限".to_string(),
+                    system.environment.cpu_limit
+                        .map(|limit| format!("{:.2} コア", limit))
+                        .unwrap_or_else(|| "-".to_string()),
+                ],
+                vec![
+                    "cgroup メモリ上限".to_string(),
+                    system.environment.memory_limit.map(format_bytes).unwrap_or_else(|| "-".to_string()),
+                ],
+                vec![
+                    "メモリ".to_string(),
+                    format!(
+                        "{} / {} ({:.1}%)",
+                        format_bytes(system.effective_used_memory),
+                        format_bytes(system.effective_total_memory),
+                        system.memory_usage_percent
+                    ),
+                ],
+                vec!["ホストメモリ".to_string(), format_bytes(system.total_memory)],
+            ],
+        },
+        ReportTable {
+            title: "ディスク",
+            headers: vec!["名前", "マウント", "ファイルシステム", "使用量", "合計", "使用率"],
+            rows: system.disks.iter().map(|disk| vec![
+                disk.name.clone(),
+                disk.mount_point.clone(),
+                disk.file_system.clone(),
+                format_bytes(disk.used_space),
+                format_bytes(disk.total_space),
+                format!("{:.1}%", disk.usage_percent),
+            ]).collect(),
+        },
+        ReportTable {
+            title: "ネットワーク",
+            headers: vec!["インターフェース", "MACアドレス", "受信", "送信"],
+            rows: report.networks.iter().map(|network| vec![
+                network.name.clone(),
+                network.mac_address.clone(),
+                format_bytes(network.total_received),
+                format_bytes(network.total_transmitted),
+            ]).collect(),
+        },
+        ReportTable {
+            title: "プロセス（メモリ使用量上位）",
+            headers: vec!["PID", "名前", "ユーザー", "CPU", "メモリ"],
+            rows: report.top_processes.iter().map(|process| vec![
+                process.pid.to_string(),
+                process.name.clone(),
+                process.user.clone().unwrap_or_else(|| "-".to_string()),
+                format!("{:.1}%", process.cpu_usage),
+                format_bytes(process.memory),
+            ]).collect(),
+        },
+    ]
+}
+
+/// Markdownの表セル内で意味を持つ文字をエスケープ
+fn escape_markdown(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Markdown形式のレポートを生成
+fn render_markdown(report: &SystemReport) -> String {
+    let mut out = String::new();
+    out.push_str("# システムレポート\n\n");
+    out.push_str(&format!("- 作成日時: {}\n", report.generated_at));
+    if report.redacted {
+        out.push_str("- ホスト名・ユーザー名は伏せ字にしています\n");
+    }
+
+    for table in report_tables(report) {
+        out.push_str(&format!("\n## {}\n\n", table.title));
+        out.push_str(&format!("| {} |\n", table.headers.join(" | ")));
+        out.push_str(&format!("|{}\n", " --- |".repeat(table.headers.len())));
+        for row in table.rows {
+            let cells: Vec<String> = row.iter().map(|cell| escape_markdown(cell)).collect();
+            out.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+    }
+
+    out
+}
+
+/// HTMLで意味を持つ文字をエスケープ
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// 外部リソースに依存しない単一ファイルのHTMLレポートを生成
+fn render_html(report: &SystemReport) -> String {
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<title>システムレポート</title>\n<style>\n");
+    out.push_str("body { font-family: sans-serif; margin: 2rem; color: #222; }\n");
+    out.push_str("table { border-collapse: collapse; margin-bottom: 1.5rem; }\n");
+    out.push_str("th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }\n");
+    out.push_str("th { background: #f0f0f0; }\n");
+    out.push_str("</style>\n</head>\n<body>\n<h1>システムレポート</h1>\n");
+    out.push_str(&format!("<p>作成日時: {}</p>\n", escape_html(&report.generated_at)));
+    if report.redacted {
+        out.push_str("<p>ホスト名・ユーザー名は伏せ字にしています</p>\n");
+    }
+
+    for table in report_tables(report) {
+        out.push_str(&format!("<h2>{}</h2>\n<table>\n<tr>", escape_html(table.title)));
+        for header in table.headers {
+            out.push_str(&format!("<th>{}</th>", escape_html(header)));
+        }
+        out.push_str("</tr>\n");
+        for row in table.rows {
+            out.push_str("<tr>");
+            for cell in row {
+                out.push_str(&format!("<td>{}</td>", escape_html(&cell)));
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</table>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system_service::tests::{process, FakeMetricsProvider};
+    use crate::system_service::{DiskSnapshot, HostSnapshot};
+    use tempfile::TempDir;
+
+    fn test_app_info() -> AppInfo {
+        AppInfo {
+            name: "tauri-app".to_string(),
+            version: "0.1.0".to_string(),
+            identifier: "com.example.standalone".to_string(),
+            tauri_version: "2.0.0".to_string(),
+            data_dir: Some("/home/alice/.local/share/com.example.standalone".to_string()),
+        }
+    }
+
+    fn test_settings() -> AppSettings {
+        AppSettings {
+            allowed_directories: vec!["/media/alice/photos".to_string()],
+            bookmark_count: 2,
+            thumbnail_cache_dir: Some("/home/alice/.cache/com.example.standalone/thumbnails".to_string()),
+        }
+    }
+
+    fn test_provider() -> FakeMetricsProvider {
+        FakeMetricsProvider {
+            host: HostSnapshot {
+                hostname: Some("alice-laptop".to_string()),
+                ..Default::default()
+            },
+            disks: vec![DiskSnapshot {
+                name: "<disk> | 1".to_string(),
+                mount_point: "/home/alice".to_string(),
+                total_space: 2048,
+                available_space: 1024,
+                file_system: "ext4".to_string(),
+            }],
+            processes: vec![process(42, "editor", 4096)],
+            networks: vec![
+                NetworkInfo {
+                    name: "eth0".to_string(),
+                    mac_address: "00:11:22:aa:bb:cc".to_string(),
+                    total_received: 100,
+                    total_transmitted: 200,
+                },
+                NetworkInfo {
+                    name: "enx001122aabbcd".to_string(),
+                    mac_address: "00:11:22:AA:BB:CD".to_string(),
+                    total_received: 0,
+                    total_transmitted: 0,
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_report_collects_sections() {
+        let report = ReportService::build_report(&mut test_provider(), test_app_info(), test_settings(), false).unwrap();
+        assert!(!report.redacted);
+        assert_eq!(report.system.hostname, "alice-laptop");
+        assert_eq!(report.top_processes.len(), 1);
+        assert_eq!(report.app.version, "0.1.0");
+        assert_eq!(report.settings.bookmark_count, 2);
+    }
+
+    #[test]
+    fn test_redact_hides_hostname_and_user_names() {
+        let mut report = ReportService::build_report(&mut test_provider(), test_app_info(), test_settings(), false).unwrap();
+        ReportService::redact(&mut report, &[]);
+
+        assert!(report.redacted);
+        assert_eq!(report.system.hostname, REDACTED);
+        assert_eq!(report.top_processes[0].user.as_deref(), Some(REDACTED));
+        assert_eq!(report.system.disks[0].mount_point, "/home/[REDACTED]");
+        assert_eq!(
+            report.app.data_dir.as_deref(),
+            Some("/home/[REDACTED]/.local/share/com.example.standalone")
+        );
+
+        assert_eq!(report.settings.allowed_directories, vec!["/media/[REDACTED]/photos"]);
+        assert_eq!(report.networks[0].name, REDACTED);
+        assert_eq!(report.networks[1].name, "eth0");
+        assert!(report.networks.iter().all(|network| network.mac_address == REDACTED));
+
+        let json = ReportService::render(&report, ReportFormat::Json).unwrap();
+        assert!(!json.contains("alice"));
+        assert!(!json.to_ascii_lowercase().contains("aa:bb"));
+        assert!(!json.contains("001122"));
+    }
+
+    #[test]
+    fn test_redact_path_only_replaces_whole_segments() {
+        let names = vec!["al".to_string()];
+        assert_eq!(redact_path("/home/al/alpha", &names), "/home/[REDACTED]/alpha");
+        assert_eq!(redact_path("C:\\Users\\al", &names), "C:\\Users\\[REDACTED]");
+    }
+
+    #[test]
+    fn test_render_markdown_and_html_escape_values() {
+        let report = ReportService::build_report(&mut test_provider(), test_app_info(), test_settings(), false).unwrap();
+
+        let markdown = ReportService::render(&report, ReportFormat::Markdown).unwrap();
+        assert!(markdown.starts_with("# システムレポート"));
+        assert!(markdown.contains("<disk> \\| 1"));
+        assert!(markdown.contains("## 設定"));
+        assert!(markdown.contains("| ブックマーク数 | 2 |"));
+
+        let html = ReportService::render(&report, ReportFormat::Html).unwrap();
+        assert!(html.contains("&lt;disk&gt; | 1"));
+        assert!(!html.contains("<disk>"));
+        assert!(!html.contains("<link") && !html.contains("<script"));
+    }
+
+    #[test]
+    fn test_write_report_creates_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("report.json");
+        let report = ReportService::build_report(&mut test_provider(), test_app_info(), test_settings(), false).unwrap();
+
+        ReportService::write_report(&report, ReportFormat::Json, &path).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(parsed["app"]["name"], "tauri-app");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+}
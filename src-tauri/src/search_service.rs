@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use crate::file_service::{ContentSearchOptions, FileService};
+use crate::walker::{build_walker, SearchSummary};
+
+/// 検索結果をまとめて送るイベント名
+pub const SEARCH_MATCH_EVENT: &str = "search-match";
+
+/// 内容検索の結果をまとめて送るイベント名
+pub const CONTENT_MATCH_EVENT: &str = "content-match";
+
+/// 検索終了時に送るイベント名（ファイル名検索・内容検索共通）
+pub const SEARCH_FINISHED_EVENT: &str = "search-finished";
+
+/// 一度のイベントで送る最大件数
+const BATCH_SIZE: usize = 100;
+
+/// 件数が溜まらなくても結果を送る間隔
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// ファイル名パターンの種類
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternKind {
+    #[default]
+    Glob,
+    Regex,
+}
+
+/// 検索条件（すべて省略可能）
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// ファイル名のパターン。`/` を含むグロブはルートからの相対パスと照合する
+    pub pattern: Option<String>,
+    pub pattern_kind: PatternKind,
+    pub case_sensitive: bool,
+    /// .gitignore / .ignore に従って除外する
+    pub respect_gitignore: bool,
+    pub include_hidden: bool,
+    pub include_directories: bool,
+    pub max_depth: Option<usize>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// 更新日時の下限（UNIX秒）
+    pub modified_after: Option<u64>,
+    /// 更新日時の上限（UNIX秒）
+    pub modified_before: Option<u64>,
+    pub max_results: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SearchMatch {
+    pub path: String,
+    pub name: String,
+    pub is_directory: bool,
+    pub size: u64,
+    pub modified: Option<u64>,
+}
+
+/// search-match / content-match イベントの内容
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SearchMatchBatch<T = SearchMatch> {
+    pub search_id: u64,
+    pub matches: Vec<T>,
+}
+
+/// search-finished イベントの内容
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SearchFinished {
+    pub search_id: u64,
+    pub summary: SearchSummary,
+    pub error: Option<String>,
+}
+
+/// コンパイル済みのファイル名パターン
+enum NameMatcher {
+    Any,
+    Name(GlobMatcher),
+    RelativePath(GlobMatcher),
+    Regex(Regex),
+}
+
+impl NameMatcher {
+    fn new(options: &SearchOptions) -> Result<Self, String> {
+        let Some(pattern) = options.pattern.as_deref().filter(|p| !p.is_empty()) else {
+            return Ok(NameMatcher::Any);
+        };
+
+        match options.pattern_kind {
+            PatternKind::Glob => {
+                let glob = GlobBuilder::new(pattern)
+                    .case_insensitive(!options.case_sensitive)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| format!("グロブパターンが不正です: {}", e))?
+                    .compile_matcher();
+                if pattern.contains('/') {
+                    Ok(NameMatcher::RelativePath(glob))
+                } else {
+                    Ok(NameMatcher::Name(glob))
+                }
+            }
+            PatternKind::Regex => RegexBuilder::new(pattern)
+                .case_insensitive(!options.case_sensitive)
+                .build()
+                .map(NameMatcher::Regex)
+                .map_err(|e| format!("正規表現が不正です: {}", e)),
+        }
+    }
+
+    fn is_match(&self, name: &str, relative: &Path) -> bool {
+        match self {
+            NameMatcher::Any => true,
+            NameMatcher::Name(glob) => glob.is_match(name),
+            NameMatcher::RelativePath(glob) => glob.is_match(relative),
+            NameMatcher::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// ディレクトリを再帰的に検索するサービスクラス
+pub struct SearchService;
+
+impl SearchService {
+
+    /// 検索条件が正しいかを確認（不正なパターンや範囲はエラー）
+    pub fn validate(options: &SearchOptions) -> Result<(), String> {
+        NameMatcher::new(options)?;
+        if let (Some(min), Some(max)) = (options.min_size, options.max_size) {
+            if min > max {
+                return Err("サイズの下限が上限を超えています".to_string());
+            }
+        }
+        if let (Some(after), Some(before)) = (options.modified_after, options.modified_before) {
+            if after > before {
+                return Err("更新日時の開始が終了より後になっています".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// `root` 以下を走査し、条件に合うエントリごとに `on_match` を呼ぶ
+    ///
+    /// `cancel` が立つと走査を中断する。シンボリックリンクは辿らない。
+    pub fn search<F>(
+        root: &Path,
+        options: &SearchOptions,
+        cancel: &AtomicBool,
+        mut on_match: F,
+    ) -> Result<SearchSummary, String>
+    where
+        F: FnMut(SearchMatch),
+    {
+        if !root.is_dir() {
+            return Err("ディレクトリが見つかりません".to_string());
+        }
+        Self::validate(options)?;
+        let matcher = NameMatcher::new(options)?;
+
+        let walker = build_walker(root, options.include_hidden, options.respect_gitignore, options.max_depth);
+
+        let mut summary = SearchSummary::default();
+        for entry in walker {
+            if cancel.load(Ordering::Relaxed) {
+                summary.cancelled = true;
+                break;
+            }
+
+            // 読めないディレクトリなどは飛ばして続行する
+            let Ok(entry) = entry else { continue };
+            if entry.depth() == 0 {
+                continue;
+            }
+            summary.scanned += 1;
+
+            let Some(file_type) = entry.file_type() else { continue };
+            let is_directory = file_type.is_dir();
+            if is_directory && !options.include_directories {
+                continue;
+            }
+
+            let name = entry.file_name().to_string_lossy().to_string();
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            if !matcher.is_match(&name, relative) {
+                continue;
+            }
+
+            let Ok(metadata) = entry.metadata() else { continue };
+            let size = if is_directory { 0 } else { metadata.len() };
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs());
+
+            if !is_directory
+                && (options.min_size.is_some_and(|min| size < min)
+                    || options.max_size.is_some_and(|max| size > max))
+            {
+                continue;
+            }
+            let in_range = |time: Option<u64>| match time {
+                Some(time) => {
+                    options.modified_after.is_none_or(|after| time >= after)
+                        && options.modified_before.is_none_or(|before| time <= before)
+                }
+                None => options.modified_after.is_none() && options.modified_before.is_none(),
+            };
+            if !in_range(modified) {
+                continue;
+            }
+
+            on_match(SearchMatch {
+                path: entry.path().to_string_lossy().to_string(),
+                name,
+                is_directory,
+                size,
+                modified,
+            });
+            summary.matched += 1;
+
+            if options.max_results.is_some_and(|max| summary.matched >= max) {
+                summary.truncated = true;
+                break;
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+/// 実行中の検索を管理し、IDで中断できるようにする
+#[derive(Default)]
+pub struct SearchRegistry {
+    next_id: AtomicU64,
+    running: Mutex<HashMap<u64, Arc<AtomicBool>>>,
+}
+
+impl SearchRegistry {
+    /// バックグラウンドでファイル名検索を開始し、検索IDを返す
+    ///
+    /// 結果は search-match イベントでまとめて送り、終了時に search-finished を送る。
+    pub fn start(&self, app: tauri::AppHandle, root: PathBuf, options: SearchOptions) -> Result<u64, String> {
+        SearchService::validate(&options)?;
+        self.spawn(app, SEARCH_MATCH_EVENT, move |cancel, on_match| {
+            SearchService::search(&root, &options, cancel, on_match)
+        })
+    }
+
+    /// バックグラウンドで内容検索を開始し、検索IDを返す
+    ///
+    /// 結果は content-match イベントでまとめて送り、終了時に search-finished を送る。
+    pub fn start_content_search(&self, app: tauri::AppHandle, root: PathBuf, options: ContentSearchOptions) -> Result<u64, String> {
+        FileService::validate_content_search(&options)?;
+        self.spawn(app, CONTENT_MATCH_EVENT, move |cancel, on_match| {
+            FileService::search_contents(&root, &options, cancel, on_match)
+        })
+    }
+
+    /// 検索処理を別スレッドで実行し、見つかった結果を一定件数・一定間隔ごとにイベントで送る
+    fn spawn<T, R>(&self, app: tauri::AppHandle, event: &'static str, run: R) -> Result<u64, String>
+    where
+        T: Serialize + Clone + Send + 'static,
+        R: FnOnce(&AtomicBool, &mut dyn FnMut(T)) -> Result<SearchSummary, String> + Send + 'static,
+    {
+        let search_id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.running
+            .lock()
+            .map_err(|e| format!("検索状態のロックに失敗しました: {}", e))?
+            .insert(search_id, cancel.clone());
+
+        std::thread::spawn(move || {
+            let mut batch = Vec::new();
+            let mut last_sent = Instant::now();
+            let send = |matches: Vec<T>| {
+                let _ = app.emit(event, SearchMatchBatch { search_id, matches });
+            };
+
+            let result = run(&cancel, &mut |found| {
+                batch.push(found);
+                if batch.len() >= BATCH_SIZE || last_sent.elapsed() >= BATCH_INTERVAL {
+                    send(std::mem::take(&mut batch));
+                    last_sent = Instant::now();
+                }
+            });
+            if !batch.is_empty() {
+                send(batch);
+            }
+
+            let (summary, error) = match result {
+                Ok(summary) => (summary, None),
+                Err(e) => (SearchSummary::default(), Some(e)),
+            };
+            let _ = app.emit(SEARCH_FINISHED_EVENT, SearchFinished { search_id, summary, error });
+
+            if let Some(registry) = tauri::Manager::try_state::<SearchRegistry>(&app) {
+                registry.finish(search_id);
+            }
+        });
+
+        Ok(search_id)
+    }
+
+    /// 検索を中断する。該当する検索が無ければ false
+    pub fn cancel(&self, search_id: u64) -> Result<bool, String> {
+        let running = self
+            .running
+            .lock()
+            .map_err(|e| format!("検索状態のロックに失敗しました: {}", e))?;
+        match running.get(&search_id) {
+            Some(cancel) => {
+                cancel.store(true, Ordering::Relaxed);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn finish(&self, search_id: u64) {
+        if let Ok(mut running) = self.running.lock() {
+            running.remove(&search_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join("README.md"), "readme").unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("src/nested/lib.RS"), "x".repeat(2048)).unwrap();
+        fs::write(root.join("target/build.rs"), "").unwrap();
+        fs::write(root.join(".hidden.rs"), "").unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        temp_dir
+    }
+
+    fn names(root: &Path, options: &SearchOptions) -> Vec<String> {
+        let mut found = Vec::new();
+        SearchService::search(root, options, &AtomicBool::new(false), |m| found.push(m.name)).unwrap();
+        found.sort();
+        found
+    }
+
+    fn glob(pattern: &str) -> SearchOptions {
+        SearchOptions { pattern: Some(pattern.to_string()), ..Default::default() }
+    }
+
+    #[test]
+    fn test_glob_case_and_gitignore() {
+        let temp_dir = setup();
+        let root = temp_dir.path();
+
+        assert_eq!(names(root, &glob("*.rs")), vec!["build.rs", "lib.RS", "main.rs"]);
+        assert_eq!(
+            names(root, &SearchOptions { case_sensitive: true, ..glob("*.rs") }),
+            vec!["build.rs", "main.rs"]
+        );
+        assert_eq!(
+            names(root, &SearchOptions { respect_gitignore: true, ..glob("*.rs") }),
+            vec!["lib.RS", "main.rs"]
+        );
+        assert_eq!(
+            names(root, &SearchOptions { include_hidden: true, ..glob("*.rs") }),
+            vec![".hidden.rs", "build.rs", "lib.RS", "main.rs"]
+        );
+        assert_eq!(names(root, &glob("src/*.rs")), vec!["main.rs"]);
+        assert_eq!(names(root, &glob("src/**/*.rs")), vec!["lib.RS", "main.rs"]);
+    }
+
+    #[test]
+    fn test_regex_depth_and_directories() {
+        let temp_dir = setup();
+        let root = temp_dir.path();
+        let options = SearchOptions {
+            pattern: Some("^(main|lib)\\.".to_string()),
+            pattern_kind: PatternKind::Regex,
+            ..Default::default()
+        };
+        assert_eq!(names(root, &options), vec!["lib.RS", "main.rs"]);
+        assert_eq!(names(root, &SearchOptions { max_depth: Some(2), ..options }), vec!["main.rs"]);
+
+        let dirs = SearchOptions { include_directories: true, ..glob("n*") };
+        assert_eq!(names(root, &dirs), vec!["nested"]);
+    }
+
+    #[test]
+    fn test_size_and_modified_filters() {
+        let temp_dir = setup();
+        let root = temp_dir.path();
+
+        assert_eq!(names(root, &SearchOptions { min_size: Some(1024), ..Default::default() }), vec!["lib.RS"]);
+        assert_eq!(
+            names(root, &SearchOptions { min_size: Some(1), max_size: Some(100), ..glob("*.rs") }),
+            vec!["main.rs"]
+        );
+        assert!(names(root, &SearchOptions { modified_after: Some(u64::MAX / 2), ..Default::default() }).is_empty());
+        assert_eq!(names(root, &SearchOptions { modified_before: Some(u64::MAX / 2), ..glob("*.md") }), vec!["README.md"]);
+    }
+
+    #[test]
+    fn test_max_results_and_cancel() {
+        let temp_dir = setup();
+        let root = temp_dir.path();
+
+        let summary = SearchService::search(
+            root,
+            &SearchOptions { max_results: Some(1), ..glob("*.rs") },
+            &AtomicBool::new(false),
+            |_| {},
+        ).unwrap();
+        assert_eq!(summary.matched, 1);
+        assert!(summary.truncated);
+
+        let cancel = AtomicBool::new(false);
+        let mut found = 0;
+        let summary = SearchService::search(root, &SearchOptions::default(), &cancel, |_| {
+            found += 1;
+            cancel.store(true, Ordering::Relaxed);
+        }).unwrap();
+        assert_eq!(found, 1);
+        assert!(summary.cancelled);
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let invalid_regex = SearchOptions {
+            pattern: Some("(".to_string()),
+            pattern_kind: PatternKind::Regex,
+            ..Default::default()
+        };
+        assert!(SearchService::validate(&invalid_regex).unwrap_err().contains("正規表現"));
+        assert!(SearchService::validate(&glob("[")).is_err());
+        assert!(SearchService::validate(&SearchOptions { min_size: Some(10), max_size: Some(1), ..Default::default() }).is_err());
+
+        let result = SearchService::search(Path::new("/nonexistent"), &SearchOptions::default(), &AtomicBool::new(false), |_| {});
+        assert_eq!(result.unwrap_err(), "ディレクトリが見つかりません");
+    }
+
+    #[test]
+    fn test_registry_cancel_unknown_search() {
+        let registry = SearchRegistry::default();
+        assert!(!registry.cancel(42).unwrap());
+    }
+}
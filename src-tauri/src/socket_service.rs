@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SocketProtocol {
+    Tcp,
+    Tcp6,
+    Udp,
+    Udp6,
+}
+
+impl SocketProtocol {
+    const ALL: [SocketProtocol; 4] = [
+        SocketProtocol::Tcp,
+        SocketProtocol::Tcp6,
+        SocketProtocol::Udp,
+        SocketProtocol::Udp6,
+    ];
+
+    /// /proc/net 以下のファイル名
+    fn proc_file(&self) -> &'static str {
+        match self {
+            SocketProtocol::Tcp => "tcp",
+            SocketProtocol::Tcp6 => "tcp6",
+            SocketProtocol::Udp => "udp",
+            SocketProtocol::Udp6 => "udp6",
+        }
+    }
+
+    fn is_tcp(&self) -> bool {
+        matches!(self, SocketProtocol::Tcp | SocketProtocol::Tcp6)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SocketInfo {
+    pub protocol: SocketProtocol,
+    pub local_address: String,
+    pub local_port: u16,
+    pub remote_address: String,
+    pub remote_port: u16,
+    pub state: String,
+    pub uid: u32,
+    pub inode: u64,
+    pub pid: Option<u32>,
+    pub process_name: Option<String>,
+}
+
+impl SocketInfo {
+    /// 接続待ち状態か（TCPの LISTEN、または未接続のUDP）
+    pub fn is_listening(&self) -> bool {
+        self.state == "LISTEN" || self.state == "UNCONN"
+    }
+}
+
+/// ソケット一覧の絞り込み条件（未指定の項目は条件にしない）
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SocketFilter {
+    pub protocols: Option<Vec<SocketProtocol>>,
+    pub listening_only: Option<bool>,
+    pub state: Option<String>,
+    pub port: Option<u16>,
+    pub pid: Option<u32>,
+}
+
+impl SocketFilter {
+    /// ソケットが条件に一致するか
+    pub fn matches(&self, socket: &SocketInfo) -> bool {
+        if let Some(protocols) = &self.protocols {
+            if !protocols.contains(&socket.protocol) {
+                return false;
+            }
+        }
+        if self.listening_only.unwrap_or(false) && !socket.is_listening() {
+            return false;
+        }
+        if let Some(state) = &self.state {
+            if !socket.state.eq_ignore_ascii_case(state) {
+                return false;
+            }
+        }
+        if let Some(port) = self.port {
+            if socket.local_port != port && socket.remote_port != port {
+                return false;
+            }
+        }
+        if let Some(pid) = self.pid {
+            if socket.pid != Some(pid) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// /proc/net からソケット情報を取得するサービスクラス（Linux専用）
+pub struct SocketService;
+
+impl SocketService {
+
+    /// 実行中のシステムのソケット一覧を取得
+    pub fn list() -> Result<Vec<SocketInfo>, String> {
+        if cfg!(target_os = "linux") {
+            Self::list_from_root(Path::new("/"))
+        } else {
+            Err("ソケット一覧はLinuxでのみ取得できます".to_string())
+        }
+    }
+
+    /// 指定したルート配下の /proc を読んでソケット一覧を取得
+    pub fn list_from_root(root: &Path) -> Result<Vec<SocketInfo>, String> {
+        let proc_dir = root.join("proc");
+        let net_dir = proc_dir.join("net");
+        if !net_dir.is_dir() {
+            return Err("/proc/net が見つかりません".to_string());
+        }
+
+        let mut sockets = Vec::new();
+        for protocol in SocketProtocol::ALL {
+            // IPv6 が無効な環境では tcp6/udp6 が存在しないため読めないものは飛ばす
+            if let Ok(content) = fs::read_to_string(net_dir.join(protocol.proc_file())) {
+                sockets.extend(parse_proc_net(&content, protocol));
+            }
+        }
+
+        let owners = socket_owners(&proc_dir);
+        for socket in &mut sockets {
+            if let Some((pid, name)) = owners.get(&socket.inode) {
+                socket.pid = Some(*pid);
+                socket.process_name = name.clone();
+            }
+        }
+
+        Ok(sockets)
+    }
+}
+
+/// /proc/net/{tcp,tcp6,udp,udp6} の内容を解析
+fn parse_proc_net(content: &str, protocol: SocketProtocol) -> Vec<SocketInfo> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| parse_proc_net_line(line, protocol))
+        .collect()
+}
+
+/// /proc/net の1行を解析。形式が不正な行は None
+fn parse_proc_net_line(line: &str, protocol: SocketProtocol) -> Option<SocketInfo> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 10 {
+        return None;
+    }
+
+    let (local_address, local_port) = parse_socket_address(fields[1])?;
+    let (remote_address, remote_port) = parse_socket_address(fields[2])?;
+    let state_code = u8::from_str_radix(fields[3], 16).ok()?;
+
+    Some(SocketInfo {
+        protocol,
+        local_address,
+        local_port,
+        remote_address,
+        remote_port,
+        state: state_name(state_code, protocol).to_string(),
+        uid: fields[7].parse().ok()?,
+        inode: fields[9].parse().ok()?,
+        pid: None,
+        process_name: None,
+    })
+}
+
+/// "0100007F:1F90" 形式のアドレスを (IPアドレス, ポート) に変換
+///
+/// アドレス部はホストのバイト順のまま32ビット単位で16進出力されているため、
+/// ネイティブエンディアンでバイト列に戻す。
+fn parse_socket_address(value: &str) -> Option<(String, u16)> {
+    let (address, port) = value.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let address = match address.len() {
+        8 => {
+            let raw = u32::from_str_radix(address, 16).ok()?;
+            Ipv4Addr::from(raw.to_ne_bytes()).to_string()
+        }
+        32 => {
+            let mut bytes = [0u8; 16];
+            for (i, chunk) in bytes.chunks_mut(4).enumerate() {
+                let word = u32::from_str_radix(&address[i * 8..i * 8 + 8], 16).ok()?;
+                chunk.copy_from_slice(&word.to_ne_bytes());
+            }
+            Ipv6Addr::from(bytes).to_string()
+        }
+        _ => return None,
+    };
+
+    Some((address, port))
+}
+
+/// カーネルの状態コードを表示名に変換
+fn state_name(code: u8, protocol: SocketProtocol) -> &'static str {
+    match code {
+        0x01 => "ESTABLISHED",
+        0x02 => "SYN_SENT",
+        0x03 => "SYN_RECV",
+        0x04 => "FIN_WAIT1",
+        0x05 => "FIN_WAIT2",
+        0x06 => "TIME_WAIT",
+        // UDP では未接続のソケットが CLOSE として報告される
+        0x07 if !protocol.is_tcp() => "UNCONN",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE_WAIT",
+        0x09 => "LAST_ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+/// /proc/<pid>/fd のリンク先からソケットの inode と所有プロセスの対応表を作る
+///
+/// 他ユーザーのプロセスは権限不足で読めないため、読めたものだけを返す。
+fn socket_owners(proc_dir: &Path) -> HashMap<u64, (u32, Option<String>)> {
+    let mut owners = HashMap::new();
+    let Ok(entries) = fs::read_dir(proc_dir) else {
+        return owners;
+    };
+
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+            continue;
+        };
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+
+        let name = fs::read_to_string(entry.path().join("comm"))
+            .ok()
+            .map(|comm| comm.trim().to_string());
+
+        for fd in fds.flatten() {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            if let Some(inode) = parse_socket_link(&target.to_string_lossy()) {
+                owners.entry(inode).or_insert_with(|| (pid, name.clone()));
+            }
+        }
+    }
+
+    owners
+}
+
+/// "socket:[12345]" 形式のリンク先から inode を取り出す
+fn parse_socket_link(target: &str) -> Option<u64> {
+    target
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const TCP: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4242 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:1F90 0100007F:D431 01 00000000:00000000 00:00000000 00000000  1000        0 4343 1 0000000000000000 20 4 30 10 -1
+";
+
+    const TCP6: &str = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000001000000:0BB8 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 5151 1 0000000000000000 100 0 0 10 0
+";
+
+    const UDP: &str = "   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+  100: 00000000:14E9 00000000:0000 07 00000000:00000000 00:00000000 00000000   100        0 6161 2 0000000000000000 0
+";
+
+    #[test]
+    fn test_parse_ipv4_and_ipv6_addresses() {
+        assert_eq!(parse_socket_address("0100007F:1F90"), Some(("127.0.0.1".to_string(), 8080)));
+        assert_eq!(
+            parse_socket_address("00000000000000000000000001000000:0BB8"),
+            Some(("::1".to_string(), 3000))
+        );
+        assert_eq!(parse_socket_address("zz:1"), None);
+    }
+
+    #[test]
+    fn test_parse_proc_net_tcp() {
+        let sockets = parse_proc_net(TCP, SocketProtocol::Tcp);
+        assert_eq!(sockets.len(), 2);
+        assert_eq!(sockets[0].state, "LISTEN");
+        assert_eq!(sockets[0].local_port, 8080);
+        assert_eq!(sockets[0].uid, 1000);
+        assert_eq!(sockets[0].inode, 4242);
+        assert_eq!(sockets[1].state, "ESTABLISHED");
+        assert_eq!(sockets[1].remote_port, 54321);
+    }
+
+    #[test]
+    fn test_udp_unconnected_is_listening() {
+        let sockets = parse_proc_net(UDP, SocketProtocol::Udp);
+        assert_eq!(sockets.len(), 1);
+        assert_eq!(sockets[0].state, "UNCONN");
+        assert!(sockets[0].is_listening());
+    }
+
+    #[test]
+    fn test_filter_by_listening_port_and_protocol() {
+        let mut sockets = parse_proc_net(TCP, SocketProtocol::Tcp);
+        sockets.extend(parse_proc_net(TCP6, SocketProtocol::Tcp6));
+
+        let listening = SocketFilter { listening_only: Some(true), ..Default::default() };
+        assert_eq!(sockets.iter().filter(|s| listening.matches(s)).count(), 2);
+
+        let by_port = SocketFilter { port: Some(8080), ..Default::default() };
+        assert_eq!(sockets.iter().filter(|s| by_port.matches(s)).count(), 2);
+
+        let tcp6 = SocketFilter { protocols: Some(vec![SocketProtocol::Tcp6]), ..Default::default() };
+        let matched: Vec<_> = sockets.iter().filter(|s| tcp6.matches(s)).collect();
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].local_address, "::1");
+
+        let established = SocketFilter { state: Some("established".to_string()), ..Default::default() };
+        assert_eq!(sockets.iter().filter(|s| established.matches(s)).count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_from_root_resolves_owning_pid() {
+        let temp_dir = TempDir::new().unwrap();
+        let proc_dir = temp_dir.path().join("proc");
+        fs::create_dir_all(proc_dir.join("net")).unwrap();
+        fs::write(proc_dir.join("net/tcp"), TCP).unwrap();
+        fs::create_dir_all(proc_dir.join("321/fd")).unwrap();
+        fs::write(proc_dir.join("321/comm"), "vite\n").unwrap();
+        std::os::unix::fs::symlink("socket:[4242]", proc_dir.join("321/fd/5")).unwrap();
+        std::os::unix::fs::symlink("/dev/null", proc_dir.join("321/fd/0")).unwrap();
+
+        let sockets = SocketService::list_from_root(temp_dir.path()).unwrap();
+        assert_eq!(sockets.len(), 2);
+        assert_eq!(sockets[0].pid, Some(321));
+        assert_eq!(sockets[0].process_name.as_deref(), Some("vite"));
+        assert_eq!(sockets[1].pid, None);
+    }
+
+    #[test]
+    fn test_list_from_root_without_proc() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(SocketService::list_from_root(temp_dir.path()).is_err());
+    }
+}
@@ -1,5 +1,9 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use serde::{Deserialize, Serialize};
-use sysinfo::{System, Disks};
+use sysinfo::{System, Disks, Networks, Users};
+use crate::environment_service::{EnvironmentInfo, EnvironmentService};
+use crate::socket_service::{SocketFilter, SocketInfo, SocketService};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct SystemInfo {
@@ -9,12 +13,18 @@ pub struct SystemInfo {
     pub hostname: String,
     pub cpu_brand: String,
     pub cpu_cores: usize,
+    /// ホスト全体のメモリ量
     pub total_memory: u64,
     pub used_memory: u64,
     pub available_memory: u64,
+    /// cgroup の上限があればそれを反映した、このアプリから使えるメモリ量
+    pub effective_total_memory: u64,
+    pub effective_used_memory: u64,
+    /// effective_used_memory / effective_total_memory の割合（%）
     pub memory_usage_percent: f32,
     pub uptime: u64,
     pub disks: Vec<DiskInfo>,
+    pub environment: EnvironmentInfo,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -35,6 +45,239 @@ pub struct RealTimeMetrics {
     pub timestamp: i64,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NetworkInfo {
+    pub name: String,
+    pub mac_address: String,
+    pub total_received: u64,
+    pub total_transmitted: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub user: Option<String>,
+    pub cpu_usage: f32,
+    pub memory: u64,
+}
+
+/// OS・ホストに関する静的な情報
+#[derive(Clone, Debug, Default)]
+pub struct HostSnapshot {
+    pub os_name: Option<String>,
+    pub os_version: Option<String>,
+    pub kernel_version: Option<String>,
+    pub hostname: Option<String>,
+    pub uptime: u64,
+}
+
+/// メモリ使用量のサンプル（バイト単位）
+#[derive(Clone, Debug, Default)]
+pub struct MemorySnapshot {
+    pub total: u64,
+    pub used: u64,
+    pub available: u64,
+}
+
+/// ディスク1台分のサンプル
+#[derive(Clone, Debug, Default)]
+pub struct DiskSnapshot {
+    pub name: String,
+    pub mount_point: String,
+    pub total_space: u64,
+    pub available_space: u64,
+    pub file_system: String,
+}
+
+/// システムメトリクスの取得元を抽象化するトレイト
+///
+/// 本番では `SysinfoMetricsProvider` を使い、テストでは任意の値を返す実装に差し替える。
+pub trait MetricsProvider {
+    /// OS名・カーネル・ホスト名・稼働時間を取得
+    fn host(&mut self) -> HostSnapshot;
+
+    /// CPUのブランド名を取得
+    fn cpu_brand(&mut self) -> String;
+
+    /// 論理コア数を取得
+    fn cpu_core_count(&mut self) -> usize;
+
+    /// コアごとのCPU使用率（%）をサンプリング
+    fn sample_cpu_usage(&mut self) -> Vec<f32>;
+
+    /// メモリ使用量を取得
+    fn memory(&mut self) -> MemorySnapshot;
+
+    /// マウントされているディスク一覧を取得
+    fn disks(&mut self) -> Vec<DiskSnapshot>;
+
+    /// ネットワークインターフェース一覧を取得
+    fn networks(&mut self) -> Vec<NetworkInfo>;
+
+    /// 実行中のプロセス一覧をサンプリング
+    fn processes(&mut self) -> Vec<ProcessInfo>;
+
+    /// コンテナ・仮想化環境と cgroup の制限を取得
+    fn environment(&mut self) -> EnvironmentInfo;
+
+    /// TCP/UDP ソケットの一覧を取得
+    fn sockets(&mut self) -> Result<Vec<SocketInfo>, String>;
+}
+
+/// 一度だけ検出する環境情報と、定期的に読み直すメモリ使用量のファイル
+struct DetectedEnvironment {
+    info: EnvironmentInfo,
+    memory_usage_file: Option<PathBuf>,
+}
+
+/// コンテナ・仮想化環境は実行中に変わらないため、プロセス全体で最初の1回だけ検出する
+static DETECTED_ENVIRONMENT: OnceLock<DetectedEnvironment> = OnceLock::new();
+
+/// sysinfo クレートを使った MetricsProvider の実装
+pub struct SysinfoMetricsProvider {
+    system: System,
+}
+
+impl SysinfoMetricsProvider {
+    /// CPU使用率の計測に必要な待機時間
+    const CPU_SAMPLE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+    pub fn new() -> Self {
+        Self { system: System::new() }
+    }
+}
+
+impl Default for SysinfoMetricsProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetricsProvider for SysinfoMetricsProvider {
+    fn host(&mut self) -> HostSnapshot {
+        HostSnapshot {
+            os_name: System::name(),
+            os_version: System::os_version(),
+            kernel_version: System::kernel_version(),
+            hostname: System::host_name(),
+            uptime: System::uptime(),
+        }
+    }
+
+    fn cpu_brand(&mut self) -> String {
+        self.system.refresh_cpu();
+        // global_cpu_info() のブランド名は環境によって空になるため、先頭コアの値を使う
+        self.system
+            .cpus()
+            .first()
+            .map(|cpu| cpu.brand().to_string())
+            .unwrap_or_default()
+    }
+
+    fn cpu_core_count(&mut self) -> usize {
+        self.system.refresh_cpu();
+        self.system.cpus().len()
+    }
+
+    fn sample_cpu_usage(&mut self) -> Vec<f32> {
+        // CPU情報を更新（少し時間をおいて正確な使用率を取得）
+        self.system.refresh_cpu();
+        std::thread::sleep(Self::CPU_SAMPLE_INTERVAL);
+        self.system.refresh_cpu();
+
+        self.system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect()
+    }
+
+    fn memory(&mut self) -> MemorySnapshot {
+        self.system.refresh_memory();
+        MemorySnapshot {
+            total: self.system.total_memory(),
+            used: self.system.used_memory(),
+            available: self.system.available_memory(),
+        }
+    }
+
+    fn disks(&mut self) -> Vec<DiskSnapshot> {
+        Disks::new_with_refreshed_list()
+            .iter()
+            .map(|disk| DiskSnapshot {
+                name: disk.name().to_string_lossy().to_string(),
+                mount_point: disk.mount_point().to_string_lossy().to_string(),
+                total_space: disk.total_space(),
+                available_space: disk.available_space(),
+                file_system: disk.file_system().to_string_lossy().to_string(),
+            })
+            .collect()
+    }
+
+    fn networks(&mut self) -> Vec<NetworkInfo> {
+        Networks::new_with_refreshed_list()
+            .iter()
+            .map(|(name, data)| NetworkInfo {
+                name: name.clone(),
+                mac_address: data.mac_address().to_string(),
+                total_received: data.total_received(),
+                total_transmitted: data.total_transmitted(),
+            })
+            .collect()
+    }
+
+    fn processes(&mut self) -> Vec<ProcessInfo> {
+        // CPU使用率は2回の更新の差分から計算されるため間隔をあけて更新する
+        self.system.refresh_processes();
+        std::thread::sleep(Self::CPU_SAMPLE_INTERVAL);
+        self.system.refresh_processes();
+
+        let users = Users::new_with_refreshed_list();
+        self.system
+            .processes()
+            .values()
+            .map(|process| ProcessInfo {
+                pid: process.pid().as_u32(),
+                name: process.name().to_string(),
+                user: process
+                    .user_id()
+                    .and_then(|uid| users.get_user_by_id(uid))
+                    .map(|user| user.name().to_string()),
+                cpu_usage: process.cpu_usage(),
+                memory: process.memory(),
+            })
+            .collect()
+    }
+
+    fn environment(&mut self) -> EnvironmentInfo {
+        let detected = DETECTED_ENVIRONMENT.get_or_init(|| DetectedEnvironment {
+            info: EnvironmentService::detect(),
+            memory_usage_file: if cfg!(target_os = "linux") {
+                EnvironmentService::memory_usage_file(Path::new("/"))
+            } else {
+                None
+            },
+        });
+
+        // cgroup 内のメモリ使用量だけを読み直す
+        let mut info = detected.info.clone();
+        if let Some(file) = &detected.memory_usage_file {
+            info.memory_usage = EnvironmentService::read_memory_usage(file);
+        }
+        info
+    }
+
+    fn sockets(&mut self) -> Result<Vec<SocketInfo>, String> {
+        SocketService::list()
+    }
+}
+
+/// 使用量と総量から使用率（%）を計算する。総量が0の場合は0を返す
+pub fn usage_percent(used: u64, total: u64) -> f32 {
+    if total > 0 {
+        (used as f32 / total as f32) * 100.0
+    } else {
+        0.0
+    }
+}
+
 /// システム情報取得を担当するサービスクラス
 pub struct SystemService;
 
@@ -42,91 +285,122 @@ impl SystemService {
     
     /// システム情報を取得
     pub fn get_system_info() -> Result<SystemInfo, String> {
-        let mut sys = System::new_all();
-        sys.refresh_all();
+        Self::get_system_info_with(&mut SysinfoMetricsProvider::new())
+    }
+
+    /// 指定したプロバイダからシステム情報を組み立てる
+    pub fn get_system_info_with(provider: &mut impl MetricsProvider) -> Result<SystemInfo, String> {
+        let host = provider.host();
 
         // CPU情報
-        let cpu_brand = sys.global_cpu_info().brand().to_string();
-        let cpu_cores = sys.cpus().len();
-
-        // メモリ情報
-        let total_memory = sys.total_memory();
-        let used_memory = sys.used_memory();
-        let available_memory = sys.available_memory();
-        let memory_usage_percent = if total_memory > 0 {
-            (used_memory as f32 / total_memory as f32) * 100.0
-        } else {
-            0.0
-        };
+        let cpu_brand = provider.cpu_brand();
+        let cpu_cores = provider.cpu_core_count();
+
+        // メモリ情報（cgroup の上限があればそれを基準に使用率を計算）
+        let memory = provider.memory();
+        let environment = provider.environment();
+        let (effective_total, effective_used) = environment.effective_memory(memory.total, memory.used);
+        let memory_usage_percent = usage_percent(effective_used, effective_total);
 
         // ディスク情報
-        let disks_obj = Disks::new_with_refreshed_list();
-        let disks: Vec<DiskInfo> = disks_obj.iter().map(|disk| {
-            let total_space = disk.total_space();
-            let available_space = disk.available_space();
-            let used_space = total_space - available_space;
-            let usage_percent = if total_space > 0 {
-                (used_space as f32 / total_space as f32) * 100.0
-            } else {
-                0.0
-            };
+        let disks: Vec<DiskInfo> = provider.disks().into_iter().map(|disk| {
+            // 予約領域などで available が total を上回る場合に備えて飽和減算する
+            let used_space = disk.total_space.saturating_sub(disk.available_space);
 
             DiskInfo {
-                name: disk.name().to_string_lossy().to_string(),
-                mount_point: disk.mount_point().to_string_lossy().to_string(),
-                total_space,
-                available_space,
+                name: disk.name,
+                mount_point: disk.mount_point,
+                total_space: disk.total_space,
+                available_space: disk.available_space,
                 used_space,
-                usage_percent,
-                file_system: disk.file_system().to_string_lossy().to_string(),
+                usage_percent: usage_percent(used_space, disk.total_space),
+                file_system: disk.file_system,
             }
         }).collect();
 
+        let unknown = || "Unknown".to_string();
+
         Ok(SystemInfo {
-            os_name: System::name().unwrap_or_else(|| "Unknown".to_string()),
-            os_version: System::os_version().unwrap_or_else(|| "Unknown".to_string()),
-            kernel_version: System::kernel_version().unwrap_or_else(|| "Unknown".to_string()),
-            hostname: System::host_name().unwrap_or_else(|| "Unknown".to_string()),
+            os_name: host.os_name.unwrap_or_else(unknown),
+            os_version: host.os_version.unwrap_or_else(unknown),
+            kernel_version: host.kernel_version.unwrap_or_else(unknown),
+            hostname: host.hostname.unwrap_or_else(unknown),
             cpu_brand,
             cpu_cores,
-            total_memory,
-            used_memory,
-            available_memory,
+            total_memory: memory.total,
+            used_memory: memory.used,
+            available_memory: memory.available,
+            effective_total_memory: effective_total,
+            effective_used_memory: effective_used,
             memory_usage_percent,
-            uptime: System::uptime(),
+            uptime: host.uptime,
             disks,
+            environment,
         })
     }
 
+    /// ネットワークインターフェース一覧を名前順で取得
+    pub fn get_network_info_with(provider: &mut impl MetricsProvider) -> Vec<NetworkInfo> {
+        let mut networks = provider.networks();
+        networks.sort_by(|a, b| a.name.cmp(&b.name));
+        networks
+    }
+
+    /// メモリ使用量の多い順に上位のプロセスを取得
+    pub fn get_top_processes_with(provider: &mut impl MetricsProvider, limit: usize) -> Vec<ProcessInfo> {
+        let mut processes = provider.processes();
+        processes.sort_by(|a, b| {
+            b.memory
+                .cmp(&a.memory)
+                .then_with(|| b.cpu_usage.total_cmp(&a.cpu_usage))
+                .then_with(|| a.pid.cmp(&b.pid))
+        });
+        processes.truncate(limit);
+        processes
+    }
+
+    /// 条件に一致するソケット一覧を取得
+    pub fn list_sockets(filter: &SocketFilter) -> Result<Vec<SocketInfo>, String> {
+        Self::list_sockets_with(&mut SysinfoMetricsProvider::new(), filter)
+    }
+
+    /// 指定したプロバイダからソケット一覧を取得し、プロトコル・ポート順に並べる
+    pub fn list_sockets_with(provider: &mut impl MetricsProvider, filter: &SocketFilter) -> Result<Vec<SocketInfo>, String> {
+        let mut sockets: Vec<SocketInfo> = provider
+            .sockets()?
+            .into_iter()
+            .filter(|socket| filter.matches(socket))
+            .collect();
+        sockets.sort_by(|a, b| {
+            a.protocol
+                .cmp(&b.protocol)
+                .then_with(|| a.local_port.cmp(&b.local_port))
+                .then_with(|| a.remote_port.cmp(&b.remote_port))
+        });
+        Ok(sockets)
+    }
+
     /// リアルタイムメトリクス（CPU、メモリ使用率）を取得
     pub fn get_realtime_metrics() -> Result<RealTimeMetrics, String> {
-        let mut system = System::new_all();
-        
-        // CPU情報を更新（少し時間をおいて正確な使用率を取得）
-        system.refresh_cpu();
-        std::thread::sleep(std::time::Duration::from_millis(200));
-        system.refresh_cpu();
-        
-        // メモリ情報を更新
-        system.refresh_memory();
-        
+        Self::get_realtime_metrics_with(&mut SysinfoMetricsProvider::new())
+    }
+
+    /// 指定したプロバイダからリアルタイムメトリクスを取得
+    pub fn get_realtime_metrics_with(provider: &mut impl MetricsProvider) -> Result<RealTimeMetrics, String> {
         // CPUの使用率を計算（全コアの平均）
-        let cpu_usage = if system.cpus().is_empty() {
+        let cpu_samples = provider.sample_cpu_usage();
+        let cpu_usage = if cpu_samples.is_empty() {
             0.0
         } else {
-            system.cpus().iter()
-                .map(|cpu| cpu.cpu_usage())
-                .sum::<f32>() / system.cpus().len() as f32
+            cpu_samples.iter().sum::<f32>() / cpu_samples.len() as f32
         };
 
         // メモリ使用率を計算
-        let total_memory = system.total_memory();
-        let used_memory = system.used_memory();
-        let memory_usage = if total_memory > 0 {
-            (used_memory as f32 / total_memory as f32) * 100.0
-        } else {
-            0.0
-        };
+        let memory = provider.memory();
+        let (effective_total, effective_used) = provider
+            .environment()
+            .effective_memory(memory.total, memory.used);
+        let memory_usage = usage_percent(effective_used, effective_total);
 
         // 現在のタイムスタンプ（ミリ秒）
         let timestamp = std::time::SystemTime::now()
@@ -143,9 +417,104 @@ impl SystemService {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
+    /// 呼ばれるたびに次の値を返し、使い切った後は最後の値を返し続ける台本
+    #[derive(Default)]
+    pub(crate) struct Script<T> {
+        values: Vec<T>,
+        next: usize,
+    }
+
+    impl<T: Clone + Default> Script<T> {
+        pub(crate) fn new(values: Vec<T>) -> Self {
+            Self { values, next: 0 }
+        }
+
+        /// 次に返す値（進めない）
+        fn peek(&self) -> T {
+            self.values
+                .get(self.next.min(self.values.len().saturating_sub(1)))
+                .cloned()
+                .unwrap_or_default()
+        }
+
+        fn advance(&mut self) -> T {
+            let value = self.peek();
+            self.next += 1;
+            value
+        }
+    }
+
+    /// テスト用に決めた値を返す MetricsProvider
+    ///
+    /// CPU 使用率とメモリ使用量は呼ばれるたびに台本の次の値を返すため、連続したサンプリングを再現できる。
+    #[derive(Default)]
+    pub(crate) struct FakeMetricsProvider {
+        pub host: HostSnapshot,
+        pub cpu_brand: String,
+        pub cpu_samples: Script<Vec<f32>>,
+        pub memory: Script<MemorySnapshot>,
+        pub disks: Vec<DiskSnapshot>,
+        pub networks: Vec<NetworkInfo>,
+        pub processes: Vec<ProcessInfo>,
+        pub environment: EnvironmentInfo,
+        pub sockets: Vec<SocketInfo>,
+    }
+
+    impl MetricsProvider for FakeMetricsProvider {
+        fn host(&mut self) -> HostSnapshot {
+            self.host.clone()
+        }
+
+        fn cpu_brand(&mut self) -> String {
+            self.cpu_brand.clone()
+        }
+
+        fn cpu_core_count(&mut self) -> usize {
+            self.cpu_samples.peek().len()
+        }
+
+        fn sample_cpu_usage(&mut self) -> Vec<f32> {
+            self.cpu_samples.advance()
+        }
+
+        fn memory(&mut self) -> MemorySnapshot {
+            self.memory.advance()
+        }
+
+        fn disks(&mut self) -> Vec<DiskSnapshot> {
+            self.disks.clone()
+        }
+
+        fn networks(&mut self) -> Vec<NetworkInfo> {
+            self.networks.clone()
+        }
+
+        fn processes(&mut self) -> Vec<ProcessInfo> {
+            self.processes.clone()
+        }
+
+        fn environment(&mut self) -> EnvironmentInfo {
+            self.environment.clone()
+        }
+
+        fn sockets(&mut self) -> Result<Vec<SocketInfo>, String> {
+            Ok(self.sockets.clone())
+        }
+    }
+
+    pub(crate) fn process(pid: u32, name: &str, memory: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            user: Some("alice".to_string()),
+            cpu_usage: 0.0,
+            memory,
+        }
+    }
+
     #[test]
     fn test_get_system_info() {
         let result = SystemService::get_system_info();
@@ -156,4 +525,185 @@ mod tests {
         assert!(system_info.cpu_cores > 0);
         assert!(system_info.total_memory > 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_get_system_info_with_fake_provider() {
+        let mut provider = FakeMetricsProvider {
+            host: HostSnapshot {
+                os_name: Some("TestOS".to_string()),
+                os_version: None,
+                kernel_version: Some("6.0.0".to_string()),
+                hostname: Some("test-host".to_string()),
+                uptime: 3600,
+            },
+            cpu_brand: "Test CPU".to_string(),
+            cpu_samples: Script::new(vec![vec![0.0; 4]]),
+            memory: Script::new(vec![MemorySnapshot { total: 8_000, used: 2_000, available: 6_000 }]),
+            disks: vec![DiskSnapshot {
+                name: "sda1".to_string(),
+                mount_point: "/".to_string(),
+                total_space: 1_000,
+                available_space: 250,
+                file_system: "ext4".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let info = SystemService::get_system_info_with(&mut provider).unwrap();
+        assert_eq!(info.os_name, "TestOS");
+        assert_eq!(info.os_version, "Unknown");
+        assert_eq!(info.hostname, "test-host");
+        assert_eq!(info.cpu_brand, "Test CPU");
+        assert_eq!(info.cpu_cores, 4);
+        assert_eq!(info.memory_usage_percent, 25.0);
+        assert_eq!(info.uptime, 3600);
+        assert_eq!(info.disks.len(), 1);
+        assert_eq!(info.disks[0].used_space, 750);
+        assert_eq!(info.disks[0].usage_percent, 75.0);
+    }
+
+    #[test]
+    fn test_zero_totals_report_zero_percent() {
+        let mut provider = FakeMetricsProvider {
+            disks: vec![DiskSnapshot::default()],
+            ..Default::default()
+        };
+
+        let info = SystemService::get_system_info_with(&mut provider).unwrap();
+        assert_eq!(info.memory_usage_percent, 0.0);
+        assert_eq!(info.disks[0].usage_percent, 0.0);
+    }
+
+    #[test]
+    fn test_disk_available_exceeding_total_does_not_underflow() {
+        let mut provider = FakeMetricsProvider {
+            disks: vec![DiskSnapshot {
+                total_space: 100,
+                available_space: 150,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let info = SystemService::get_system_info_with(&mut provider).unwrap();
+        assert_eq!(info.disks[0].used_space, 0);
+        assert_eq!(info.disks[0].usage_percent, 0.0);
+    }
+
+    #[test]
+    fn test_memory_percent_uses_cgroup_limit() {
+        let mut provider = FakeMetricsProvider {
+            memory: Script::new(vec![MemorySnapshot { total: 16_000, used: 12_000, available: 4_000 }]),
+            environment: EnvironmentInfo {
+                container: Some("docker".to_string()),
+                memory_limit: Some(2_000),
+                memory_usage: Some(500),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let info = SystemService::get_system_info_with(&mut provider).unwrap();
+        assert_eq!(info.memory_usage_percent, 25.0);
+        assert_eq!(info.total_memory, 16_000);
+        assert_eq!(info.effective_total_memory, 2_000);
+        assert_eq!(info.effective_used_memory, 500);
+        assert_eq!(info.environment.container.as_deref(), Some("docker"));
+
+        let metrics = SystemService::get_realtime_metrics_with(&mut provider).unwrap();
+        assert_eq!(metrics.memory_usage, 25.0);
+    }
+
+    #[test]
+    fn test_top_processes_sorted_by_memory_and_truncated() {
+        let mut provider = FakeMetricsProvider {
+            processes: vec![
+                process(1, "small", 10),
+                process(2, "large", 300),
+                process(3, "medium", 200),
+            ],
+            ..Default::default()
+        };
+
+        let top = SystemService::get_top_processes_with(&mut provider, 2);
+        let names: Vec<&str> = top.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["large", "medium"]);
+    }
+
+    #[test]
+    fn test_list_sockets_filters_and_sorts() {
+        use crate::socket_service::SocketProtocol;
+
+        let socket = |protocol, local_port, state: &str| SocketInfo {
+            protocol,
+            local_address: "127.0.0.1".to_string(),
+            local_port,
+            remote_address: "0.0.0.0".to_string(),
+            remote_port: 0,
+            state: state.to_string(),
+            uid: 1000,
+            inode: local_port as u64,
+            pid: None,
+            process_name: None,
+        };
+        let mut provider = FakeMetricsProvider {
+            sockets: vec![
+                socket(SocketProtocol::Udp, 53, "UNCONN"),
+                socket(SocketProtocol::Tcp, 5173, "LISTEN"),
+                socket(SocketProtocol::Tcp, 3000, "LISTEN"),
+                socket(SocketProtocol::Tcp, 40000, "ESTABLISHED"),
+            ],
+            ..Default::default()
+        };
+
+        let filter = SocketFilter { listening_only: Some(true), ..Default::default() };
+        let sockets = SystemService::list_sockets_with(&mut provider, &filter).unwrap();
+        let ports: Vec<u16> = sockets.iter().map(|s| s.local_port).collect();
+        assert_eq!(ports, vec![3000, 5173, 53]);
+    }
+
+    #[test]
+    fn test_realtime_metrics_averages_cores() {
+        let mut provider = FakeMetricsProvider {
+            cpu_samples: Script::new(vec![vec![10.0, 30.0, 50.0, 70.0]]),
+            memory: Script::new(vec![MemorySnapshot { total: 4_000, used: 1_000, available: 3_000 }]),
+            ..Default::default()
+        };
+
+        let metrics = SystemService::get_realtime_metrics_with(&mut provider).unwrap();
+        assert_eq!(metrics.cpu_usage, 40.0);
+        assert_eq!(metrics.memory_usage, 25.0);
+        assert!(metrics.timestamp > 0);
+    }
+
+    #[test]
+    fn test_realtime_metrics_follow_successive_samples() {
+        let memory = |used| MemorySnapshot { total: 4_000, used, available: 4_000 - used };
+        let mut provider = FakeMetricsProvider {
+            cpu_samples: Script::new(vec![vec![10.0, 30.0], vec![60.0, 80.0], vec![20.0, 20.0]]),
+            memory: Script::new(vec![memory(1_000), memory(3_000), memory(2_000)]),
+            ..Default::default()
+        };
+
+        let samples: Vec<RealTimeMetrics> = (0..4)
+            .map(|_| SystemService::get_realtime_metrics_with(&mut provider).unwrap())
+            .collect();
+        let cpu_changes: Vec<f32> = samples.windows(2).map(|pair| pair[1].cpu_usage - pair[0].cpu_usage).collect();
+        let memory_changes: Vec<f32> = samples.windows(2).map(|pair| pair[1].memory_usage - pair[0].memory_usage).collect();
+
+        assert_eq!(samples[0].cpu_usage, 20.0);
+        assert_eq!(cpu_changes, vec![50.0, -50.0, 0.0]);
+        assert_eq!(samples[0].memory_usage, 25.0);
+        assert_eq!(memory_changes, vec![50.0, -25.0, 0.0]);
+        assert!(samples.windows(2).all(|pair| pair[1].timestamp >= pair[0].timestamp));
+    }
+
+    #[test]
+    fn test_realtime_metrics_without_cpus() {
+        let mut provider = FakeMetricsProvider::default();
+
+        let metrics = SystemService::get_realtime_metrics_with(&mut provider).unwrap();
+        assert_eq!(metrics.cpu_usage, 0.0);
+        assert_eq!(metrics.memory_usage, 0.0);
+    }
+}
@@ -0,0 +1,40 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 一時ファイル名を重複させないための連番
+static TEMPORARY_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 保存先と同じディレクトリに置く、書き込み途中の一時ファイルのパス
+///
+/// 書き終えてから保存先へ名前を変えることで、読みかけのファイルを見せない。名前にはプロセス ID と
+/// プロセス内の連番を入れるため、同じ保存先への書き込みが並行しても一時ファイルは衝突しない。
+pub fn temp_path_for(destination: &Path) -> PathBuf {
+    let name = destination
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    destination.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        name,
+        std::process::id(),
+        TEMPORARY_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_paths_are_unique_and_beside_destination() {
+        let destination = Path::new("/data/photo.png");
+        let first = temp_path_for(destination);
+        let second = temp_path_for(destination);
+
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), destination.parent());
+        let name = first.file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.starts_with(".photo.png."));
+        assert!(name.ends_with(".tmp"));
+    }
+}
@@ -0,0 +1,346 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use image::ImageFormat;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{Emitter, Manager};
+use crate::temp_path::temp_path_for;
+
+/// サムネイルの長辺のピクセル数
+pub const THUMBNAIL_SIZE: u32 = 256;
+
+/// キャッシュの上限サイズ（バイト）
+const DEFAULT_CACHE_LIMIT: u64 = 200 * 1024 * 1024;
+
+/// サムネイル生成完了時に送るイベント名
+pub const THUMBNAIL_READY_EVENT: &str = "thumbnail-ready";
+
+/// サムネイルを生成できる拡張子
+const SUPPORTED_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "webp", "bmp"];
+
+/// サムネイル生成結果（1ファイルごとにイベントで送る）
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ThumbnailReady {
+    pub path: String,
+    pub thumbnail_path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// キャッシュ削除の結果
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct CacheEvictionResult {
+    pub removed_files: usize,
+    pub freed_bytes: u64,
+    pub remaining_bytes: u64,
+}
+
+/// サムネイル生成とディスクキャッシュを担当するサービスクラス
+pub struct ThumbnailService {
+    cache_dir: PathBuf,
+    cache_limit: u64,
+}
+
+impl ThumbnailService {
+    /// アプリのキャッシュディレクトリ配下にサムネイルキャッシュを作成
+    pub fn new(app_handle: &tauri::AppHandle) -> Result<Self, String> {
+        let cache_dir = app_handle
+            .path()
+            .app_cache_dir()
+            .map_err(|e| format!("キャッシュディレクトリの取得に失敗しました: {}", e))?
+            .join("thumbnails");
+        Self::with_cache_dir(cache_dir, DEFAULT_CACHE_LIMIT)
+    }
+
+    /// キャッシュディレクトリと上限サイズを指定して作成
+    pub fn with_cache_dir(cache_dir: PathBuf, cache_limit: u64) -> Result<Self, String> {
+        fs::create_dir_all(&cache_dir)
+            .map_err(|e| format!("ディレクトリの作成に失敗しました: {}", e))?;
+        Ok(Self { cache_dir, cache_limit })
+    }
+
+    /// キャッシュディレクトリのパス
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// サムネイルを生成できるファイルか（拡張子で判定）
+    pub fn is_supported(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            .unwrap_or(false)
+    }
+
+    /// パス・更新日時・サイズからキャッシュキーを作る
+    ///
+    /// 元ファイルが更新されるとキーが変わるため、古いサムネイルは LRU で自然に消える。
+    pub fn cache_key(path: &Path, modified: Option<SystemTime>, size: u64) -> String {
+        let modified = modified
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_nanos())
+            .unwrap_or(0);
+
+        let mut hasher = Sha256::new();
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(modified.to_le_bytes());
+        hasher.update(size.to_le_bytes());
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// キャッシュ済みならそのパスを、なければ生成してパスを返す
+    pub fn get_or_create(&self, path: &Path) -> Result<PathBuf, String> {
+        if !Self::is_supported(path) {
+            return Err("サポートされていないファイル形式です".to_string());
+        }
+
+        let metadata = fs::metadata(path)
+            .map_err(|e| format!("ファイル情報の取得に失敗しました: {}", e))?;
+        let key = Self::cache_key(path, metadata.modified().ok(), metadata.len());
+        let thumbnail_path = self.cache_dir.join(format!("{}.png", key));
+
+        if thumbnail_path.exists() {
+            // LRU 判定のため、使われたサムネイルの更新日時を進める
+            if let Ok(file) = File::options().append(true).open(&thumbnail_path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+            return Ok(thumbnail_path);
+        }
+
+        Self::generate(path, &thumbnail_path)?;
+        Ok(thumbnail_path)
+    }
+
+    /// 画像をデコードしてサムネイルPNGを書き出す
+    fn generate(source: &Path, destination: &Path) -> Result<(), String> {
+        let image = image::open(source)
+            .map_err(|e| format!("画像のデコードに失敗しました: {}", e))?;
+        let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+
+        // 並列生成中に読みかけのファイルを返さないよう、一時ファイルに書いてから置き換える
+        let temporary = temp_path_for(destination);
+        let result = thumbnail
+            .save_with_format(&temporary, ImageFormat::Png)
+            .map_err(|e| format!("サムネイルの保存に失敗しました: {}", e))
+            .and_then(|_| {
+                fs::rename(&temporary, destination)
+                    .map_err(|e| format!("サムネイルの保存に失敗しました: {}", e))
+            });
+        if result.is_err() {
+            let _ = fs::remove_file(&temporary);
+        }
+        result
+    }
+
+    /// 複数ファイルのサムネイルを並列に生成し、1件ごとにコールバックを呼ぶ
+    pub fn generate_all<F>(&self, paths: &[PathBuf], on_ready: F)
+    where
+        F: Fn(ThumbnailReady) + Sync,
+    {
+        paths.par_iter().for_each(|path| {
+            let result = self.get_or_create(path);
+            on_ready(ThumbnailReady {
+                path: path.to_string_lossy().to_string(),
+                thumbnail_path: result.as_ref().ok().map(|p| p.to_string_lossy().to_string()),
+                error: result.err(),
+            });
+        });
+    }
+
+    /// 上限サイズを超えた分を、最後に使われた日時の古い順に削除
+    pub fn evict(&self) -> Result<CacheEvictionResult, String> {
+        self.evict_to(self.cache_limit)
+    }
+
+    /// キャッシュを空にする
+    pub fn clear(&self) -> Result<CacheEvictionResult, String> {
+        self.evict_to(0)
+    }
+
+    fn evict_to(&self, limit: u64) -> Result<CacheEvictionResult, String> {
+        let read_dir = fs::read_dir(&self.cache_dir)
+            .map_err(|e| format!("ディレクトリの読み込みに失敗しました: {}", e))?;
+
+        let mut entries: Vec<(PathBuf, u64, SystemTime)> = read_dir
+            .flatten()
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                if !metadata.is_file() {
+                    return None;
+                }
+                Some((entry.path(), metadata.len(), metadata.modified().unwrap_or(UNIX_EPOCH)))
+            })
+            .collect();
+        entries.sort_by_key(|(_, _, modified)| *modified);
+
+        let mut result = CacheEvictionResult {
+            remaining_bytes: entries.iter().map(|(_, size, _)| size).sum(),
+            ..Default::default()
+        };
+        for (path, size, _) in entries {
+            if result.remaining_bytes <= limit {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                result.removed_files += 1;
+                result.freed_bytes += size;
+                result.remaining_bytes -= size;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// バックグラウンドでサムネイルを生成し、完了ごとにイベントを送る
+    ///
+    /// 要求ごとにスレッドを作らず rayon のスレッドプールで実行するため、要求が続いても
+    /// 同時に動く生成処理は CPU 数までに抑えられる。
+    pub fn spawn_generation(app: tauri::AppHandle, paths: Vec<PathBuf>) {
+        rayon::spawn(move || {
+            let service = app.state::<ThumbnailService>();
+            service.generate_all(&paths, |ready| {
+                let _ = app.emit(THUMBNAIL_READY_EVENT, ready);
+            });
+            let _ = service.evict();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    fn write_image(path: &Path, width: u32, height: u32, format: ImageFormat) {
+        RgbImage::from_pixel(width, height, Rgb([200, 100, 50]))
+            .save_with_format(path, format)
+            .unwrap();
+    }
+
+    fn setup(limit: u64) -> (ThumbnailService, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let service = ThumbnailService::with_cache_dir(temp_dir.path().join("cache"), limit).unwrap();
+        (service, temp_dir)
+    }
+
+    #[test]
+    fn test_generates_thumbnail_keeping_aspect_ratio() {
+        let (service, temp_dir) = setup(DEFAULT_CACHE_LIMIT);
+        let source = temp_dir.path().join("wide.jpg");
+        write_image(&source, 1024, 512, ImageFormat::Jpeg);
+
+        let thumbnail = service.get_or_create(&source).unwrap();
+        assert!(thumbnail.starts_with(service.cache_dir()));
+        let decoded = image::open(&thumbnail).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (THUMBNAIL_SIZE, THUMBNAIL_SIZE / 2));
+    }
+
+    #[test]
+    fn test_cache_hit_and_invalidation_on_change() {
+        let (service, temp_dir) = setup(DEFAULT_CACHE_LIMIT);
+        let source = temp_dir.path().join("photo.png");
+        write_image(&source, 300, 300, ImageFormat::Png);
+
+        let first = service.get_or_create(&source).unwrap();
+        let second = service.get_or_create(&source).unwrap();
+        assert_eq!(first, second);
+
+        // サイズが変わればキーも変わる
+        write_image(&source, 400, 300, ImageFormat::Png);
+        let third = service.get_or_create(&source).unwrap();
+        assert_ne!(first, third);
+    }
+
+    #[test]
+    fn test_cache_key_depends_on_path_mtime_and_size() {
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(1_000);
+        let base = ThumbnailService::cache_key(Path::new("/a.png"), Some(time), 10);
+        assert_eq!(base, ThumbnailService::cache_key(Path::new("/a.png"), Some(time), 10));
+        assert_ne!(base, ThumbnailService::cache_key(Path::new("/b.png"), Some(time), 10));
+        assert_ne!(base, ThumbnailService::cache_key(Path::new("/a.png"), None, 10));
+        assert_ne!(base, ThumbnailService::cache_key(Path::new("/a.png"), Some(time), 11));
+    }
+
+    #[test]
+    fn test_unsupported_and_corrupt_files() {
+        let (service, temp_dir) = setup(DEFAULT_CACHE_LIMIT);
+        let text = temp_dir.path().join("notes.txt");
+        fs::write(&text, "hello").unwrap();
+        assert_eq!(service.get_or_create(&text).unwrap_err(), "サポートされていないファイル形式です");
+
+        let corrupt = temp_dir.path().join("broken.png");
+        fs::write(&corrupt, b"not a png").unwrap();
+        assert!(service.get_or_create(&corrupt).unwrap_err().starts_with("画像のデコードに失敗しました"));
+    }
+
+    #[test]
+    fn test_failed_save_removes_temporary_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("photo.png");
+        write_image(&source, 40, 20, ImageFormat::Png);
+        // 保存先が空でないディレクトリだと置き換えに失敗する
+        let out = temp_dir.path().join("out");
+        let destination = out.join("thumb.png");
+        fs::create_dir_all(destination.join("occupied")).unwrap();
+
+        assert!(ThumbnailService::generate(&source, &destination).is_err());
+        assert_eq!(fs::read_dir(&out).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_generate_all_reports_each_file() {
+        let (service, temp_dir) = setup(DEFAULT_CACHE_LIMIT);
+        let formats = [
+            ("a.png", ImageFormat::Png),
+            ("b.gif", ImageFormat::Gif),
+            ("c.bmp", ImageFormat::Bmp),
+            ("d.webp", ImageFormat::WebP),
+        ];
+        let mut paths = Vec::new();
+        for (name, format) in formats {
+            let path = temp_dir.path().join(name);
+            write_image(&path, 64, 64, format);
+            paths.push(path);
+        }
+        paths.push(temp_dir.path().join("missing.png"));
+
+        let results = Mutex::new(Vec::new());
+        service.generate_all(&paths, |ready| results.lock().unwrap().push(ready));
+
+        let results = results.into_inner().unwrap();
+        assert_eq!(results.len(), 5);
+        assert_eq!(results.iter().filter(|r| r.thumbnail_path.is_some()).count(), 4);
+        let missing = results.iter().find(|r| r.path.ends_with("missing.png")).unwrap();
+        assert!(missing.error.is_some());
+    }
+
+    #[test]
+    fn test_evict_removes_least_recently_used_first() {
+        let (service, _temp_dir) = setup(250);
+        let base = SystemTime::now();
+        for (index, name) in ["old", "middle", "new"].iter().enumerate() {
+            let path = service.cache_dir().join(format!("{}.png", name));
+            fs::write(&path, vec![0u8; 100]).unwrap();
+            let file = File::options().append(true).open(&path).unwrap();
+            file.set_modified(base - std::time::Duration::from_secs(100 - index as u64 * 10)).unwrap();
+        }
+
+        let result = service.evict().unwrap();
+        assert_eq!(result.removed_files, 1);
+        assert_eq!(result.freed_bytes, 100);
+        assert_eq!(result.remaining_bytes, 200);
+        assert!(!service.cache_dir().join("old.png").exists());
+        assert!(service.cache_dir().join("new.png").exists());
+
+        let cleared = service.clear().unwrap();
+        assert_eq!(cleared.remaining_bytes, 0);
+    }
+}
@@ -0,0 +1,31 @@
+use std::path::Path;
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+
+/// 検索の集計結果
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SearchSummary {
+    pub matched: usize,
+    pub scanned: usize,
+    /// バイナリやサイズ上限超過で読まなかったファイル数（内容検索のみ）
+    pub skipped: usize,
+    pub cancelled: bool,
+    /// max_results に達して打ち切った
+    pub truncated: bool,
+}
+
+/// 隠しファイル・ignore ファイル・深さの設定を反映した走査器を作成
+pub fn build_walker(root: &Path, include_hidden: bool, respect_gitignore: bool, max_depth: Option<usize>) -> ignore::Walk {
+    WalkBuilder::new(root)
+        .hidden(!include_hidden)
+        .ignore(respect_gitignore)
+        .git_ignore(respect_gitignore)
+        .git_global(respect_gitignore)
+        .git_exclude(respect_gitignore)
+        .parents(respect_gitignore)
+        // Git リポジトリ外でも .gitignore を有効にする
+        .require_git(false)
+        .follow_links(false)
+        .max_depth(max_depth)
+        .build()
+}
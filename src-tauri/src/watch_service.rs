@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::time::Duration;
+use notify_debouncer_full::notify::event::{ModifyKind, RenameMode};
+use notify_debouncer_full::notify::{Event, EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
+use serde::{Deserialize, Serialize};
+
+/// ディレクトリ変更時に送るイベント名
+pub const DIRECTORY_CHANGED_EVENT: &str = "directory-changed";
+
+/// 短時間に連続した変更をまとめる待ち時間
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// 変更の種類
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Create,
+    Modify,
+    Remove,
+    Rename,
+}
+
+/// 1件の変更。Rename の場合は paths が [変更前, 変更後] になる
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FileChange {
+    pub kind: ChangeKind,
+    pub paths: Vec<String>,
+}
+
+/// directory-changed イベントの内容
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DirectoryChanged {
+    /// 監視しているディレクトリ
+    pub path: String,
+    pub changes: Vec<FileChange>,
+}
+
+/// 変更通知の送り先。購読しているウィンドウのラベルと変更内容を受け取る
+type ChangeSink = Arc<dyn Fn(&[String], DirectoryChanged) + Send + Sync>;
+
+/// 監視中のディレクトリ一覧（ウォッチャーのコールバックからも購読者を参照する）
+type Watches = Mutex<HashMap<PathBuf, WatchEntry>>;
+
+/// 監視中のディレクトリ1件分
+struct WatchEntry {
+    /// 保持している間だけ監視が続く
+    _debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+    /// ウィンドウごとの購読数
+    subscribers: HashMap<String, usize>,
+}
+
+/// ディレクトリの変更を監視し、購読しているウィンドウに通知するサービスクラス
+///
+/// 同じディレクトリへの購読は1つのウォッチャーを共有し、購読数が0になったら停止する。
+pub struct WatchService {
+    watches: Arc<Watches>,
+    sink: ChangeSink,
+}
+
+impl WatchService {
+    /// 変更をまとめ、購読しているウィンドウのラベルとともに `sink` に渡すインスタンスを作成
+    pub fn new<F>(sink: F) -> Self
+    where
+        F: Fn(&[String], DirectoryChanged) + Send + Sync + 'static,
+    {
+        Self {
+            watches: Arc::new(Mutex::new(HashMap::new())),
+            sink: Arc::new(sink),
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<PathBuf, WatchEntry>>, String> {
+        self.watches
+            .lock()
+            .map_err(|e| format!("監視状態のロックに失敗しました: {}", e))
+    }
+
+    /// ウィンドウからディレクトリを購読し、そのディレクトリの購読総数を返す
+    ///
+    /// 最初の購読でウォッチャーを作成する。監視は直下のみ（再帰しない）。
+    pub fn subscribe(&self, window: &str, dir: &Path) -> Result<usize, String> {
+        if !dir.is_dir() {
+            return Err("ディレクトリが見つかりません".to_string());
+        }
+
+        let mut watches = self.lock()?;
+        if !watches.contains_key(dir) {
+            let entry = WatchEntry {
+                _debouncer: self.create_watcher(dir)?,
+                subscribers: HashMap::new(),
+            };
+            watches.insert(dir.to_path_buf(), entry);
+        }
+
+        let entry = watches.get_mut(dir).expect("直前に登録済み");
+        *entry.subscribers.entry(window.to_string()).or_insert(0) += 1;
+        Ok(entry.subscribers.values().sum())
+    }
+
+    /// 購読を1つ解除する。購読していなかった場合は false
+    pub fn unsubscribe(&self, window: &str, dir: &Path) -> Result<bool, String> {
+        let mut watches = self.lock()?;
+        let Some(entry) = watches.get_mut(dir) else {
+            return Ok(false);
+        };
+        let Some(count) = entry.subscribers.get_mut(window) else {
+            return Ok(false);
+        };
+
+        *count -= 1;
+        if *count == 0 {
+            entry.subscribers.remove(window);
+        }
+        if entry.subscribers.is_empty() {
+            // ウォッチャーは破棄と同時に停止する
+            watches.remove(dir);
+        }
+        Ok(true)
+    }
+
+    /// ウィンドウが閉じられたときに、そのウィンドウの購読をすべて解除する
+    pub fn unsubscribe_window(&self, window: &str) -> Result<usize, String> {
+        let mut watches = self.lock()?;
+        let mut removed = 0;
+        watches.retain(|_, entry| {
+            removed += entry.subscribers.remove(window).unwrap_or(0);
+            !entry.subscribers.is_empty()
+        });
+        Ok(removed)
+    }
+
+    /// 監視中のディレクトリ一覧
+    pub fn watched_directories(&self) -> Result<Vec<String>, String> {
+        let mut paths: Vec<String> = self
+            .lock()?
+            .keys()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// ディレクトリのウォッチャーを作成
+    fn create_watcher(&self, dir: &Path) -> Result<Debouncer<RecommendedWatcher, RecommendedCache>, String> {
+        let sink = self.sink.clone();
+        // ウォッチャー自身が一覧に含まれるため、循環参照にならないよう弱参照で持つ
+        let watches: Weak<Watches> = Arc::downgrade(&self.watches);
+        let watched = dir.to_path_buf();
+        let root = dir.to_string_lossy().to_string();
+        let mut debouncer = new_debouncer(DEBOUNCE_TIMEOUT, None, move |result: DebounceEventResult| {
+            // 監視エラーは通知できないため無視し、次の変更を待つ
+            let Ok(events) = result else { return };
+            let changes: Vec<FileChange> = events.iter().filter_map(|event| classify(event)).collect();
+            if changes.is_empty() {
+                return;
+            }
+
+            // 通知時点で購読しているウィンドウだけに送る
+            let windows: Vec<String> = watches
+                .upgrade()
+                .and_then(|watches| {
+                    let watches = watches.lock().ok()?;
+                    Some(watches.get(&watched)?.subscribers.keys().cloned().collect())
+                })
+                .unwrap_or_default();
+            if !windows.is_empty() {
+                sink(&windows, DirectoryChanged { path: root.clone(), changes });
+            }
+        })
+        .map_err(|e| format!("監視の開始に失敗しました: {}", e))?;
+
+        debouncer
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("監視の開始に失敗しました: {}", e))?;
+        Ok(debouncer)
+    }
+}
+
+/// notify のイベントを作成・変更・削除・名前変更に分類（アクセスなどは None）
+pub fn classify(event: &Event) -> Option<FileChange> {
+    let kind = match event.kind {
+        EventKind::Create(_) => ChangeKind::Create,
+        EventKind::Remove(_) => ChangeKind::Remove,
+        EventKind::Modify(ModifyKind::Name(mode)) => match mode {
+            // 監視範囲の外へ移動したものは削除、外から来たものは作成として扱う
+            RenameMode::From => ChangeKind::Remove,
+            RenameMode::To => ChangeKind::Create,
+            _ => ChangeKind::Rename,
+        },
+        EventKind::Modify(_) => ChangeKind::Modify,
+        EventKind::Access(_) | EventKind::Any | EventKind::Other => return None,
+    };
+
+    Some(FileChange {
+        kind,
+        paths: event
+            .paths
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify_debouncer_full::notify::event::{AccessKind, CreateKind, DataChange};
+    use std::fs;
+    use std::sync::mpsc;
+    use tempfile::TempDir;
+
+    fn event(kind: EventKind, paths: &[&str]) -> Event {
+        paths
+            .iter()
+            .fold(Event::new(kind), |event, path| event.add_path(PathBuf::from(path)))
+    }
+
+    fn silent() -> WatchService {
+        WatchService::new(|_, _| {})
+    }
+
+    #[test]
+    fn test_classify_events() {
+        let create = classify(&event(EventKind::Create(CreateKind::File), &["/a"])).unwrap();
+        assert_eq!(create.kind, ChangeKind::Create);
+
+        let modify = classify(&event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &["/a"])).unwrap();
+        assert_eq!(modify.kind, ChangeKind::Modify);
+
+        let rename = classify(&event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["/a", "/b"])).unwrap();
+        assert_eq!(rename, FileChange { kind: ChangeKind::Rename, paths: vec!["/a".into(), "/b".into()] });
+
+        let moved_out = classify(&event(EventKind::Modify(ModifyKind::Name(RenameMode::From)), &["/a"])).unwrap();
+        assert_eq!(moved_out.kind, ChangeKind::Remove);
+
+        assert!(classify(&event(EventKind::Access(AccessKind::Read), &["/a"])).is_none());
+    }
+
+    #[test]
+    fn test_subscriptions_are_reference_counted() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let service = silent();
+
+        assert_eq!(service.subscribe("main", dir).unwrap(), 1);
+        assert_eq!(service.subscribe("main", dir).unwrap(), 2);
+        assert_eq!(service.subscribe("viewer", dir).unwrap(), 3);
+        assert_eq!(service.watched_directories().unwrap().len(), 1);
+
+        assert!(service.unsubscribe("main", dir).unwrap());
+        assert!(service.unsubscribe("viewer", dir).unwrap());
+        assert!(!service.unsubscribe("viewer", dir).unwrap());
+        assert_eq!(service.watched_directories().unwrap().len(), 1);
+
+        assert!(service.unsubscribe("main", dir).unwrap());
+        assert!(service.watched_directories().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unsubscribe_window_cleans_up() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        let service = silent();
+
+        service.subscribe("main", first.path()).unwrap();
+        service.subscribe("main", first.path()).unwrap();
+        service.subscribe("main", second.path()).unwrap();
+        service.subscribe("other", second.path()).unwrap();
+
+        assert_eq!(service.unsubscribe_window("main").unwrap(), 3);
+        assert_eq!(
+            service.watched_directories().unwrap(),
+            vec![second.path().to_string_lossy().to_string()]
+        );
+    }
+
+    #[test]
+    fn test_subscribe_nonexistent_directory() {
+        let result = silent().subscribe("main", Path::new("/nonexistent/directory"));
+        assert_eq!(result.unwrap_err(), "ディレクトリが見つかりません");
+    }
+
+    #[test]
+    fn test_reports_debounced_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().canonicalize().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let service = WatchService::new(move |windows: &[String], changed| {
+            let _ = sender.send((windows.to_vec(), changed));
+        });
+        service.subscribe("main", &dir).unwrap();
+
+        let file = dir.join("new.txt");
+        fs::write(&file, "hello").unwrap();
+
+        let (windows, changed) = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(windows, vec!["main".to_string()]);
+        assert_eq!(changed.path, dir.to_string_lossy());
+        assert!(changed
+            .changes
+            .iter()
+            .any(|change| change.kind == ChangeKind::Create && change.paths == vec![file.to_string_lossy().to_string()]));
+    }
+}
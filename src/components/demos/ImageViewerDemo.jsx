@@ -1,5 +1,5 @@
 import React, { useState } from "react";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke, convertFileSrc } from "@tauri-apps/api/core";
 
 function ImageViewerDemo() {
     const [selectedImage, setSelectedImage] = useState(null);
@@ -11,14 +11,11 @@ function ImageViewerDemo() {
             const selected = await invoke("select_image_file");
 
             if (selected) {
-                // 画像ファイルを読み込んでBase64で取得
-                const imageData = await invoke("read_image_file", {
-                    filePath: selected,
-                });
-
+                // localimg:// スキーム経由で画像を直接読み込む
                 setSelectedImage({
                     path: selected,
-                    data: imageData,
+                    src: convertFileSrc(selected, "localimg"),
+                    rendered: false,
                 });
             }
         } catch (error) {
@@ -28,6 +25,22 @@ function ImageViewerDemo() {
         }
     };
 
+    // WebView で表示できない形式（TIFF など）は PNG に変換して表示する
+    const showRendered = async (image, target) => {
+        try {
+            const rendered = await invoke("render_image", { filePath: image.path });
+            setSelectedImage({
+                ...image,
+                src: `data:image/png;base64,${rendered.data}`,
+                rendered: true,
+            });
+        } catch (error) {
+            console.error("画像変換エラー:", error);
+            target.style.display = "none";
+            target.nextSibling.style.display = "block";
+        }
+    };
+
     return (
         <div id="demo-display">
             <div className="demo-content">
@@ -47,7 +60,7 @@ function ImageViewerDemo() {
                         <div className="image-display">
                             <div className="image-placeholder">
                                 <p>🖼️ 画像ファイルを選択してください</p>
-                                <p>対応形式: PNG, JPG, JPEG, GIF, BMP, WebP</p>
+                                <p>対応形式: PNG, JPG, JPEG, GIF, BMP, WebP, SVG, TIFF, ICO</p>
                             </div>
                         </div>
                     )}
@@ -55,10 +68,14 @@ function ImageViewerDemo() {
                     {selectedImage && (
                         <div className="image-display has-image">
                             <img
-                                src={`data:image/*;base64,${selectedImage.data}`}
+                                src={selectedImage.src}
                                 alt="選択された画像"
                                 style={{ maxWidth: "100%", maxHeight: "400px" }}
                                 onError={(e) => {
+                                    if (!selectedImage.rendered) {
+                                        showRendered(selectedImage, e.target);
+                                        return;
+                                    }
                                     e.target.style.display = "none";
                                     e.target.nextSibling.style.display =
                                         "block";